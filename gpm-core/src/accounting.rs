@@ -0,0 +1,570 @@
+use crate::config::EnergyConfig;
+use crate::gpu::GpuMetrics;
+use crate::ollama::{LlmSession, SessionHardware};
+use crate::tracker::MAX_SAMPLE_GAP_SECS;
+use chrono::{Datelike, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsagePeriod {
+    #[default]
+    Day,
+    /// ISO weeks, starting on Monday
+    Week,
+}
+
+impl UsagePeriod {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+        }
+    }
+
+    /// First day of the period containing `date`.
+    pub fn start_of(&self, date: chrono::NaiveDate) -> chrono::NaiveDate {
+        match self {
+            Self::Day => date,
+            Self::Week => date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64),
+        }
+    }
+
+    /// SQLite expression mapping `column` to the first day of its period.
+    pub(crate) fn bucket_sql(&self, column: &str) -> String {
+        match self {
+            Self::Day => format!("DATE({})", column),
+            Self::Week => format!("DATE({}, 'weekday 0', '-6 days')", column),
+        }
+    }
+}
+
+/// GPU usage of one user over one day or week.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserUsage {
+    /// Username, or the numeric UID if it could not be resolved
+    pub user: String,
+    pub uid: Option<u32>,
+    pub period_start: chrono::NaiveDate,
+    pub gpu_hours: f64,
+    /// Largest VRAM footprint of any single process of the user
+    pub peak_gpu_memory_mb: u64,
+    pub energy_wh: f64,
+    pub sample_count: u64,
+}
+
+/// Each process's share of the GPU (in `metrics.processes` order) by VRAM,
+/// or an even split if none reports memory. Used to attribute board power,
+/// and utilization when the driver has no per-process samples.
+pub fn vram_shares(metrics: &GpuMetrics) -> Vec<f64> {
+    let total_memory: u64 = metrics.processes.iter().map(|p| p.used_gpu_memory).sum();
+
+    metrics
+        .processes
+        .iter()
+        .map(|p| {
+            if total_memory > 0 {
+                p.used_gpu_memory as f64 / total_memory as f64
+            } else {
+                1.0 / metrics.processes.len() as f64
+            }
+        })
+        .collect()
+}
+
+/// Turns successive samples of each GPU into the energy used between them.
+#[derive(Default)]
+pub struct EnergyMeter {
+    last: HashMap<String, (chrono::DateTime<chrono::Utc>, u32, Option<u64>)>,
+}
+
+impl EnergyMeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wh used by the GPU since its previous sample: the difference of the
+    /// driver's energy counter when both samples have one, else the
+    /// trapezoid of the two power readings. `None` for the first sample.
+    pub fn observe(&mut self, metrics: &GpuMetrics) -> Option<f64> {
+        let counter = metrics.telemetry.total_energy_mj;
+        let (last_time, last_power, last_counter) = self
+            .last
+            .insert(metrics.stable_id(), (metrics.timestamp, metrics.power_usage, counter))?;
+
+        // The counter restarts when the driver is reloaded
+        if let (Some(previous), Some(current)) = (last_counter, counter) {
+            if current >= previous {
+                return Some((current - previous) as f64 / 3_600_000.0);
+            }
+        }
+
+        let secs = (metrics.timestamp - last_time).num_milliseconds() as f64 / 1000.0;
+        if secs <= 0.0 || secs > MAX_SAMPLE_GAP_SECS as f64 {
+            return Some(0.0);
+        }
+
+        Some((last_power + metrics.power_usage) as f64 / 2.0 * secs / 3600.0)
+    }
+}
+
+/// Price per kWh at `hour` (0-23, local time) under `config`'s tariff.
+pub fn price_per_kwh(config: &EnergyConfig, hour: u32) -> f64 {
+    config
+        .time_of_use
+        .iter()
+        .find(|w| {
+            if w.start_hour <= w.end_hour {
+                (w.start_hour..w.end_hour).contains(&hour)
+            } else {
+                hour >= w.start_hour || hour < w.end_hour
+            }
+        })
+        .map_or(config.price_per_kwh, |w| w.price_per_kwh)
+}
+
+/// Energy attributed to something (a GPU, category or model) at one time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnergySample {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub key: String,
+    pub energy_wh: f64,
+}
+
+/// Energy and cost of one GPU, category or model over one day or week.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnergyUsage {
+    /// GPU UUID (or index), workload category, or LLM model
+    pub key: String,
+    /// Local date, like the tariff hours
+    pub period_start: chrono::NaiveDate,
+    pub energy_wh: f64,
+    /// In `EnergyReport::currency`, at the tariff in force when it was used
+    pub cost: f64,
+    pub co2_grams: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnergyReport {
+    pub period: UsagePeriod,
+    pub currency: String,
+    pub gpus: Vec<EnergyUsage>,
+    /// Process energy is their share of board power, so categories need not
+    /// add up to the GPU totals
+    pub categories: Vec<EnergyUsage>,
+    /// Energy of all GPUs while each model's sessions ran, split evenly
+    /// between concurrent sessions
+    pub llm_models: Vec<EnergyUsage>,
+}
+
+/// Build an energy report from GPU samples (with `energy_wh` filled in),
+/// per-category process energy and the LLM sessions in the same range.
+pub fn energy_report(
+    gpu_metrics: &[GpuMetrics],
+    category_energy: &[EnergySample],
+    sessions: &[LlmSession],
+    config: &EnergyConfig,
+    period: UsagePeriod,
+) -> EnergyReport {
+    let mut gpu_energy: Vec<EnergySample> = gpu_metrics
+        .iter()
+        .filter_map(|m| {
+            Some(EnergySample {
+                timestamp: m.timestamp,
+                key: m.stable_id(),
+                energy_wh: m.energy_wh?,
+            })
+        })
+        .collect();
+    gpu_energy.sort_by_key(|s| s.timestamp);
+
+    let llm_energy = session_energy(&gpu_energy, sessions);
+
+    EnergyReport {
+        period,
+        currency: config.currency.clone(),
+        gpus: summarize(&gpu_energy, config, period),
+        categories: summarize(category_energy, config, period),
+        llm_models: summarize(&llm_energy, config, period),
+    }
+}
+
+/// GPU energy during each finished session, keyed by model. `gpu_energy`
+/// must be sorted by time.
+fn session_energy(gpu_energy: &[EnergySample], sessions: &[LlmSession]) -> Vec<EnergySample> {
+    let windows: Vec<_> = sessions
+        .iter()
+        .filter_map(|s| Some((s.start_time, s.end_time?, s.model.as_str())))
+        .collect();
+
+    let range = |start, end| {
+        gpu_energy.partition_point(|s| s.timestamp < start)..gpu_energy.partition_point(|s| s.timestamp <= end)
+    };
+
+    let mut concurrent = vec![0u32; gpu_energy.len()];
+    for (start, end, _) in &windows {
+        for i in range(*start, *end) {
+            concurrent[i] += 1;
+        }
+    }
+
+    windows
+        .iter()
+        .flat_map(|(start, end, model)| {
+            range(*start, *end).map(|i| EnergySample {
+                timestamp: gpu_energy[i].timestamp,
+                key: model.to_string(),
+                energy_wh: gpu_energy[i].energy_wh / concurrent[i] as f64,
+            })
+        })
+        .collect()
+}
+
+/// GPU cost of a finished session from `samples` of the GPUs hosting it,
+/// which must be sorted by time and may extend past the session's end.
+/// A GPU with no sample during a session shorter than the poll interval is
+/// represented by its first sample after the end. `None` if the session has
+/// not ended or no GPU has a sample.
+pub fn session_hardware(session: &LlmSession, samples: &[GpuMetrics]) -> Option<SessionHardware> {
+    let end = session.end_time?;
+
+    let mut by_gpu: BTreeMap<String, Vec<&GpuMetrics>> = BTreeMap::new();
+    for m in samples.iter().filter(|m| m.timestamp >= session.start_time) {
+        let gpu = by_gpu.entry(m.stable_id()).or_default();
+        if m.timestamp <= end || gpu.is_empty() {
+            gpu.push(m);
+        }
+    }
+    if by_gpu.is_empty() {
+        return None;
+    }
+
+    let avg_power_watts: f64 = by_gpu
+        .values()
+        .map(|gpu| mean(gpu.iter().map(|m| m.power_usage as f64)))
+        .sum();
+    let peak_memory: u64 = by_gpu
+        .values()
+        .map(|gpu| gpu.iter().map(|m| m.memory_used).max().unwrap_or(0))
+        .sum();
+    let avg_gpu_utilization = mean(by_gpu.values().flatten().map(|m| m.utilization_gpu as f64));
+
+    let energy_joules = avg_power_watts * (end - session.start_time).num_milliseconds() as f64 / 1000.0;
+
+    Some(SessionHardware {
+        energy_joules,
+        joules_per_token: (session.total_tokens > 0).then(|| energy_joules / session.total_tokens as f64),
+        avg_power_watts,
+        peak_gpu_memory_mb: peak_memory / (1024 * 1024),
+        avg_gpu_utilization,
+    })
+}
+
+/// Efficiency of one model across the sessions that have hardware data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelEfficiency {
+    pub model: String,
+    pub session_count: u64,
+    pub total_tokens: u64,
+    pub avg_tokens_per_second: f64,
+    pub energy_joules: f64,
+    pub joules_per_token: Option<f64>,
+    pub avg_power_watts: f64,
+    pub peak_gpu_memory_mb: u64,
+    pub avg_gpu_utilization: f64,
+    /// Electricity cost per million tokens, in `EnergyConfig::currency`
+    pub cost_per_million_tokens: Option<f64>,
+}
+
+/// Per-model efficiency, most energy-efficient first.
+pub fn model_efficiency(sessions: &[LlmSession], config: &EnergyConfig) -> Vec<ModelEfficiency> {
+    let mut by_model: BTreeMap<&str, Vec<(&LlmSession, &SessionHardware)>> = BTreeMap::new();
+    for s in sessions {
+        if let Some(hardware) = &s.hardware {
+            by_model.entry(s.model.as_str()).or_default().push((s, hardware));
+        }
+    }
+
+    let mut models: Vec<_> = by_model
+        .into_iter()
+        .map(|(model, sessions)| {
+            let count = sessions.len() as f64;
+            let total_tokens: u64 = sessions.iter().map(|(s, _)| s.total_tokens).sum();
+            let energy_joules: f64 = sessions.iter().map(|(_, h)| h.energy_joules).sum();
+            let cost: f64 = sessions
+                .iter()
+                .map(|(s, h)| {
+                    let hour = s.start_time.with_timezone(&chrono::Local).hour();
+                    h.energy_joules / 3_600_000.0 * price_per_kwh(config, hour)
+                })
+                .sum();
+
+            ModelEfficiency {
+                model: model.to_string(),
+                session_count: sessions.len() as u64,
+                total_tokens,
+                avg_tokens_per_second: sessions.iter().map(|(s, _)| s.tokens_per_second).sum::<f64>() / count,
+                energy_joules,
+                joules_per_token: (total_tokens > 0).then(|| energy_joules / total_tokens as f64),
+                avg_power_watts: sessions.iter().map(|(_, h)| h.avg_power_watts).sum::<f64>() / count,
+                peak_gpu_memory_mb: sessions.iter().map(|(_, h)| h.peak_gpu_memory_mb).max().unwrap_or(0),
+                avg_gpu_utilization: sessions.iter().map(|(_, h)| h.avg_gpu_utilization).sum::<f64>() / count,
+                cost_per_million_tokens: (total_tokens > 0).then(|| cost / total_tokens as f64 * 1e6),
+            }
+        })
+        .collect();
+
+    models.sort_by(|a, b| {
+        a.joules_per_token
+            .unwrap_or(f64::INFINITY)
+            .total_cmp(&b.joules_per_token.unwrap_or(f64::INFINITY))
+    });
+    models
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), v| (sum + v, count + 1));
+    sum / count as f64
+}
+
+fn summarize(samples: &[EnergySample], config: &EnergyConfig, period: UsagePeriod) -> Vec<EnergyUsage> {
+    summarize_in(samples, config, period, &chrono::Local)
+}
+
+/// Days start at midnight in `tz`, the clock the tariff hours are read in,
+/// so a day's energy and its cost cover the same window.
+fn summarize_in<Tz: chrono::TimeZone>(
+    samples: &[EnergySample],
+    config: &EnergyConfig,
+    period: UsagePeriod,
+    tz: &Tz,
+) -> Vec<EnergyUsage> {
+    let mut totals: BTreeMap<(chrono::NaiveDate, &str), (f64, f64)> = BTreeMap::new();
+
+    for s in samples {
+        let local = s.timestamp.with_timezone(tz);
+        let hour = local.hour();
+        let total = totals
+            .entry((period.start_of(local.date_naive()), s.key.as_str()))
+            .or_default();
+        total.0 += s.energy_wh;
+        total.1 += s.energy_wh / 1000.0 * price_per_kwh(config, hour);
+    }
+
+    totals
+        .into_iter()
+        .map(|((period_start, key), (energy_wh, cost))| EnergyUsage {
+            key: key.to_string(),
+            period_start,
+            energy_wh,
+            cost,
+            co2_grams: config.carbon_intensity_g_per_kwh.map(|g| energy_wh / 1000.0 * g),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu::GpuProcess;
+
+    #[test]
+    fn test_vram_shares() {
+        let process = |pid, used_gpu_memory| GpuProcess {
+            pid,
+            name: String::new(),
+            used_gpu_memory,
+            utilization: None,
+            mig_device: None,
+        };
+        let mut metrics = GpuMetrics {
+            timestamp: chrono::Utc::now(),
+            gpu_id: 0,
+            name: "Test GPU".to_string(),
+            utilization_gpu: 90,
+            utilization_memory: 40,
+            memory_used: 4 << 30,
+            memory_total: 8 << 30,
+            temperature: 70,
+            power_usage: 200,
+            processes: vec![process(1, 3 << 30), process(2, 1 << 30)],
+            telemetry: Default::default(),
+            identity: Default::default(),
+            energy_wh: None,
+            mig_devices: Vec::new(),
+        };
+        assert_eq!(vram_shares(&metrics), vec![0.75, 0.25]);
+
+        metrics.processes = vec![process(1, 0), process(2, 0)];
+        assert_eq!(vram_shares(&metrics), vec![0.5, 0.5]);
+    }
+
+    fn sample(seconds: i64, power_usage: u32, total_energy_mj: Option<u64>) -> GpuMetrics {
+        GpuMetrics {
+            timestamp: chrono::DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap(),
+            gpu_id: 0,
+            name: "Test GPU".to_string(),
+            utilization_gpu: 50,
+            utilization_memory: 10,
+            memory_used: 1 << 30,
+            memory_total: 8 << 30,
+            temperature: 60,
+            power_usage,
+            processes: Vec::new(),
+            telemetry: crate::gpu::GpuTelemetry {
+                total_energy_mj,
+                ..Default::default()
+            },
+            identity: Default::default(),
+            energy_wh: None,
+            mig_devices: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_energy_meter() {
+        let mut meter = EnergyMeter::new();
+
+        assert_eq!(meter.observe(&sample(0, 100, None)), None);
+        // Trapezoid: (100 + 300) / 2 W for 36 s = 2 Wh
+        assert_eq!(meter.observe(&sample(36, 300, None)), Some(2.0));
+        // Too long a gap to interpolate
+        assert_eq!(meter.observe(&sample(3600, 300, Some(1_000_000))), Some(0.0));
+        // The counter wins over power: 7.2 kJ = 2 Wh
+        assert_eq!(meter.observe(&sample(3602, 300, Some(8_200_000))), Some(2.0));
+    }
+
+    #[test]
+    fn test_time_of_use_price() {
+        let config = EnergyConfig {
+            price_per_kwh: 0.30,
+            time_of_use: vec![crate::config::TariffWindow {
+                start_hour: 22,
+                end_hour: 6,
+                price_per_kwh: 0.10,
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(price_per_kwh(&config, 23), 0.10);
+        assert_eq!(price_per_kwh(&config, 5), 0.10);
+        assert_eq!(price_per_kwh(&config, 6), 0.30);
+        assert_eq!(price_per_kwh(&EnergyConfig::default(), 12), 0.0);
+    }
+
+    #[test]
+    fn test_energy_report() {
+        let mut metrics: Vec<_> = (0..4).map(|i| sample(i * 60, 200, None)).collect();
+        for m in &mut metrics {
+            m.energy_wh = Some(10.0);
+        }
+
+        let session = |model: &str, start: i64, end: i64| LlmSession {
+            id: model.to_string(),
+            start_time: metrics[0].timestamp + chrono::Duration::seconds(start),
+            end_time: Some(metrics[0].timestamp + chrono::Duration::seconds(end)),
+            model: model.to_string(),
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            total_tokens: 0,
+            tokens_per_second: 0.0,
+            time_to_first_token_ms: None,
+            time_per_output_token_ms: None,
+            hardware: None,
+            api_flavor: Default::default(),
+            abandoned: false,
+        };
+        // Both cover the sample at 60 s; only llama covers 120 s
+        let sessions = vec![session("llama", 30, 150), session("qwen", 50, 70)];
+
+        let config = EnergyConfig {
+            price_per_kwh: 0.5,
+            carbon_intensity_g_per_kwh: Some(400.0),
+            ..Default::default()
+        };
+        let report = energy_report(&metrics, &[], &sessions, &config, UsagePeriod::Day);
+
+        assert_eq!(report.gpus.len(), 1);
+        assert_eq!(report.gpus[0].energy_wh, 40.0);
+        assert!((report.gpus[0].cost - 0.02).abs() < 1e-9);
+        assert_eq!(report.gpus[0].co2_grams, Some(16.0));
+
+        let energy = |model: &str| report.llm_models.iter().find(|u| u.key == model).unwrap().energy_wh;
+        assert_eq!(energy("llama"), 15.0);
+        assert_eq!(energy("qwen"), 5.0);
+    }
+
+    #[test]
+    fn test_days_follow_tariff_clock() {
+        let config = EnergyConfig {
+            price_per_kwh: 0.3,
+            time_of_use: vec![crate::config::TariffWindow {
+                start_hour: 0,
+                end_hour: 6,
+                price_per_kwh: 0.1,
+            }],
+            ..Default::default()
+        };
+        // 2023-11-14 20:00 UTC is 05:00 on the 15th at UTC+9
+        let sample = EnergySample {
+            timestamp: chrono::DateTime::from_timestamp(1_699_992_000, 0).unwrap(),
+            key: "GPU-0".to_string(),
+            energy_wh: 1000.0,
+        };
+        let tz = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
+
+        let usage = summarize_in(&[sample], &config, UsagePeriod::Day, &tz);
+        assert_eq!(usage[0].period_start, chrono::NaiveDate::from_ymd_opt(2023, 11, 15).unwrap());
+        assert!((usage[0].cost - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_session_hardware_and_efficiency() {
+        let mut samples: Vec<_> = (0..4).map(|i| sample(i * 10, 100 + i as u32 * 100, None)).collect();
+        samples[1].memory_used = 3 << 30;
+        samples[1].utilization_gpu = 90;
+
+        let mut session = LlmSession {
+            id: "s1".to_string(),
+            start_time: samples[0].timestamp + chrono::Duration::seconds(5),
+            end_time: Some(samples[0].timestamp + chrono::Duration::seconds(25)),
+            model: "llama3".to_string(),
+            prompt_tokens: 100,
+            completion_tokens: 300,
+            total_tokens: 400,
+            tokens_per_second: 20.0,
+            time_to_first_token_ms: None,
+            time_per_output_token_ms: None,
+            hardware: None,
+            api_flavor: Default::default(),
+            abandoned: false,
+        };
+
+        // Samples at 10 s and 20 s: 250 W for 20 s
+        let hardware = session_hardware(&session, &samples).unwrap();
+        assert_eq!(hardware.avg_power_watts, 250.0);
+        assert_eq!(hardware.energy_joules, 5000.0);
+        assert_eq!(hardware.joules_per_token, Some(12.5));
+        assert_eq!(hardware.peak_gpu_memory_mb, 3072);
+        assert_eq!(hardware.avg_gpu_utilization, 70.0);
+
+        // Shorter than the poll interval: falls back to the next sample
+        let mut short = session.clone();
+        short.start_time = samples[0].timestamp + chrono::Duration::seconds(11);
+        short.end_time = Some(samples[0].timestamp + chrono::Duration::seconds(13));
+        assert_eq!(session_hardware(&short, &samples).unwrap().avg_power_watts, 300.0);
+
+        session.hardware = Some(hardware);
+        let config = EnergyConfig {
+            price_per_kwh: 0.36,
+            ..Default::default()
+        };
+        let efficiency = model_efficiency(&[session.clone(), LlmSession { hardware: None, ..session }], &config);
+        assert_eq!(efficiency.len(), 1);
+        assert_eq!(efficiency[0].session_count, 1);
+        assert_eq!(efficiency[0].joules_per_token, Some(12.5));
+        // 5 kJ at 0.36/kWh = 0.0005 for 400 tokens
+        assert!((efficiency[0].cost_per_million_tokens.unwrap() - 1.25).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,588 @@
+pub mod notify;
+
+use crate::config::AlertConfig;
+use crate::error::Result;
+use crate::gpu::{DeviceEvent, DeviceEventKind, GpuEvent, GpuEventKind, GpuMetrics};
+use notify::AlertNotifier;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    Info,
+    #[default]
+    Warning,
+    Critical,
+}
+
+impl AlertSeverity {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Critical => "critical",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "info" => Some(Self::Info),
+            "warning" => Some(Self::Warning),
+            "critical" => Some(Self::Critical),
+            _ => None,
+        }
+    }
+}
+
+/// The per-GPU value a rule watches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    Temperature,
+    MemoryPercent,
+    Utilization,
+    PowerWatts,
+    /// Whether the GPU is enumerated at all; used by device lost/added alerts
+    Availability,
+    /// A driver-reported error (Xid, double-bit ECC); the value is the Xid
+    GpuEvent,
+}
+
+impl AlertMetric {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Temperature => "temperature",
+            Self::MemoryPercent => "memory_percent",
+            Self::Utilization => "utilization",
+            Self::PowerWatts => "power_watts",
+            Self::Availability => "availability",
+            Self::GpuEvent => "gpu_event",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "temperature" => Some(Self::Temperature),
+            "memory_percent" => Some(Self::MemoryPercent),
+            "utilization" => Some(Self::Utilization),
+            "power_watts" => Some(Self::PowerWatts),
+            "availability" => Some(Self::Availability),
+            "gpu_event" => Some(Self::GpuEvent),
+            _ => None,
+        }
+    }
+
+    pub fn value(&self, metrics: &GpuMetrics) -> f64 {
+        match self {
+            Self::Temperature => metrics.temperature as f64,
+            Self::MemoryPercent => {
+                if metrics.memory_total > 0 {
+                    metrics.memory_used as f64 / metrics.memory_total as f64 * 100.0
+                } else {
+                    0.0
+                }
+            }
+            Self::Utilization => metrics.utilization_gpu as f64,
+            Self::PowerWatts => metrics.power_usage as f64,
+            // A sample exists, so the GPU is there
+            Self::Availability => 1.0,
+            // Driver errors arrive as events, not samples
+            Self::GpuEvent => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertState {
+    Firing,
+    Resolved,
+}
+
+impl AlertState {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Firing => "firing",
+            Self::Resolved => "resolved",
+        }
+    }
+}
+
+/// Fires once `metric` has stayed at or above `threshold` for `duration_secs`,
+/// and resolves when it drops below `threshold - hysteresis`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub name: String,
+    pub metric: AlertMetric,
+    pub threshold: f64,
+
+    #[serde(default)]
+    pub hysteresis: f64,
+
+    #[serde(default)]
+    pub duration_secs: u64,
+
+    #[serde(default)]
+    pub severity: AlertSeverity,
+}
+
+impl AlertRule {
+    fn clear_threshold(&self) -> f64 {
+        self.threshold - self.hysteresis.max(0.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    pub id: String,
+    pub rule: String,
+    pub metric: AlertMetric,
+    pub severity: AlertSeverity,
+    pub state: AlertState,
+    pub gpu_id: u32,
+    pub gpu_name: String,
+    pub threshold: f64,
+    pub value: f64,
+    pub peak_value: f64,
+    pub message: String,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub resolved_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Clone)]
+enum RuleState {
+    Pending { since: chrono::DateTime<chrono::Utc> },
+    Firing(Alert),
+}
+
+/// Evaluates GPU samples against alert rules and tracks the firing/resolved
+/// state of each (rule, GPU) pair between samples.
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    states: HashMap<(usize, u32), RuleState>,
+    /// Firing `gpu_lost` alerts by UUID (or index when there is none)
+    lost_devices: HashMap<String, Alert>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            rules,
+            states: HashMap::new(),
+            lost_devices: HashMap::new(),
+        }
+    }
+
+    /// Built-in temperature and memory rules from the `[alerts]` thresholds,
+    /// followed by any extra rules from the config file.
+    pub fn from_config(config: &AlertConfig) -> Self {
+        let mut rules = vec![
+            AlertRule {
+                name: "gpu_temperature_high".to_string(),
+                metric: AlertMetric::Temperature,
+                threshold: config.temp_threshold_celsius,
+                hysteresis: config.temp_hysteresis_celsius,
+                duration_secs: config.duration_secs,
+                severity: AlertSeverity::Critical,
+            },
+            AlertRule {
+                name: "gpu_memory_high".to_string(),
+                metric: AlertMetric::MemoryPercent,
+                threshold: config.memory_threshold_percent,
+                hysteresis: config.memory_hysteresis_percent,
+                duration_secs: config.duration_secs,
+                severity: AlertSeverity::Warning,
+            },
+        ];
+
+        rules.extend(config.rules.iter().cloned());
+
+        Self::new(rules)
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    /// Evaluate one sample and return every alert whose state changed:
+    /// newly firing alerts and alerts that just resolved.
+    pub fn evaluate(&mut self, metrics: &GpuMetrics) -> Vec<Alert> {
+        let mut changed = Vec::new();
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            let key = (index, metrics.gpu_id);
+            let value = rule.metric.value(metrics);
+            let now = metrics.timestamp;
+
+            match self.states.get_mut(&key) {
+                None => {
+                    if value >= rule.threshold {
+                        if rule.duration_secs == 0 {
+                            let alert = Self::fire(rule, metrics, value, now);
+                            changed.push(alert.clone());
+                            self.states.insert(key, RuleState::Firing(alert));
+                        } else {
+                            self.states.insert(key, RuleState::Pending { since: now });
+                        }
+                    }
+                }
+                Some(RuleState::Pending { since }) => {
+                    if value < rule.threshold {
+                        self.states.remove(&key);
+                    } else if (now - *since).num_seconds() >= rule.duration_secs as i64 {
+                        let alert = Self::fire(rule, metrics, value, *since);
+                        changed.push(alert.clone());
+                        self.states.insert(key, RuleState::Firing(alert));
+                    }
+                }
+                Some(RuleState::Firing(alert)) => {
+                    alert.value = value;
+                    alert.peak_value = alert.peak_value.max(value);
+
+                    if value < rule.clear_threshold() {
+                        alert.state = AlertState::Resolved;
+                        alert.resolved_at = Some(now);
+
+                        info!(
+                            "Alert resolved: {} on GPU {} ({} = {:.1})",
+                            rule.name, metrics.gpu_id, rule.metric.as_str(), value
+                        );
+
+                        changed.push(alert.clone());
+                        self.states.remove(&key);
+                    }
+                }
+            }
+        }
+
+        changed
+    }
+
+    fn fire(
+        rule: &AlertRule,
+        metrics: &GpuMetrics,
+        value: f64,
+        started_at: chrono::DateTime<chrono::Utc>,
+    ) -> Alert {
+        let message = format!(
+            "GPU {} ({}) {} is {:.1}, threshold {:.1}",
+            metrics.gpu_id,
+            metrics.name,
+            rule.metric.as_str(),
+            value,
+            rule.threshold
+        );
+
+        warn!("Alert firing: {}: {}", rule.name, message);
+
+        Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            rule: rule.name.clone(),
+            metric: rule.metric,
+            severity: rule.severity,
+            state: AlertState::Firing,
+            gpu_id: metrics.gpu_id,
+            gpu_name: metrics.name.clone(),
+            threshold: rule.threshold,
+            value,
+            peak_value: value,
+            message,
+            started_at,
+            resolved_at: None,
+        }
+    }
+
+    /// Turn a device event into alerts: a removed GPU fires a critical
+    /// `gpu_lost` alert that resolves when the GPU returns, and a GPU that
+    /// was not seen before produces a one-off `gpu_added` info alert, stored
+    /// already resolved.
+    pub fn device_event(&mut self, event: &DeviceEvent) -> Vec<Alert> {
+        let key = event.uuid.clone().unwrap_or_else(|| event.gpu_id.to_string());
+
+        match event.kind {
+            DeviceEventKind::Removed => {
+                if self.lost_devices.contains_key(&key) {
+                    return Vec::new();
+                }
+
+                let reason = event.detail.as_deref().unwrap_or("no longer enumerated");
+                let alert = Self::device_alert(
+                    "gpu_lost",
+                    AlertSeverity::Critical,
+                    event,
+                    0.0,
+                    format!("GPU {} ({}) was lost: {}", event.gpu_id, event.name, reason),
+                );
+
+                warn!("Alert firing: {}: {}", alert.rule, alert.message);
+                self.lost_devices.insert(key, alert.clone());
+                vec![alert]
+            }
+            DeviceEventKind::Added => match self.lost_devices.remove(&key) {
+                Some(mut alert) => {
+                    alert.state = AlertState::Resolved;
+                    alert.value = 1.0;
+                    alert.resolved_at = Some(event.timestamp);
+
+                    info!("Alert resolved: gpu_lost on GPU {} ({})", event.gpu_id, event.name);
+                    vec![alert]
+                }
+                None => {
+                    let mut alert = Self::device_alert(
+                        "gpu_added",
+                        AlertSeverity::Info,
+                        event,
+                        1.0,
+                        format!("GPU {} ({}) was added", event.gpu_id, event.name),
+                    );
+                    alert.state = AlertState::Resolved;
+                    alert.resolved_at = Some(event.timestamp);
+                    vec![alert]
+                }
+            },
+        }
+    }
+
+    /// A one-off alert, stored already resolved, for an Xid or double-bit
+    /// ECC error. `None` for state changes, which are only recorded.
+    pub fn gpu_event_alert(event: &GpuEvent, gpu_name: &str) -> Option<Alert> {
+        if !event.is_error() {
+            return None;
+        }
+
+        let rule = match event.kind {
+            GpuEventKind::Xid => "gpu_xid",
+            _ => "gpu_ecc_double_bit",
+        };
+        let severity = if event.is_critical() { AlertSeverity::Critical } else { AlertSeverity::Warning };
+        let gpu_id = event.gpu_id.unwrap_or_default();
+
+        let mut message = format!("GPU {} ({}): {}", gpu_id, gpu_name, event.message);
+        if !event.pids.is_empty() {
+            let pids: Vec<_> = event.pids.iter().map(u32::to_string).collect();
+            message.push_str(&format!(" (pids {})", pids.join(", ")));
+        }
+
+        warn!("Alert: {}: {}", rule, message);
+
+        let value = event.xid.unwrap_or_default() as f64;
+        Some(Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            rule: rule.to_string(),
+            metric: AlertMetric::GpuEvent,
+            severity,
+            state: AlertState::Resolved,
+            gpu_id,
+            gpu_name: gpu_name.to_string(),
+            threshold: 0.0,
+            value,
+            peak_value: value,
+            message,
+            started_at: event.timestamp,
+            resolved_at: Some(event.timestamp),
+        })
+    }
+
+    fn device_alert(rule: &str, severity: AlertSeverity, event: &DeviceEvent, value: f64, message: String) -> Alert {
+        Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            rule: rule.to_string(),
+            metric: AlertMetric::Availability,
+            severity,
+            state: AlertState::Firing,
+            gpu_id: event.gpu_id,
+            gpu_name: event.name.clone(),
+            threshold: 1.0,
+            value,
+            peak_value: value,
+            message,
+            started_at: event.timestamp,
+            resolved_at: None,
+        }
+    }
+
+    pub fn active_alerts(&self) -> Vec<Alert> {
+        self.states
+            .values()
+            .filter_map(|s| match s {
+                RuleState::Firing(alert) => Some(alert.clone()),
+                RuleState::Pending { .. } => None,
+            })
+            .chain(self.lost_devices.values().cloned())
+            .collect()
+    }
+}
+
+/// Pairs the rule engine with the notification sinks so every state
+/// change the engine reports is delivered without blocking collection.
+pub struct AlertManager {
+    engine: RwLock<AlertEngine>,
+    notifier: Arc<AlertNotifier>,
+}
+
+impl AlertManager {
+    pub fn new(engine: AlertEngine, notifier: AlertNotifier) -> Self {
+        Self {
+            engine: RwLock::new(engine),
+            notifier: Arc::new(notifier),
+        }
+    }
+
+    pub fn from_config(config: &AlertConfig) -> Result<Self> {
+        Ok(Self::new(
+            AlertEngine::from_config(config),
+            AlertNotifier::from_config(config)?,
+        ))
+    }
+
+    /// Evaluate one sample, dispatch notifications for changed alerts in the
+    /// background, and return the changed alerts for persistence.
+    pub async fn process(&self, metrics: &GpuMetrics) -> Vec<Alert> {
+        let changed = self.engine.write().await.evaluate(metrics);
+        self.dispatch(&changed);
+        changed
+    }
+
+    /// Like `process`, for a GPU being added or removed.
+    pub async fn process_device_event(&self, event: &DeviceEvent) -> Vec<Alert> {
+        let changed = self.engine.write().await.device_event(event);
+        self.dispatch(&changed);
+        changed
+    }
+
+    /// Alert on a driver error event; see `AlertEngine::gpu_event_alert`.
+    pub fn process_gpu_event(&self, event: &GpuEvent, gpu_name: &str) -> Option<Alert> {
+        let alert = AlertEngine::gpu_event_alert(event, gpu_name)?;
+        self.dispatch(std::slice::from_ref(&alert));
+        Some(alert)
+    }
+
+    fn dispatch(&self, changed: &[Alert]) {
+        if !self.notifier.is_empty() {
+            for alert in changed {
+                let notifier = Arc::clone(&self.notifier);
+                let alert = alert.clone();
+                tokio::spawn(async move {
+                    notifier.notify(&alert).await;
+                });
+            }
+        }
+    }
+
+    pub async fn active_alerts(&self) -> Vec<Alert> {
+        self.engine.read().await.active_alerts()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(seconds: i64, temperature: u32) -> GpuMetrics {
+        GpuMetrics {
+            timestamp: chrono::DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap(),
+            gpu_id: 0,
+            name: "NVIDIA GeForce RTX 3080".to_string(),
+            utilization_gpu: 90,
+            utilization_memory: 30,
+            memory_used: 1024,
+            memory_total: 10240,
+            temperature,
+            power_usage: 250,
+            processes: Vec::new(),
+            telemetry: Default::default(),
+            identity: Default::default(),
+            energy_wh: None,
+            mig_devices: Vec::new(),
+        }
+    }
+
+    fn temp_engine() -> AlertEngine {
+        AlertEngine::new(vec![AlertRule {
+            name: "gpu_temperature_high".to_string(),
+            metric: AlertMetric::Temperature,
+            threshold: 85.0,
+            hysteresis: 5.0,
+            duration_secs: 10,
+            severity: AlertSeverity::Critical,
+        }])
+    }
+
+    #[test]
+    fn test_alert_requires_duration_window() {
+        let mut engine = temp_engine();
+
+        assert!(engine.evaluate(&sample(0, 90)).is_empty());
+        assert!(engine.evaluate(&sample(5, 90)).is_empty());
+        // Dipping below the threshold restarts the window
+        assert!(engine.evaluate(&sample(6, 80)).is_empty());
+        assert!(engine.evaluate(&sample(12, 90)).is_empty());
+
+        let fired = engine.evaluate(&sample(22, 91));
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].state, AlertState::Firing);
+        assert_eq!(fired[0].started_at, sample(12, 0).timestamp);
+        assert_eq!(engine.active_alerts().len(), 1);
+    }
+
+    #[test]
+    fn test_alert_resolves_with_hysteresis() {
+        let mut engine = temp_engine();
+
+        engine.evaluate(&sample(0, 90));
+        assert_eq!(engine.evaluate(&sample(10, 95)).len(), 1);
+
+        // Below threshold but within hysteresis band: still firing
+        assert!(engine.evaluate(&sample(12, 82)).is_empty());
+        assert_eq!(engine.active_alerts().len(), 1);
+
+        let resolved = engine.evaluate(&sample(14, 79));
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].state, AlertState::Resolved);
+        assert_eq!(resolved[0].peak_value, 95.0);
+        assert!(resolved[0].resolved_at.is_some());
+        assert!(engine.active_alerts().is_empty());
+    }
+
+    #[test]
+    fn test_device_lost_and_recovered() {
+        let mut engine = temp_engine();
+        let event = |kind| DeviceEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: sample(0, 0).timestamp,
+            kind,
+            gpu_id: 1,
+            uuid: Some("GPU-b".to_string()),
+            name: "NVIDIA GeForce RTX 3080".to_string(),
+            pci_bus_id: None,
+            detail: Some("GPU is lost".to_string()),
+        };
+
+        let added = engine.device_event(&event(DeviceEventKind::Added));
+        assert_eq!(added[0].rule, "gpu_added");
+        assert_eq!(added[0].state, AlertState::Resolved);
+        assert!(engine.active_alerts().is_empty());
+
+        let lost = engine.device_event(&event(DeviceEventKind::Removed));
+        assert_eq!(lost[0].severity, AlertSeverity::Critical);
+        assert!(lost[0].message.contains("GPU is lost"));
+        assert!(engine.device_event(&event(DeviceEventKind::Removed)).is_empty());
+        assert_eq!(engine.active_alerts().len(), 1);
+
+        let back = engine.device_event(&event(DeviceEventKind::Added));
+        assert_eq!(back[0].id, lost[0].id);
+        assert_eq!(back[0].state, AlertState::Resolved);
+        assert!(engine.active_alerts().is_empty());
+    }
+
+    #[test]
+    fn test_default_rules_from_config() {
+        let engine = AlertEngine::from_config(&crate::GpmConfig::default().alerts);
+        let metrics: Vec<_> = engine.rules().iter().map(|r| r.metric).collect();
+        assert_eq!(metrics, vec![AlertMetric::Temperature, AlertMetric::MemoryPercent]);
+    }
+}
@@ -0,0 +1,487 @@
+use super::{Alert, AlertSeverity, AlertState};
+use crate::config::AlertConfig;
+use crate::error::{GpmError, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SinkKind {
+    /// Generic JSON webhook: `{"event": "...", "alert": {...}}`
+    Webhook,
+    /// Slack incoming webhook (`{"text": "..."}`)
+    Slack,
+    /// Discord webhook (`{"content": "...", "embeds": [...]}`)
+    Discord,
+    /// freedesktop.org notification, sent to `org.freedesktop.Notifications`
+    /// on a session D-Bus
+    Desktop,
+}
+
+impl SinkKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Webhook => "webhook",
+            Self::Slack => "slack",
+            Self::Discord => "discord",
+            Self::Desktop => "desktop",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationSinkConfig {
+    #[serde(rename = "type")]
+    pub kind: SinkKind,
+
+    #[serde(default)]
+    pub url: Option<String>,
+
+    /// Severities routed to this sink; empty means all.
+    #[serde(default)]
+    pub severities: Vec<AlertSeverity>,
+
+    #[serde(default = "default_notify_resolved")]
+    pub notify_resolved: bool,
+
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+
+    /// Desktop sinks: the session bus to notify on, e.g.
+    /// `unix:path=/run/user/1000/bus`. A system service has no session bus of
+    /// its own, so this defaults to `DBUS_SESSION_BUS_ADDRESS` only when set.
+    #[serde(default)]
+    pub dbus_address: Option<String>,
+}
+
+impl NotificationSinkConfig {
+    fn desktop() -> Self {
+        Self {
+            kind: SinkKind::Desktop,
+            url: None,
+            severities: Vec::new(),
+            notify_resolved: default_notify_resolved(),
+            max_retries: 0,
+            retry_backoff_ms: default_retry_backoff_ms(),
+            dbus_address: None,
+        }
+    }
+
+    fn session_bus(&self) -> Option<String> {
+        self.dbus_address
+            .clone()
+            .or_else(|| std::env::var("DBUS_SESSION_BUS_ADDRESS").ok())
+            .filter(|a| !a.is_empty())
+    }
+
+    fn accepts(&self, alert: &Alert) -> bool {
+        if alert.state == AlertState::Resolved && !self.notify_resolved {
+            return false;
+        }
+        self.severities.is_empty() || self.severities.contains(&alert.severity)
+    }
+}
+
+fn default_notify_resolved() -> bool { true }
+fn default_max_retries() -> u32 { 3 }
+fn default_retry_backoff_ms() -> u64 { 500 }
+
+/// Delivers alert state changes to the configured sinks.
+pub struct AlertNotifier {
+    client: Client,
+    sinks: Vec<NotificationSinkConfig>,
+}
+
+impl AlertNotifier {
+    /// Build the sink list from `[[alerts.sinks]]`. Desktop sinks are only
+    /// kept when `enable_desktop_notifications` is set, and one is added
+    /// automatically if the flag is set without an explicit desktop sink.
+    pub fn from_config(config: &AlertConfig) -> Result<Self> {
+        let mut sinks = Vec::new();
+
+        for sink in &config.sinks {
+            match sink.kind {
+                SinkKind::Desktop if !config.enable_desktop_notifications => {
+                    info!("Desktop notification sink ignored: enable_desktop_notifications is off");
+                }
+                SinkKind::Desktop => sinks.push(sink.clone()),
+                _ => {
+                    if sink.url.as_deref().unwrap_or_default().is_empty() {
+                        return Err(GpmError::ConfigError(config::ConfigError::Message(format!(
+                            "alerts.sinks: {} sink requires a url",
+                            sink.kind.as_str()
+                        ))));
+                    }
+                    sinks.push(sink.clone());
+                }
+            }
+        }
+
+        if config.enable_desktop_notifications && !sinks.iter().any(|s| s.kind == SinkKind::Desktop) {
+            sinks.push(NotificationSinkConfig::desktop());
+        }
+
+        Ok(Self::new(sinks))
+    }
+
+    pub fn new(sinks: Vec<NotificationSinkConfig>) -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap(),
+            sinks,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    /// Send `alert` to every sink that routes its severity. Failures are
+    /// logged and do not stop delivery to the remaining sinks.
+    pub async fn notify(&self, alert: &Alert) {
+        for sink in self.sinks.iter().filter(|s| s.accepts(alert)) {
+            if let Err(e) = self.send_with_retry(sink, alert).await {
+                warn!("Failed to deliver alert {} to {} sink: {}", alert.rule, sink.kind.as_str(), e);
+            }
+        }
+    }
+
+    async fn send_with_retry(&self, sink: &NotificationSinkConfig, alert: &Alert) -> Result<()> {
+        let mut attempt = 0;
+
+        loop {
+            match self.send(sink, alert).await {
+                Ok(()) => {
+                    debug!("Delivered alert {} to {} sink", alert.rule, sink.kind.as_str());
+                    return Ok(());
+                }
+                Err(SendError::Permanent(e)) => return Err(e),
+                Err(SendError::Retryable(e)) => {
+                    if attempt >= sink.max_retries {
+                        return Err(e);
+                    }
+
+                    let backoff = sink.retry_backoff_ms.saturating_mul(1 << attempt.min(16));
+                    debug!("Retrying {} sink in {}ms: {}", sink.kind.as_str(), backoff, e);
+                    tokio::time::sleep(Duration::from_millis(backoff)).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    async fn send(&self, sink: &NotificationSinkConfig, alert: &Alert) -> std::result::Result<(), SendError> {
+        match sink.kind {
+            SinkKind::Desktop => send_desktop(sink, alert).await,
+            kind => {
+                let url = sink.url.as_deref().unwrap_or_default();
+                let payload = webhook_payload(kind, alert);
+
+                let response = self
+                    .client
+                    .post(url)
+                    .json(&payload)
+                    .send()
+                    .await
+                    .map_err(|e| SendError::Retryable(e.into()))?;
+
+                let status = response.status();
+                if status.is_success() {
+                    Ok(())
+                } else {
+                    let err = GpmError::NotificationError(format!("{} returned {}", url, status));
+                    if status.is_server_error() || status.as_u16() == 429 {
+                        Err(SendError::Retryable(err))
+                    } else {
+                        Err(SendError::Permanent(err))
+                    }
+                }
+            }
+        }
+    }
+}
+
+enum SendError {
+    Retryable(GpmError),
+    Permanent(GpmError),
+}
+
+fn headline(alert: &Alert) -> String {
+    match alert.state {
+        AlertState::Firing => format!("[{}] {} firing", alert.severity.as_str().to_uppercase(), alert.rule),
+        // One-off events such as a GPU being added are stored already resolved
+        AlertState::Resolved if alert.resolved_at == Some(alert.started_at) => {
+            format!("[{}] {}", alert.severity.as_str().to_uppercase(), alert.rule)
+        }
+        AlertState::Resolved => format!("[RESOLVED] {}", alert.rule),
+    }
+}
+
+fn webhook_payload(kind: SinkKind, alert: &Alert) -> serde_json::Value {
+    match kind {
+        SinkKind::Slack => serde_json::json!({
+            "text": format!("*{}*\n{}", headline(alert), alert.message),
+        }),
+        SinkKind::Discord => {
+            let color = match (alert.state, alert.severity) {
+                (AlertState::Resolved, _) => 0x2ecc71,
+                (_, AlertSeverity::Critical) => 0xe74c3c,
+                (_, AlertSeverity::Warning) => 0xf39c12,
+                (_, AlertSeverity::Info) => 0x3498db,
+            };
+            serde_json::json!({
+                "content": headline(alert),
+                "embeds": [{
+                    "title": alert.rule,
+                    "description": alert.message,
+                    "color": color,
+                    "timestamp": alert.resolved_at.unwrap_or(alert.started_at).to_rfc3339(),
+                }],
+            })
+        }
+        _ => serde_json::json!({
+            "event": format!("alert.{}", alert.state.as_str()),
+            "alert": alert,
+        }),
+    }
+}
+
+async fn send_desktop(sink: &NotificationSinkConfig, alert: &Alert) -> std::result::Result<(), SendError> {
+    let Some(bus) = sink.session_bus() else {
+        return Err(SendError::Permanent(GpmError::NotificationError(
+            "No session D-Bus to notify on: set dbus_address on the desktop sink".to_string(),
+        )));
+    };
+
+    // Urgency hint levels of the notification spec: low, normal, critical
+    let urgency: u8 = match (alert.state, alert.severity) {
+        (AlertState::Resolved, _) => 0,
+        (_, AlertSeverity::Critical) => 2,
+        _ => 1,
+    };
+
+    let failed = |e: zbus::Error| GpmError::NotificationError(format!("Desktop notification on {} failed: {}", bus, e));
+
+    let connection = zbus::connection::Builder::address(bus.as_str())
+        .map_err(|e| SendError::Permanent(failed(e)))?
+        .build()
+        .await
+        .map_err(|e| SendError::Retryable(failed(e)))?;
+
+    let hints = HashMap::from([("urgency", zbus::zvariant::Value::U8(urgency))]);
+    connection
+        .call_method(
+            Some("org.freedesktop.Notifications"),
+            "/org/freedesktop/Notifications",
+            Some("org.freedesktop.Notifications"),
+            "Notify",
+            &("GPM", 0u32, "", headline(alert), alert.message.as_str(), Vec::<&str>::new(), hints, -1i32),
+        )
+        .await
+        .map_err(|e| SendError::Retryable(failed(e)))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::AlertMetric;
+    use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct StandIn {
+        failures_left: Arc<AtomicUsize>,
+        received: Arc<Mutex<Vec<serde_json::Value>>>,
+    }
+
+    async fn record(State(stand_in): State<StandIn>, Json(body): Json<serde_json::Value>) -> StatusCode {
+        stand_in.received.lock().unwrap().push(body);
+        if stand_in.failures_left.load(Ordering::SeqCst) > 0 {
+            stand_in.failures_left.fetch_sub(1, Ordering::SeqCst);
+            StatusCode::SERVICE_UNAVAILABLE
+        } else {
+            StatusCode::OK
+        }
+    }
+
+    async fn start_stand_in(failures: usize) -> (String, StandIn) {
+        let stand_in = StandIn::default();
+        stand_in.failures_left.store(failures, Ordering::SeqCst);
+
+        let app = Router::new().route("/hook", post(record)).with_state(stand_in.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        (format!("http://{}/hook", addr), stand_in)
+    }
+
+    fn sink(kind: SinkKind, url: &str) -> NotificationSinkConfig {
+        NotificationSinkConfig {
+            kind,
+            url: Some(url.to_string()),
+            severities: Vec::new(),
+            notify_resolved: true,
+            max_retries: 3,
+            retry_backoff_ms: 1,
+            dbus_address: None,
+        }
+    }
+
+    fn alert(severity: AlertSeverity) -> Alert {
+        Alert {
+            id: "a1".to_string(),
+            rule: "gpu_temperature_high".to_string(),
+            metric: AlertMetric::Temperature,
+            severity,
+            state: AlertState::Firing,
+            gpu_id: 0,
+            gpu_name: "NVIDIA GeForce RTX 3080".to_string(),
+            threshold: 85.0,
+            value: 91.0,
+            peak_value: 91.0,
+            message: "GPU 0 temperature is 91.0, threshold 85.0".to_string(),
+            started_at: chrono::Utc::now(),
+            resolved_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_webhook_retries_until_success() {
+        let (url, stand_in) = start_stand_in(2).await;
+        let notifier = AlertNotifier::new(vec![sink(SinkKind::Webhook, &url)]);
+
+        notifier.notify(&alert(AlertSeverity::Critical)).await;
+
+        let received = stand_in.received.lock().unwrap();
+        assert_eq!(received.len(), 3);
+        assert_eq!(received[2]["event"], "alert.firing");
+        assert_eq!(received[2]["alert"]["rule"], "gpu_temperature_high");
+    }
+
+    #[tokio::test]
+    async fn test_slack_and_discord_formats() {
+        let (url, stand_in) = start_stand_in(0).await;
+        let notifier = AlertNotifier::new(vec![sink(SinkKind::Slack, &url), sink(SinkKind::Discord, &url)]);
+
+        notifier.notify(&alert(AlertSeverity::Warning)).await;
+
+        let received = stand_in.received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert!(received[0]["text"].as_str().unwrap().contains("[WARNING] gpu_temperature_high"));
+        assert_eq!(received[1]["embeds"][0]["color"], 0xf39c12);
+    }
+
+    #[tokio::test]
+    async fn test_severity_routing() {
+        let (url, stand_in) = start_stand_in(0).await;
+        let mut critical_only = sink(SinkKind::Webhook, &url);
+        critical_only.severities = vec![AlertSeverity::Critical];
+        let notifier = AlertNotifier::new(vec![critical_only]);
+
+        notifier.notify(&alert(AlertSeverity::Warning)).await;
+        assert!(stand_in.received.lock().unwrap().is_empty());
+
+        notifier.notify(&alert(AlertSeverity::Critical)).await;
+        assert_eq!(stand_in.received.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_desktop_sink_honors_flag() {
+        let mut config = crate::GpmConfig::default().alerts;
+        config.sinks = vec![NotificationSinkConfig::desktop()];
+
+        assert!(AlertNotifier::from_config(&config).unwrap().is_empty());
+
+        config.enable_desktop_notifications = true;
+        assert!(!AlertNotifier::from_config(&config).unwrap().is_empty());
+
+        config.sinks = vec![NotificationSinkConfig { url: None, ..sink(SinkKind::Slack, "") }];
+        assert!(AlertNotifier::from_config(&config).is_err());
+    }
+
+    /// The two calls a desktop notification makes on a session bus.
+    #[derive(Clone, Default)]
+    struct StandInBus {
+        notified: Arc<Mutex<Vec<(String, String, u8)>>>,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.DBus")]
+    impl StandInBus {
+        fn hello(&self) -> String {
+            ":1.1".to_string()
+        }
+    }
+
+    struct StandInNotifications(StandInBus);
+
+    #[zbus::interface(name = "org.freedesktop.Notifications")]
+    impl StandInNotifications {
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &self,
+            app_name: String,
+            _replaces_id: u32,
+            _app_icon: String,
+            summary: String,
+            _body: String,
+            _actions: Vec<String>,
+            hints: HashMap<String, zbus::zvariant::OwnedValue>,
+            _expire_timeout: i32,
+        ) -> u32 {
+            let urgency = hints.get("urgency").and_then(|u| u8::try_from(u).ok()).unwrap_or_default();
+            self.0.notified.lock().unwrap().push((app_name, summary, urgency));
+            1
+        }
+    }
+
+    #[tokio::test]
+    async fn test_desktop_sink_notifies_on_configured_bus() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("bus");
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+        let bus = StandInBus::default();
+
+        let served = bus.clone();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let _connection = zbus::connection::Builder::unix_stream(stream)
+                .server(zbus::Guid::generate())
+                .unwrap()
+                .p2p()
+                .serve_at("/org/freedesktop/DBus", served.clone())
+                .unwrap()
+                .serve_at("/org/freedesktop/Notifications", StandInNotifications(served))
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+            std::future::pending::<()>().await;
+        });
+
+        let desktop = NotificationSinkConfig {
+            dbus_address: Some(format!("unix:path={}", socket.display())),
+            ..NotificationSinkConfig::desktop()
+        };
+        AlertNotifier::new(vec![desktop]).notify(&alert(AlertSeverity::Critical)).await;
+
+        let notified = bus.notified.lock().unwrap();
+        assert_eq!(
+            *notified,
+            vec![("GPM".to_string(), "[CRITICAL] gpu_temperature_high firing".to_string(), 2)]
+        );
+    }
+}
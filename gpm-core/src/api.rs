@@ -1,8 +1,8 @@
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::{IntoResponse, Json},
-    routing::get,
+    routing::{delete, get},
     Router,
 };
 use std::sync::Arc;
@@ -10,15 +10,27 @@ use tokio::sync::Mutex;
 use tower_http::cors::{Any, CorsLayer};
 
 use crate::{
-    gpu::{GpuMonitorBackend, GpuMetrics},
-    storage::Database,
+    accounting::{EnergyReport, ModelEfficiency, UsagePeriod, UserUsage},
+    alerts::Alert,
+    config::EnergyConfig,
+    classifier::overrides::{ClassificationOverride, OverrideKind, OverrideSet},
+    classifier::{ProcessEventRecord, WorkloadCategory},
+    gpu::{
+        DeviceEvent, GpuEvent, GpuEventKind, GpuIdentity, GpuMetrics, GpuMonitorBackend, GpuSelector, GpuTelemetry, KnownGpu,
+        MigDevice, ProcessUtilization,
+    },
+    ollama::{ApiFlavor, ProxyRequest},
+    storage::{Database, HistoryQuery},
+    tracker::ProcessSession,
 };
 
 /// API state shared across routes
 #[derive(Clone)]
 pub struct ApiState {
     pub db: Arc<Database>,
+    pub history: Arc<HistoryQuery>,
     pub gpu_monitor: Arc<Mutex<Option<GpuMonitorBackend>>>,
+    pub energy: EnergyConfig,
 }
 
 /// Create API router
@@ -30,10 +42,23 @@ pub fn create_router(state: ApiState) -> Router {
 
     Router::new()
         .route("/api/info", get(get_dashboard_info))
+        .route("/api/gpus", get(get_gpus))
+        .route("/api/gpus/events", get(get_device_events))
+        .route("/api/gpu-events", get(get_gpu_events))
         .route("/api/realtime", get(get_realtime_metrics))
         .route("/api/historical", get(get_historical_metrics))
         .route("/api/chart", get(get_chart_data))
         .route("/api/llm-sessions", get(get_llm_sessions))
+        .route("/api/llm-models/efficiency", get(get_llm_model_efficiency))
+        .route("/api/proxy-requests", get(get_proxy_requests))
+        .route("/api/alerts", get(get_alerts))
+        .route("/api/alerts/active", get(get_active_alerts))
+        .route("/api/process-sessions", get(get_process_sessions))
+        .route("/api/classifications", get(get_classifications))
+        .route("/api/overrides", get(get_overrides).post(create_override))
+        .route("/api/overrides/:id", delete(delete_override))
+        .route("/api/users/usage", get(get_user_usage))
+        .route("/api/energy", get(get_energy_report))
         .with_state(state)
         .layer(cors)
 }
@@ -69,6 +94,11 @@ pub struct GpuMetricData {
     pub temperature: u32,
     pub power_usage: u32,
     pub memory_percent: f64,
+    #[serde(flatten)]
+    pub telemetry: GpuTelemetry,
+    #[serde(flatten)]
+    pub identity: GpuIdentity,
+    pub mig_devices: Vec<MigDevice>,
 }
 
 impl From<GpuMetrics> for GpuMetricData {
@@ -92,6 +122,117 @@ impl From<GpuMetrics> for GpuMetricData {
             temperature: m.temperature,
             power_usage: m.power_usage,
             memory_percent,
+            telemetry: m.telemetry,
+            identity: m.identity,
+            mig_devices: m.mig_devices,
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct GpuData {
+    pub uuid: String,
+    pub gpu_index: u32,
+    pub name: String,
+    pub serial: Option<String>,
+    pub pci_bus_id: Option<String>,
+    pub vbios_version: Option<String>,
+    pub driver_version: Option<String>,
+    pub memory_total_mb: f64,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+impl From<KnownGpu> for GpuData {
+    fn from(g: KnownGpu) -> Self {
+        Self {
+            uuid: g.uuid,
+            gpu_index: g.gpu_index,
+            name: g.name,
+            serial: g.serial,
+            pci_bus_id: g.pci_bus_id,
+            vbios_version: g.vbios_version,
+            driver_version: g.driver_version,
+            memory_total_mb: g.memory_total as f64 / (1024.0 * 1024.0),
+            first_seen: g.first_seen.to_rfc3339(),
+            last_seen: g.last_seen.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct DeviceEventParams {
+    /// Look back this many hours (default 24)
+    pub hours: Option<i64>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct DeviceEventData {
+    pub id: String,
+    pub timestamp: String,
+    /// `added` or `removed`
+    pub kind: String,
+    pub gpu_id: u32,
+    pub uuid: Option<String>,
+    pub name: String,
+    pub pci_bus_id: Option<String>,
+    pub detail: Option<String>,
+}
+
+impl From<DeviceEvent> for DeviceEventData {
+    fn from(e: DeviceEvent) -> Self {
+        Self {
+            id: e.id,
+            timestamp: e.timestamp.to_rfc3339(),
+            kind: e.kind.as_str().to_string(),
+            gpu_id: e.gpu_id,
+            uuid: e.uuid,
+            name: e.name,
+            pci_bus_id: e.pci_bus_id,
+            detail: e.detail,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct GpuEventParams {
+    /// Look back this many hours (default 24)
+    pub hours: Option<i64>,
+    /// Only this kind, e.g. `xid`
+    pub kind: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct GpuEventData {
+    pub id: String,
+    pub timestamp: String,
+    pub kind: String,
+    pub gpu_id: Option<u32>,
+    pub uuid: Option<String>,
+    pub pci_bus_id: Option<String>,
+    pub xid: Option<u64>,
+    pub pids: Vec<u32>,
+    /// `nvml` or `kernel_log`
+    pub source: String,
+    pub message: String,
+    pub critical: bool,
+}
+
+impl From<GpuEvent> for GpuEventData {
+    fn from(e: GpuEvent) -> Self {
+        let critical = e.is_critical();
+        Self {
+            id: e.id,
+            timestamp: e.timestamp.to_rfc3339(),
+            kind: e.kind.as_str().to_string(),
+            gpu_id: e.gpu_id,
+            uuid: e.uuid,
+            pci_bus_id: e.pci_bus_id,
+            xid: e.xid,
+            pids: e.pids,
+            source: e.source.as_str().to_string(),
+            message: e.message,
+            critical,
         }
     }
 }
@@ -116,13 +257,16 @@ pub struct ChartDataResponse {
 
 #[derive(Debug, serde::Deserialize)]
 pub struct ChartParams {
-    pub gpu_id: u32,
+    /// Enumeration index or UUID
+    pub gpu_id: String,
     pub hours: i64,
 }
 
 #[derive(Debug, serde::Deserialize)]
 pub struct HistoricalParams {
     pub hours: i64,
+    /// Only this GPU, by enumeration index or UUID
+    pub gpu: Option<String>,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -143,6 +287,282 @@ pub struct LlmSessionData {
     pub tokens_per_second: f64,
     pub time_to_first_token_ms: Option<u64>,
     pub time_per_output_token_ms: Option<f64>,
+    pub energy_joules: Option<f64>,
+    pub joules_per_token: Option<f64>,
+    pub avg_power_watts: Option<f64>,
+    pub peak_gpu_memory_mb: Option<u64>,
+    pub avg_gpu_utilization: Option<f64>,
+    pub api_flavor: ApiFlavor,
+    pub abandoned: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ProxyRequestParams {
+    /// Look back this many hours (default 24)
+    pub hours: Option<i64>,
+    /// Only this path, e.g. `/api/embed`
+    pub endpoint: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ProxyRequestData {
+    pub id: String,
+    pub timestamp: String,
+    pub method: String,
+    pub endpoint: String,
+    pub model: Option<String>,
+    pub status: u16,
+    pub latency_ms: u64,
+    pub request_bytes: u64,
+    pub response_bytes: u64,
+    pub client_addr: Option<String>,
+    pub session_id: Option<String>,
+    pub input_count: Option<u64>,
+    pub prompt_tokens: Option<u64>,
+    pub pull_status: Option<String>,
+    pub pull_completed_bytes: Option<u64>,
+    pub pull_total_bytes: Option<u64>,
+}
+
+impl From<ProxyRequest> for ProxyRequestData {
+    fn from(r: ProxyRequest) -> Self {
+        Self {
+            id: r.id,
+            timestamp: r.timestamp.to_rfc3339(),
+            method: r.method,
+            endpoint: r.endpoint,
+            model: r.model,
+            status: r.status,
+            latency_ms: r.latency_ms,
+            request_bytes: r.request_bytes,
+            response_bytes: r.response_bytes,
+            client_addr: r.client_addr,
+            session_id: r.session_id,
+            input_count: r.input_count,
+            prompt_tokens: r.prompt_tokens,
+            pull_status: r.pull_status,
+            pull_completed_bytes: r.pull_completed_bytes,
+            pull_total_bytes: r.pull_total_bytes,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct AlertParams {
+    pub hours: i64,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct AlertData {
+    pub id: String,
+    pub rule: String,
+    pub metric: String,
+    pub severity: String,
+    pub state: String,
+    pub gpu_id: u32,
+    pub gpu_name: String,
+    pub threshold: f64,
+    pub value: f64,
+    pub peak_value: f64,
+    pub message: String,
+    pub started_at: String,
+    pub resolved_at: Option<String>,
+}
+
+impl From<Alert> for AlertData {
+    fn from(a: Alert) -> Self {
+        Self {
+            id: a.id,
+            rule: a.rule,
+            metric: a.metric.as_str().to_string(),
+            severity: a.severity.as_str().to_string(),
+            state: a.state.as_str().to_string(),
+            gpu_id: a.gpu_id,
+            gpu_name: a.gpu_name,
+            threshold: a.threshold,
+            value: a.value,
+            peak_value: a.peak_value,
+            message: a.message,
+            started_at: a.started_at.to_rfc3339(),
+            resolved_at: a.resolved_at.map(|t| t.to_rfc3339()),
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ProcessSessionParams {
+    pub hours: i64,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ProcessSessionData {
+    pub id: String,
+    pub pid: u32,
+    pub name: String,
+    pub category: String,
+    pub command_line: String,
+    pub first_seen: String,
+    pub last_seen: String,
+    pub end_time: Option<String>,
+    pub duration_secs: u64,
+    pub peak_gpu_memory_mb: u64,
+    pub avg_gpu_utilization: f64,
+}
+
+impl From<ProcessSession> for ProcessSessionData {
+    fn from(s: ProcessSession) -> Self {
+        Self {
+            id: s.id,
+            pid: s.pid,
+            name: s.name,
+            category: s.category.as_str().to_string(),
+            command_line: s.command_line,
+            first_seen: s.first_seen.to_rfc3339(),
+            last_seen: s.last_seen.to_rfc3339(),
+            end_time: s.end_time.map(|t| t.to_rfc3339()),
+            duration_secs: s.duration_secs,
+            peak_gpu_memory_mb: s.peak_gpu_memory_mb,
+            avg_gpu_utilization: s.avg_gpu_utilization,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ClassificationParams {
+    pub hours: i64,
+    pub pid: Option<u32>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ClassificationData {
+    pub id: i64,
+    pub timestamp: String,
+    pub pid: u32,
+    pub name: String,
+    pub command_line: String,
+    pub exe_path: Option<String>,
+    pub category: String,
+    pub rule: String,
+    pub confidence: f64,
+    pub evidence: Vec<String>,
+    pub gpu_memory_mb: u64,
+    pub gpu_utilization: u32,
+    /// `null` when `gpu_utilization` was estimated from VRAM share
+    pub engine_utilization: Option<ProcessUtilization>,
+    pub user: Option<String>,
+}
+
+impl From<ProcessEventRecord> for ClassificationData {
+    fn from(e: ProcessEventRecord) -> Self {
+        Self {
+            id: e.id,
+            timestamp: e.timestamp.to_rfc3339(),
+            pid: e.process.pid,
+            name: e.process.name,
+            command_line: e.process.command_line,
+            exe_path: e.process.exe_path.map(|p| p.to_string_lossy().to_string()),
+            category: e.process.category.as_str().to_string(),
+            rule: e.process.rule,
+            confidence: e.process.confidence,
+            evidence: e.process.evidence,
+            gpu_memory_mb: e.process.gpu_memory_mb,
+            gpu_utilization: e.process.gpu_utilization,
+            engine_utilization: e.process.engine_utilization,
+            user: e.process.user,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CreateOverrideRequest {
+    pub kind: OverrideKind,
+    pub pattern: String,
+    pub category: WorkloadCategory,
+    pub note: Option<String>,
+    /// Also relabel matching `process_events` from the last N hours, with
+    /// their sessions and weekly summaries. PID pins only relabel samples
+    /// taken after the pin was created.
+    pub reclassify_hours: Option<i64>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct OverrideData {
+    pub id: String,
+    pub kind: String,
+    pub pattern: String,
+    pub category: String,
+    pub note: Option<String>,
+    pub created_at: String,
+}
+
+impl From<ClassificationOverride> for OverrideData {
+    fn from(o: ClassificationOverride) -> Self {
+        Self {
+            id: o.id,
+            kind: o.kind.as_str().to_string(),
+            pattern: o.pattern,
+            category: o.category.as_str().to_string(),
+            note: o.note,
+            created_at: o.created_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct CreateOverrideResponse {
+    #[serde(rename = "override")]
+    pub created: OverrideData,
+    pub reclassified_events: u64,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct UserUsageParams {
+    /// Look back this many days (default 7)
+    pub days: Option<i64>,
+    #[serde(default)]
+    pub period: UsagePeriod,
+    pub user: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct UserUsageData {
+    pub user: String,
+    pub uid: Option<u32>,
+    pub period: String,
+    pub period_start: String,
+    pub gpu_hours: f64,
+    pub peak_gpu_memory_mb: u64,
+    pub energy_wh: f64,
+    pub sample_count: u64,
+}
+
+impl UserUsageData {
+    fn new(u: UserUsage, period: UsagePeriod) -> Self {
+        Self {
+            user: u.user,
+            uid: u.uid,
+            period: period.as_str().to_string(),
+            period_start: u.period_start.to_string(),
+            gpu_hours: u.gpu_hours,
+            peak_gpu_memory_mb: u.peak_gpu_memory_mb,
+            energy_wh: u.energy_wh,
+            sample_count: u.sample_count,
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct EnergyParams {
+    /// Look back this many days (default 7)
+    pub days: Option<i64>,
+    #[serde(default)]
+    pub period: UsagePeriod,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct EfficiencyParams {
+    /// Look back this many days (default 7)
+    pub days: Option<i64>,
 }
 
 // ============= Handlers =============
@@ -162,6 +582,77 @@ async fn get_dashboard_info(State(state): State<ApiState>) -> Result<Json<Dashbo
     }))
 }
 
+async fn get_gpus(State(state): State<ApiState>) -> Result<Json<Vec<GpuData>>, ApiError> {
+    let gpus = state
+        .db
+        .get_gpus()
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to get GPUs: {}", e)))?;
+
+    Ok(Json(gpus.into_iter().map(GpuData::from).collect()))
+}
+
+async fn get_device_events(
+    State(state): State<ApiState>,
+    Query(params): Query<DeviceEventParams>,
+) -> Result<Json<Vec<DeviceEventData>>, ApiError> {
+    let end = chrono::Utc::now();
+    let start = end - chrono::Duration::hours(params.hours.unwrap_or(24));
+
+    let events = state
+        .db
+        .get_device_events(start, end)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to get GPU device events: {}", e)))?;
+
+    Ok(Json(events.into_iter().map(DeviceEventData::from).collect()))
+}
+
+async fn get_gpu_events(
+    State(state): State<ApiState>,
+    Query(params): Query<GpuEventParams>,
+) -> Result<Json<Vec<GpuEventData>>, ApiError> {
+    let kind = match params.kind.as_deref() {
+        Some(kind) => {
+            Some(GpuEventKind::parse(kind).ok_or_else(|| ApiError::BadRequest(format!("Unknown event kind: {}", kind)))?)
+        }
+        None => None,
+    };
+
+    let end = chrono::Utc::now();
+    let start = end - chrono::Duration::hours(params.hours.unwrap_or(24));
+
+    let events = state
+        .db
+        .get_gpu_events(start, end)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to get GPU events: {}", e)))?;
+
+    Ok(Json(
+        events
+            .into_iter()
+            .filter(|e| kind.is_none_or(|kind| e.kind == kind))
+            .map(GpuEventData::from)
+            .collect(),
+    ))
+}
+
+async fn get_proxy_requests(
+    State(state): State<ApiState>,
+    Query(params): Query<ProxyRequestParams>,
+) -> Result<Json<Vec<ProxyRequestData>>, ApiError> {
+    let end = chrono::Utc::now();
+    let start = end - chrono::Duration::hours(params.hours.unwrap_or(24));
+
+    let requests = state
+        .db
+        .get_proxy_requests(start, end, params.endpoint.as_deref())
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to get proxy requests: {}", e)))?;
+
+    Ok(Json(requests.into_iter().map(ProxyRequestData::from).collect()))
+}
+
 async fn get_realtime_metrics(State(state): State<ApiState>) -> Result<Json<Vec<GpuMetricData>>, ApiError> {
     let gpu_monitor = state.gpu_monitor.lock().await;
 
@@ -181,12 +672,18 @@ async fn get_historical_metrics(
     Query(params): Query<HistoricalParams>,
 ) -> Result<Json<Vec<GpuMetricData>>, ApiError> {
     let metrics = state
-        .db
-        .get_recent_gpu_metrics(params.hours)
+        .history
+        .recent_gpu_metrics(params.hours)
         .await
         .map_err(|e| ApiError::Internal(format!("Failed to get metrics: {}", e)))?;
 
-    Ok(Json(metrics.into_iter().map(GpuMetricData::from).collect()))
+    let gpu = params.gpu.as_deref().map(GpuSelector::parse);
+
+    Ok(Json(metrics
+        .into_iter()
+        .filter(|m| gpu.as_ref().is_none_or(|g| g.matches(m)))
+        .map(GpuMetricData::from)
+        .collect()))
 }
 
 async fn get_chart_data(
@@ -194,14 +691,15 @@ async fn get_chart_data(
     Query(params): Query<ChartParams>,
 ) -> Result<Json<ChartDataResponse>, ApiError> {
     let metrics = state
-        .db
-        .get_recent_gpu_metrics(params.hours)
+        .history
+        .recent_gpu_metrics(params.hours)
         .await
         .map_err(|e| ApiError::Internal(format!("Failed to get metrics: {}", e)))?;
 
+    let gpu = GpuSelector::parse(&params.gpu_id);
     let gpu_metrics: Vec<_> = metrics
         .into_iter()
-        .filter(|m| m.gpu_id == params.gpu_id)
+        .filter(|m| gpu.matches(m))
         .map(GpuMetricData::from)
         .collect();
 
@@ -228,8 +726,8 @@ async fn get_llm_sessions(
         .with_timezone(&chrono::Utc);
 
     let sessions = state
-        .db
-        .get_llm_sessions(start, end)
+        .history
+        .llm_sessions(start, end)
         .await
         .map_err(|e| ApiError::Internal(format!("Failed to get LLM sessions: {}", e)))?;
 
@@ -246,15 +744,211 @@ async fn get_llm_sessions(
             tokens_per_second: s.tokens_per_second,
             time_to_first_token_ms: s.time_to_first_token_ms,
             time_per_output_token_ms: s.time_per_output_token_ms,
+            energy_joules: s.hardware.as_ref().map(|h| h.energy_joules),
+            joules_per_token: s.hardware.as_ref().and_then(|h| h.joules_per_token),
+            avg_power_watts: s.hardware.as_ref().map(|h| h.avg_power_watts),
+            peak_gpu_memory_mb: s.hardware.as_ref().map(|h| h.peak_gpu_memory_mb),
+            avg_gpu_utilization: s.hardware.as_ref().map(|h| h.avg_gpu_utilization),
+            api_flavor: s.api_flavor,
+            abandoned: s.abandoned,
         })
         .collect()))
 }
 
+async fn get_alerts(
+    State(state): State<ApiState>,
+    Query(params): Query<AlertParams>,
+) -> Result<Json<Vec<AlertData>>, ApiError> {
+    let end = chrono::Utc::now();
+    let start = end - chrono::Duration::hours(params.hours);
+
+    let alerts = state
+        .db
+        .get_alerts(start, end)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to get alerts: {}", e)))?;
+
+    Ok(Json(alerts.into_iter().map(AlertData::from).collect()))
+}
+
+async fn get_active_alerts(State(state): State<ApiState>) -> Result<Json<Vec<AlertData>>, ApiError> {
+    let alerts = state
+        .db
+        .get_active_alerts()
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to get active alerts: {}", e)))?;
+
+    Ok(Json(alerts.into_iter().map(AlertData::from).collect()))
+}
+
+async fn get_process_sessions(
+    State(state): State<ApiState>,
+    Query(params): Query<ProcessSessionParams>,
+) -> Result<Json<Vec<ProcessSessionData>>, ApiError> {
+    let end = chrono::Utc::now();
+    let start = end - chrono::Duration::hours(params.hours);
+
+    let sessions = state
+        .db
+        .get_process_sessions(start, end)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to get process sessions: {}", e)))?;
+
+    Ok(Json(sessions.into_iter().map(ProcessSessionData::from).collect()))
+}
+
+async fn get_classifications(
+    State(state): State<ApiState>,
+    Query(params): Query<ClassificationParams>,
+) -> Result<Json<Vec<ClassificationData>>, ApiError> {
+    let end = chrono::Utc::now();
+    let start = end - chrono::Duration::hours(params.hours);
+
+    let events = state
+        .db
+        .get_process_events(start, end, params.pid)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to get classifications: {}", e)))?;
+
+    Ok(Json(events.into_iter().map(ClassificationData::from).collect()))
+}
+
+async fn get_overrides(State(state): State<ApiState>) -> Result<Json<Vec<OverrideData>>, ApiError> {
+    let overrides = state
+        .db
+        .get_classification_overrides()
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to get overrides: {}", e)))?;
+
+    Ok(Json(overrides.into_iter().map(OverrideData::from).collect()))
+}
+
+async fn create_override(
+    State(state): State<ApiState>,
+    Json(request): Json<CreateOverrideRequest>,
+) -> Result<(StatusCode, Json<CreateOverrideResponse>), ApiError> {
+    let created = ClassificationOverride::new(request.kind, request.pattern, request.category, request.note)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    state
+        .db
+        .insert_classification_override(&created)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to store override: {}", e)))?;
+
+    let reclassified_events = match request.reclassify_hours {
+        Some(hours) => {
+            let end = chrono::Utc::now();
+            let start = end - chrono::Duration::hours(hours);
+            state
+                .db
+                .reclassify_process_events(&OverrideSet::new(vec![created.clone()]), start, end)
+                .await
+                .map_err(|e| ApiError::Internal(format!("Failed to reclassify events: {}", e)))?
+        }
+        None => 0,
+    };
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateOverrideResponse {
+            created: created.into(),
+            reclassified_events,
+        }),
+    ))
+}
+
+async fn delete_override(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let deleted = state
+        .db
+        .delete_classification_override(&id)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to delete override: {}", e)))?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::NotFound(format!("No override with id {}", id)))
+    }
+}
+
+async fn get_user_usage(
+    State(state): State<ApiState>,
+    Query(params): Query<UserUsageParams>,
+) -> Result<Json<Vec<UserUsageData>>, ApiError> {
+    let days = params.days.unwrap_or(7);
+    if days <= 0 {
+        return Err(ApiError::BadRequest("days must be positive".to_string()));
+    }
+
+    let end = chrono::Utc::now();
+    let start = end - chrono::Duration::days(days);
+
+    let usage = state
+        .db
+        .get_user_usage(start, end, params.period, params.user.as_deref())
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to get user usage: {}", e)))?;
+
+    Ok(Json(usage.into_iter().map(|u| UserUsageData::new(u, params.period)).collect()))
+}
+
+async fn get_energy_report(
+    State(state): State<ApiState>,
+    Query(params): Query<EnergyParams>,
+) -> Result<Json<EnergyReport>, ApiError> {
+    let days = params.days.unwrap_or(7);
+    if days <= 0 {
+        return Err(ApiError::BadRequest("days must be positive".to_string()));
+    }
+
+    let end = chrono::Utc::now();
+    let start = end - chrono::Duration::days(days);
+    let internal = |e: crate::error::GpmError| ApiError::Internal(format!("Failed to build energy report: {}", e));
+
+    let gpu_metrics = state.history.gpu_metrics(start, end).await.map_err(internal)?;
+    let category_energy = state.history.category_energy(start, end).await.map_err(internal)?;
+    let sessions = state.history.llm_sessions(start, end).await.map_err(internal)?;
+
+    Ok(Json(crate::accounting::energy_report(
+        &gpu_metrics,
+        &category_energy,
+        &sessions,
+        &state.energy,
+        params.period,
+    )))
+}
+
+async fn get_llm_model_efficiency(
+    State(state): State<ApiState>,
+    Query(params): Query<EfficiencyParams>,
+) -> Result<Json<Vec<ModelEfficiency>>, ApiError> {
+    let days = params.days.unwrap_or(7);
+    if days <= 0 {
+        return Err(ApiError::BadRequest("days must be positive".to_string()));
+    }
+
+    let end = chrono::Utc::now();
+    let start = end - chrono::Duration::days(days);
+
+    let sessions = state
+        .history
+        .llm_sessions(start, end)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to get LLM sessions: {}", e)))?;
+
+    Ok(Json(crate::accounting::model_efficiency(&sessions, &state.energy)))
+}
+
 // ============= Error Types =============
 
 #[derive(Debug)]
 pub enum ApiError {
     BadRequest(String),
+    NotFound(String),
     Internal(String),
 }
 
@@ -262,6 +956,7 @@ impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
         let (status, message) = match self {
             ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
             ApiError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
         };
 
@@ -3,7 +3,7 @@ use gpm_core::{
     config::GpmConfig,
     gpu::GpuMonitorBackend,
     init_logging,
-    storage::Database,
+    storage::{Database, HistoryQuery},
 };
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -39,13 +39,26 @@ async fn main() {
         }
     };
 
+    let db = Arc::new(db);
+
+    // Historical queries also read the Parquet archive
+    let history = match HistoryQuery::new(Arc::clone(&db), &config.storage.archive_dir) {
+        Ok(history) => history,
+        Err(e) => {
+            error!("Failed to open archive directory: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     // Initialize GPU monitor
     let gpu_monitor = GpuMonitorBackend::initialize(&config).ok();
 
     // Create API state
     let api_state = ApiState {
-        db: Arc::new(db),
+        db,
+        history: Arc::new(history),
         gpu_monitor: Arc::new(Mutex::new(gpu_monitor)),
+        energy: config.energy.clone(),
     };
 
     // Start web server
@@ -0,0 +1,273 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Where a process runs, derived from `/proc/<pid>/cgroup` and, for
+/// containers, the runtime's on-disk metadata.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CgroupInfo {
+    pub container_id: Option<String>,
+    pub container_name: Option<String>,
+    pub container_image: Option<String>,
+    /// `docker`, `podman`, `containerd` or `cri-o`
+    pub container_runtime: Option<String>,
+    pub systemd_unit: Option<String>,
+    pub systemd_slice: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ContainerMetadata {
+    name: Option<String>,
+    image: Option<String>,
+}
+
+/// Resolves cgroup/container details for PIDs, caching runtime metadata per
+/// container ID. Failed lookups are retried on the next resolve, since the
+/// runtime may only have been unreachable for a moment, and entries for
+/// containers not resolved since the last `retain_seen` are dropped.
+pub struct CgroupResolver {
+    proc_root: PathBuf,
+    docker_root: PathBuf,
+    podman_roots: Vec<PathBuf>,
+    containers: HashMap<String, ContainerMetadata>,
+    /// Container IDs resolved since the last `retain_seen`
+    seen: HashSet<String>,
+}
+
+impl CgroupResolver {
+    pub fn new() -> Self {
+        let mut podman_roots = vec![PathBuf::from("/var/lib/containers/storage")];
+        if let Some(data_dir) = dirs::data_local_dir() {
+            podman_roots.push(data_dir.join("containers/storage"));
+        }
+
+        Self::with_roots("/proc", "/var/lib/docker", podman_roots)
+    }
+
+    pub fn with_roots<P: Into<PathBuf>, D: Into<PathBuf>>(
+        proc_root: P,
+        docker_root: D,
+        podman_roots: Vec<PathBuf>,
+    ) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            docker_root: docker_root.into(),
+            podman_roots,
+            containers: HashMap::new(),
+            seen: HashSet::new(),
+        }
+    }
+
+    pub fn resolve(&mut self, pid: u32) -> CgroupInfo {
+        let path = self.proc_root.join(pid.to_string()).join("cgroup");
+        let Ok(contents) = std::fs::read_to_string(&path) else {
+            return CgroupInfo::default();
+        };
+
+        let mut info = parse_cgroup(&contents);
+
+        if let (Some(id), Some(runtime)) = (&info.container_id, &info.container_runtime) {
+            self.seen.insert(id.clone());
+            let metadata = match self.containers.get(id) {
+                Some(metadata) => metadata.clone(),
+                None => match self.read_container_metadata(id, runtime) {
+                    Some(metadata) => {
+                        self.containers.insert(id.clone(), metadata.clone());
+                        metadata
+                    }
+                    None => ContainerMetadata::default(),
+                },
+            };
+            info.container_name = metadata.name;
+            info.container_image = metadata.image;
+        }
+
+        info
+    }
+
+    /// Forget the metadata of containers not resolved since the last call,
+    /// so exited containers do not pile up. Call once per classification pass.
+    pub fn retain_seen(&mut self) {
+        let seen = std::mem::take(&mut self.seen);
+        self.containers.retain(|id, _| seen.contains(id));
+    }
+
+    fn read_container_metadata(&self, id: &str, runtime: &str) -> Option<ContainerMetadata> {
+        let metadata = match runtime {
+            "docker" => self.read_docker_metadata(id),
+            "podman" => self.podman_roots.iter().find_map(|root| read_podman_metadata(root, id)),
+            _ => None,
+        };
+
+        debug!("Container {} ({}) metadata: {:?}", short_id(id), runtime, metadata);
+        metadata.filter(|m| m.name.is_some() || m.image.is_some())
+    }
+
+    fn read_docker_metadata(&self, id: &str) -> Option<ContainerMetadata> {
+        let path = self.docker_root.join("containers").join(id).join("config.v2.json");
+        let config: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+
+        Some(ContainerMetadata {
+            name: config["Name"].as_str().map(|n| n.trim_start_matches('/').to_string()),
+            image: config["Config"]["Image"].as_str().map(str::to_string),
+        })
+    }
+}
+
+impl Default for CgroupResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn read_podman_metadata(root: &Path, id: &str) -> Option<ContainerMetadata> {
+    let path = root.join("overlay-containers").join("containers.json");
+    let containers: Vec<serde_json::Value> = serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+    let container = containers.iter().find(|c| c["id"].as_str() == Some(id))?;
+
+    // Podman keeps the human-readable image name in a JSON string field
+    let image = container["metadata"]
+        .as_str()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+        .and_then(|m| m["image-name"].as_str().map(str::to_string));
+
+    Some(ContainerMetadata {
+        name: container["names"][0].as_str().map(str::to_string),
+        image,
+    })
+}
+
+/// Extract container and systemd details from the contents of
+/// `/proc/<pid>/cgroup` (v1 or v2).
+pub fn parse_cgroup(contents: &str) -> CgroupInfo {
+    // Prefer the unified (v2) hierarchy, else the first v1 line with a path
+    let path = contents
+        .lines()
+        .find_map(|l| l.strip_prefix("0::"))
+        .or_else(|| {
+            contents
+                .lines()
+                .filter_map(|l| l.splitn(3, ':').nth(2))
+                .find(|p| *p != "/")
+        })
+        .unwrap_or_default();
+
+    let mut info = CgroupInfo::default();
+
+    for component in path.split('/').filter(|c| !c.is_empty()) {
+        if component.ends_with(".slice") {
+            info.systemd_slice = Some(component.to_string());
+        } else if component.ends_with(".service") || component.ends_with(".scope") {
+            info.systemd_unit = Some(component.to_string());
+        }
+
+        if let Some((runtime, id)) = container_from_component(component) {
+            info.container_runtime = Some(runtime.to_string());
+            info.container_id = Some(id.to_string());
+        }
+    }
+
+    // cgroup v1 docker: /docker/<id>
+    if info.container_id.is_none() {
+        let mut components = path.split('/').filter(|c| !c.is_empty());
+        while let Some(component) = components.next() {
+            if component == "docker" {
+                if let Some(id) = components.next().filter(|id| is_container_id(id)) {
+                    info.container_runtime = Some("docker".to_string());
+                    info.container_id = Some(id.to_string());
+                }
+            }
+        }
+    }
+
+    info
+}
+
+fn container_from_component(component: &str) -> Option<(&'static str, &str)> {
+    let name = component.strip_suffix(".scope").unwrap_or(component);
+
+    let (runtime, id) = if let Some(id) = name.strip_prefix("docker-") {
+        ("docker", id)
+    } else if let Some(id) = name.strip_prefix("libpod-") {
+        ("podman", id)
+    } else if let Some(id) = name.strip_prefix("cri-containerd-") {
+        ("containerd", id)
+    } else if let Some(id) = name.strip_prefix("crio-") {
+        ("cri-o", id)
+    } else {
+        return None;
+    };
+
+    is_container_id(id).then_some((runtime, id))
+}
+
+fn is_container_id(s: &str) -> bool {
+    s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn short_id(id: &str) -> &str {
+    &id[..id.len().min(12)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "4f2b8c1d9e7a6b5c4d3e2f1a0b9c8d7e6f5a4b3c2d1e0f9a8b7c6d5e4f3a2b1c";
+
+    #[test]
+    fn test_parse_cgroup_variants() {
+        let docker = parse_cgroup(&format!("0::/system.slice/docker-{}.scope\n", ID));
+        assert_eq!(docker.container_runtime.as_deref(), Some("docker"));
+        assert_eq!(docker.container_id.as_deref(), Some(ID));
+        assert_eq!(docker.systemd_slice.as_deref(), Some("system.slice"));
+
+        let podman = parse_cgroup(&format!(
+            "0::/user.slice/user-1000.slice/user@1000.service/user.slice/libpod-{}.scope/container\n",
+            ID
+        ));
+        assert_eq!(podman.container_runtime.as_deref(), Some("podman"));
+        assert_eq!(podman.systemd_unit.as_deref(), Some(format!("libpod-{}.scope", ID).as_str()));
+
+        let v1 = parse_cgroup(&format!("12:memory:/docker/{}\n1:name=systemd:/docker/{}\n", ID, ID));
+        assert_eq!(v1.container_id.as_deref(), Some(ID));
+
+        let unit = parse_cgroup("0::/user.slice/user-1000.slice/user@1000.service/app.slice/jupyter.service\n");
+        assert_eq!(unit.container_id, None);
+        assert_eq!(unit.systemd_unit.as_deref(), Some("jupyter.service"));
+        assert_eq!(unit.systemd_slice.as_deref(), Some("app.slice"));
+    }
+
+    #[test]
+    fn test_resolver_reads_docker_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let proc_dir = dir.path().join("proc/4242");
+        std::fs::create_dir_all(&proc_dir).unwrap();
+        std::fs::write(proc_dir.join("cgroup"), format!("0::/system.slice/docker-{}.scope\n", ID)).unwrap();
+
+        let mut resolver = CgroupResolver::with_roots(dir.path().join("proc"), dir.path().join("docker"), Vec::new());
+        assert_eq!(resolver.resolve(4242).container_name, None);
+
+        // Written after the first lookup, e.g. once the runtime caught up
+        let container_dir = dir.path().join("docker/containers").join(ID);
+        std::fs::create_dir_all(&container_dir).unwrap();
+        std::fs::write(
+            container_dir.join("config.v2.json"),
+            r#"{"Name": "/vllm-server", "Config": {"Image": "vllm/vllm-openai:latest"}}"#,
+        )
+        .unwrap();
+
+        let info = resolver.resolve(4242);
+        assert_eq!(info.container_name.as_deref(), Some("vllm-server"));
+        assert_eq!(info.container_image.as_deref(), Some("vllm/vllm-openai:latest"));
+
+        assert_eq!(resolver.resolve(1), CgroupInfo::default());
+
+        resolver.retain_seen();
+        assert_eq!(resolver.containers.len(), 1);
+        // The container has exited: a pass without it evicts its entry
+        resolver.retain_seen();
+        assert!(resolver.containers.is_empty());
+    }
+}
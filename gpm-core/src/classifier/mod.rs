@@ -0,0 +1,526 @@
+pub mod cgroup;
+pub mod overrides;
+pub mod rules;
+
+use crate::accounting;
+use crate::config::ClassifierConfig;
+use crate::error::Result;
+use crate::gpu::{GpuMetrics, ProcessUtilization};
+use crate::tracker::{ProcessEvent, ProcessTracker};
+use cgroup::{CgroupInfo, CgroupResolver};
+use overrides::{ClassificationOverride, OverrideSet};
+use rules::{Classification, ProcessFacts, RuleSet};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use sysinfo::{ProcessRefreshKind, System, Users};
+use tracing::{debug, error, info};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkloadCategory {
+    Gaming,
+    LlmInference,
+    MlTraining,
+    GeneralCompute,
+    Unknown,
+}
+
+impl WorkloadCategory {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Gaming => "gaming",
+            Self::LlmInference => "llm_inference",
+            Self::MlTraining => "ml_training",
+            Self::GeneralCompute => "general_compute",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "gaming" => Self::Gaming,
+            "llm_inference" => Self::LlmInference,
+            "ml_training" => Self::MlTraining,
+            "general_compute" => Self::GeneralCompute,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassifiedProcess {
+    pub pid: u32,
+    pub name: String,
+    pub category: WorkloadCategory,
+    pub gpu_memory_mb: u64,
+    /// SM utilization of this process (not of the whole device)
+    pub gpu_utilization: u32,
+    /// Per-engine utilization reported by the driver; `None` when
+    /// `gpu_utilization` is estimated from the process's VRAM share
+    pub engine_utilization: Option<ProcessUtilization>,
+    pub command_line: String,
+    pub exe_path: Option<PathBuf>,
+    /// Rule that produced `category` (`default` if none matched)
+    pub rule: String,
+    pub confidence: f64,
+    pub evidence: Vec<String>,
+    /// Container / systemd unit the process runs in
+    pub cgroup: CgroupInfo,
+    pub uid: Option<u32>,
+    /// Username for `uid`, if it resolves on this host
+    pub user: Option<String>,
+    /// Share of the GPU's board power attributed to this process
+    pub power_watts: f64,
+    /// UUID of the MIG device the process runs on
+    #[serde(default)]
+    pub mig_device: Option<String>,
+}
+
+/// A classified process sample as stored in `process_events`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessEventRecord {
+    pub id: i64,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub duration_secs: u64,
+    pub process: ClassifiedProcess,
+}
+
+/// What the driver reports for one process, combined over its GPUs.
+#[derive(Debug, Clone, Default)]
+struct GpuSample {
+    gpu_memory: u64,
+    gpu_utilization: u32,
+    engine_utilization: Option<ProcessUtilization>,
+    power_watts: f64,
+    mig_device: Option<String>,
+}
+
+pub struct ProcessClassifier {
+    system: System,
+    users: Users,
+    config: ClassifierConfig,
+    rules: RuleSet,
+    overrides: OverrideSet,
+    cgroups: CgroupResolver,
+    rules_modified: Option<SystemTime>,
+    steam_library_paths: Vec<PathBuf>,
+    tracker: ProcessTracker,
+}
+
+impl ProcessClassifier {
+    /// Classifier with only the built-in rules.
+    pub fn new() -> Self {
+        Self::from_config(&ClassifierConfig::default()).expect("built-in classification rules are valid")
+    }
+
+    /// Built-in rules merged with `[classifier]` rules and the optional rules
+    /// file. Fails if any rule is invalid or the rules file is unreadable.
+    pub fn from_config(config: &ClassifierConfig) -> Result<Self> {
+        let steam_library_paths = Self::discover_steam_libraries();
+        let rules_modified = config.rules_file.as_deref().and_then(Self::modified_time);
+        let rules = Self::load_rules(config, &steam_library_paths)?;
+
+        info!("Loaded {} process classification rules", rules.len());
+
+        Ok(Self {
+            system: System::new(),
+            users: Users::new_with_refreshed_list(),
+            config: config.clone(),
+            rules,
+            overrides: OverrideSet::default(),
+            cgroups: CgroupResolver::new(),
+            rules_modified,
+            steam_library_paths,
+            tracker: ProcessTracker::new(),
+        })
+    }
+
+    fn load_rules(config: &ClassifierConfig, steam_library_paths: &[PathBuf]) -> Result<RuleSet> {
+        let mut user_rules = config.rules.clone();
+
+        if let Some(path) = &config.rules_file {
+            user_rules.extend(rules::load_rules_file(path)?);
+        }
+
+        RuleSet::merge(rules::builtin_rules(steam_library_paths), user_rules)
+    }
+
+    fn modified_time(path: &std::path::Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
+    /// Re-read the rules file if it changed since it was last loaded. An
+    /// invalid file is reported and the previous rules stay in effect.
+    pub fn reload_rules_if_changed(&mut self) {
+        let Some(path) = &self.config.rules_file else {
+            return;
+        };
+
+        let modified = Self::modified_time(path);
+        if modified.is_none() || modified == self.rules_modified {
+            return;
+        }
+        self.rules_modified = modified;
+
+        match Self::load_rules(&self.config, &self.steam_library_paths) {
+            Ok(rules) => {
+                info!("Reloaded {} process classification rules from {}", rules.len(), path.display());
+                self.rules = rules;
+            }
+            Err(e) => error!("Keeping previous classification rules: {}", e),
+        }
+    }
+
+    /// Replace the manual overrides (consulted before any rule). A no-op if
+    /// the list is unchanged.
+    pub fn set_overrides(&mut self, overrides: Vec<ClassificationOverride>) {
+        if self.overrides.specs().eq(overrides.iter()) {
+            return;
+        }
+
+        info!("Loaded {} classification override(s)", overrides.len());
+        self.overrides = OverrideSet::new(overrides);
+    }
+
+    pub fn classify_gpu_processes(
+        &mut self,
+        gpu_metrics: &[GpuMetrics],
+    ) -> Vec<ClassifiedProcess> {
+        if self.config.hot_reload {
+            self.reload_rules_if_changed();
+        }
+
+        self.system.refresh_processes_specifics(
+            sysinfo::ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing()
+                .with_cmd(sysinfo::UpdateKind::OnlyIfNotSet)
+                .with_exe(sysinfo::UpdateKind::OnlyIfNotSet)
+                .with_cwd(sysinfo::UpdateKind::OnlyIfNotSet)
+                .with_environ(sysinfo::UpdateKind::OnlyIfNotSet)
+                .with_user(sysinfo::UpdateKind::OnlyIfNotSet)
+        );
+
+        let mut classified = Vec::new();
+
+        for (pid, sample) in combine_gpu_samples(gpu_metrics) {
+            let cgroup = self.cgroups.resolve(pid);
+            let owner = self.process_owner(pid);
+            if let Some(process_info) = self.classify_process(pid, sample, cgroup, owner) {
+                classified.push(process_info);
+            }
+        }
+        self.cgroups.retain_seen();
+
+        classified
+    }
+
+    /// Feed this poll's classified processes to the lifetime tracker.
+    pub fn track_processes(
+        &mut self,
+        processes: &[ClassifiedProcess],
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Vec<ProcessEvent> {
+        self.tracker.observe(processes, now)
+    }
+
+    /// UID and username of `pid`. The user list is re-read when a UID is
+    /// not in it, so accounts created after startup still resolve.
+    fn process_owner(&mut self, pid: u32) -> (Option<u32>, Option<String>) {
+        let Some(uid) = self
+            .system
+            .process(sysinfo::Pid::from_u32(pid))
+            .and_then(|p| p.user_id())
+            .cloned()
+        else {
+            return (None, None);
+        };
+
+        if self.users.get_user_by_id(&uid).is_none() {
+            self.users.refresh();
+        }
+
+        let user = self.users.get_user_by_id(&uid).map(|u| u.name().to_string());
+        (uid_number(&uid), user)
+    }
+
+    fn classify_process(
+        &self,
+        pid: u32,
+        sample: GpuSample,
+        cgroup: CgroupInfo,
+        (uid, user): (Option<u32>, Option<String>),
+    ) -> Option<ClassifiedProcess> {
+        let GpuSample { gpu_memory, gpu_utilization, engine_utilization, power_watts, mig_device } = sample;
+        let process = self.system.process(sysinfo::Pid::from_u32(pid))?;
+
+        let name = process.name().to_string_lossy().to_string();
+        let command_line = process.cmd()
+            .iter()
+            .map(|s| s.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let exe_path = process.exe().map(|p| p.to_path_buf());
+        let parent_name = process
+            .parent()
+            .and_then(|ppid| self.system.process(ppid))
+            .map(|p| p.name().to_string_lossy().to_string());
+
+        let classification = self
+            .overrides
+            .classify(pid, &command_line, exe_path.as_deref())
+            .unwrap_or_else(|| {
+                self.determine_category(&ProcessFacts {
+                    name: &name,
+                    cmdline: &command_line,
+                    exe_path: exe_path.as_deref(),
+                    cwd: process.cwd(),
+                    environ: process.environ(),
+                    parent_name: parent_name.as_deref(),
+                    gpu_utilization,
+                })
+            });
+
+        debug!(
+            "Classified process: pid={} name={} category={:?} rule={} confidence={:.2} gpu_mem={}MB",
+            pid,
+            name,
+            classification.category,
+            classification.rule,
+            classification.confidence,
+            gpu_memory / 1024 / 1024
+        );
+
+        Some(ClassifiedProcess {
+            pid,
+            name,
+            category: classification.category,
+            gpu_memory_mb: gpu_memory / 1024 / 1024,
+            gpu_utilization,
+            engine_utilization,
+            command_line,
+            exe_path,
+            rule: classification.rule,
+            confidence: classification.confidence,
+            evidence: classification.evidence,
+            cgroup,
+            uid,
+            user,
+            power_watts,
+            mig_device,
+        })
+    }
+
+    fn determine_category(&self, facts: &ProcessFacts) -> Classification {
+        self.rules.classify(facts)
+    }
+
+    fn discover_steam_libraries() -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+
+        if let Some(home) = dirs::home_dir() {
+            let steam_dir = home.join(".steam/steam/steamapps/common");
+            if steam_dir.exists() {
+                paths.push(steam_dir);
+            }
+
+            let steam_flatpak = home.join(".var/app/com.valvesoftware.Steam/.steam/steam/steamapps/common");
+            if steam_flatpak.exists() {
+                paths.push(steam_flatpak);
+            }
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            let program_files = std::env::var("ProgramFiles(x86)")
+                .or_else(|_| std::env::var("ProgramFiles"))
+                .ok();
+
+            if let Some(pf) = program_files {
+                let steam_dir = PathBuf::from(pf)
+                    .join("Steam")
+                    .join("steamapps")
+                    .join("common");
+                if steam_dir.exists() {
+                    paths.push(steam_dir);
+                }
+            }
+        }
+
+        paths
+    }
+}
+
+
+/// Per-PID driver readings across all GPUs. Utilization comes from the
+/// driver's per-process samples when available, else the device's
+/// utilization scaled by the process's VRAM share.
+fn combine_gpu_samples(gpu_metrics: &[GpuMetrics]) -> HashMap<u32, GpuSample> {
+    let mut pid_to_metrics: HashMap<u32, GpuSample> = HashMap::new();
+
+    for metrics in gpu_metrics {
+        let shares = accounting::vram_shares(metrics);
+
+        for (proc, share) in metrics.processes.iter().zip(shares) {
+            let sample = pid_to_metrics.entry(proc.pid).or_default();
+            sample.gpu_memory += proc.used_gpu_memory;
+            sample.power_watts += metrics.power_usage as f64 * share;
+            if proc.mig_device.is_some() {
+                sample.mig_device.clone_from(&proc.mig_device);
+            }
+
+            // A process on several GPUs reports its busiest one
+            match proc.utilization {
+                Some(u) => {
+                    let engines = sample.engine_utilization.get_or_insert_with(Default::default);
+                    engines.sm = engines.sm.max(u.sm);
+                    engines.memory = engines.memory.max(u.memory);
+                    engines.encoder = engines.encoder.max(u.encoder);
+                    engines.decoder = engines.decoder.max(u.decoder);
+                    sample.gpu_utilization = sample.gpu_utilization.max(u.sm);
+                }
+                None => {
+                    let estimated = (metrics.utilization_gpu as f64 * share).round() as u32;
+                    sample.gpu_utilization = sample.gpu_utilization.max(estimated);
+                }
+            }
+        }
+    }
+
+    pid_to_metrics
+}
+
+#[cfg(unix)]
+fn uid_number(uid: &sysinfo::Uid) -> Option<u32> {
+    Some(**uid)
+}
+
+// Windows identifies users by SID, which has no numeric form
+#[cfg(not(unix))]
+fn uid_number(_uid: &sysinfo::Uid) -> Option<u32> {
+    None
+}
+
+impl Default for ProcessClassifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ollama_detection() {
+        let classifier = ProcessClassifier::new();
+        let category = classifier.determine_category(&ProcessFacts {
+            name: "ollama",
+            cmdline: "/usr/bin/ollama serve",
+            gpu_utilization: 50,
+            ..Default::default()
+        }).category;
+        assert_eq!(category, WorkloadCategory::LlmInference);
+    }
+
+    #[test]
+    fn test_python_ml_training() {
+        let classifier = ProcessClassifier::new();
+        let category = classifier.determine_category(&ProcessFacts {
+            name: "python3",
+            cmdline: "python3 train.py --model transformer --epochs 10",
+            gpu_utilization: 80,
+            ..Default::default()
+        }).category;
+        assert_eq!(category, WorkloadCategory::MlTraining);
+    }
+
+    #[test]
+    fn test_python_inference() {
+        let classifier = ProcessClassifier::new();
+        let category = classifier.determine_category(&ProcessFacts {
+            name: "python3",
+            cmdline: "python3 inference.py --model llama --generate",
+            gpu_utilization: 60,
+            ..Default::default()
+        }).category;
+        assert_eq!(category, WorkloadCategory::LlmInference);
+    }
+
+    #[test]
+    fn test_per_process_utilization() {
+        use crate::gpu::GpuProcess;
+
+        let process = |pid, gb: u64, utilization| GpuProcess {
+            pid,
+            name: String::new(),
+            used_gpu_memory: gb << 30,
+            utilization,
+            mig_device: None,
+        };
+        let gpu = |processes| GpuMetrics {
+            timestamp: chrono::Utc::now(),
+            gpu_id: 0,
+            name: "Test GPU".to_string(),
+            utilization_gpu: 90,
+            utilization_memory: 50,
+            memory_used: 6 << 30,
+            memory_total: 8 << 30,
+            temperature: 70,
+            power_usage: 300,
+            processes,
+            telemetry: Default::default(),
+            identity: Default::default(),
+            energy_wh: None,
+            mig_devices: Vec::new(),
+        };
+
+        // Without driver samples, device utilization is split by VRAM
+        let estimated = combine_gpu_samples(&[gpu(vec![process(1, 3, None), process(2, 2, None), process(3, 1, None)])]);
+        assert_eq!(estimated[&1].gpu_utilization, 45);
+        assert_eq!(estimated[&3].gpu_utilization, 15);
+        assert_eq!(estimated[&1].engine_utilization, None);
+        assert_eq!(estimated[&2].power_watts, 100.0);
+
+        let engines = ProcessUtilization { sm: 70, memory: 20, encoder: 0, decoder: 5 };
+        let sampled = combine_gpu_samples(&[gpu(vec![process(1, 3, Some(engines)), process(2, 3, Some(Default::default()))])]);
+        assert_eq!(sampled[&1].gpu_utilization, 70);
+        assert_eq!(sampled[&1].engine_utilization, Some(engines));
+        assert_eq!(sampled[&2].gpu_utilization, 0);
+    }
+
+    #[test]
+    fn test_rules_file_hot_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rules.yaml");
+        let write_rules = |category: &str| {
+            std::fs::write(
+                &path,
+                format!("rules:\n  - name: custom_binary\n    category: {}\n    process_name: \"^trainer$\"\n", category),
+            )
+            .unwrap();
+        };
+
+        write_rules("ml_training");
+        let config = ClassifierConfig {
+            rules_file: Some(path.clone()),
+            ..Default::default()
+        };
+        let mut classifier = ProcessClassifier::from_config(&config).unwrap();
+        let facts = ProcessFacts { name: "trainer", ..Default::default() };
+        assert_eq!(classifier.determine_category(&facts).category, WorkloadCategory::MlTraining);
+
+        write_rules("llm_inference");
+        // Make the change visible on filesystems with coarse mtimes
+        classifier.rules_modified = None;
+        classifier.reload_rules_if_changed();
+        assert_eq!(classifier.determine_category(&facts).category, WorkloadCategory::LlmInference);
+
+        std::fs::write(&path, "rules: [{ name: broken, category: gaming, cmdline: \"(\" }]").unwrap();
+        classifier.rules_modified = None;
+        classifier.reload_rules_if_changed();
+        assert_eq!(classifier.determine_category(&facts).category, WorkloadCategory::LlmInference);
+    }
+}
@@ -0,0 +1,203 @@
+use super::rules::Classification;
+use super::WorkloadCategory;
+use crate::error::{GpmError, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverrideKind {
+    /// Full executable path or executable file name
+    Exe,
+    /// Regex on the command line
+    Cmdline,
+    /// A single process ID
+    Pid,
+}
+
+impl OverrideKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Exe => "exe",
+            Self::Cmdline => "cmdline",
+            Self::Pid => "pid",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "exe" => Some(Self::Exe),
+            "cmdline" => Some(Self::Cmdline),
+            "pid" => Some(Self::Pid),
+            _ => None,
+        }
+    }
+}
+
+/// A manual "this process is X" pin. Overrides are checked before any
+/// classification rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClassificationOverride {
+    pub id: String,
+    pub kind: OverrideKind,
+    pub pattern: String,
+    pub category: WorkloadCategory,
+    pub note: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ClassificationOverride {
+    pub fn new(kind: OverrideKind, pattern: String, category: WorkloadCategory, note: Option<String>) -> Result<Self> {
+        let o = Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            pattern,
+            category,
+            note,
+            created_at: chrono::Utc::now(),
+        };
+        CompiledOverride::compile(o.clone())?;
+        Ok(o)
+    }
+}
+
+enum Matcher {
+    Exe(String),
+    Cmdline(Regex),
+    Pid(u32),
+}
+
+struct CompiledOverride {
+    spec: ClassificationOverride,
+    matcher: Matcher,
+}
+
+impl CompiledOverride {
+    fn compile(spec: ClassificationOverride) -> Result<Self> {
+        let matcher = match spec.kind {
+            OverrideKind::Exe if spec.pattern.is_empty() => {
+                return Err(GpmError::InvalidData("exe override needs a path or file name".to_string()));
+            }
+            OverrideKind::Exe => Matcher::Exe(spec.pattern.clone()),
+            OverrideKind::Cmdline => Matcher::Cmdline(
+                Regex::new(&spec.pattern)
+                    .map_err(|e| GpmError::InvalidData(format!("invalid cmdline pattern: {}", e)))?,
+            ),
+            OverrideKind::Pid => Matcher::Pid(
+                spec.pattern
+                    .parse()
+                    .map_err(|_| GpmError::InvalidData(format!("invalid pid: {}", spec.pattern)))?,
+            ),
+        };
+
+        Ok(Self { spec, matcher })
+    }
+
+    fn matches(&self, pid: u32, cmdline: &str, exe_path: Option<&Path>) -> bool {
+        match &self.matcher {
+            Matcher::Pid(p) => *p == pid,
+            Matcher::Cmdline(re) => re.is_match(cmdline),
+            Matcher::Exe(exe) => exe_path.is_some_and(|path| {
+                path.as_os_str() == exe.as_str() || path.file_name().is_some_and(|n| n == exe.as_str())
+            }),
+        }
+    }
+}
+
+/// The active overrides, in creation order; the first match wins.
+#[derive(Default)]
+pub struct OverrideSet {
+    overrides: Vec<CompiledOverride>,
+}
+
+impl OverrideSet {
+    /// Compile `overrides`, skipping (and reporting) any that are invalid so
+    /// one bad row cannot disable the rest.
+    pub fn new(overrides: Vec<ClassificationOverride>) -> Self {
+        let overrides = overrides
+            .into_iter()
+            .filter_map(|o| {
+                let id = o.id.clone();
+                CompiledOverride::compile(o)
+                    .map_err(|e| warn!("Ignoring classification override {}: {}", id, e))
+                    .ok()
+            })
+            .collect();
+
+        Self { overrides }
+    }
+
+    pub fn specs(&self) -> impl Iterator<Item = &ClassificationOverride> {
+        self.overrides.iter().map(|o| &o.spec)
+    }
+
+    /// Classify a live process.
+    pub fn classify(&self, pid: u32, cmdline: &str, exe_path: Option<&Path>) -> Option<Classification> {
+        self.classify_at(pid, cmdline, exe_path, chrono::Utc::now())
+    }
+
+    /// Classify a sample stored at `timestamp`. PID overrides only apply from
+    /// their creation on, since earlier samples may belong to another process
+    /// that had the same PID.
+    pub fn classify_at(
+        &self,
+        pid: u32,
+        cmdline: &str,
+        exe_path: Option<&Path>,
+        timestamp: chrono::DateTime<chrono::Utc>,
+    ) -> Option<Classification> {
+        self.overrides
+            .iter()
+            .filter(|o| o.spec.kind != OverrideKind::Pid || timestamp >= o.spec.created_at)
+            .find(|o| o.matches(pid, cmdline, exe_path))
+            .map(|o| override_classification(&o.spec))
+    }
+}
+
+fn override_classification(spec: &ClassificationOverride) -> Classification {
+    Classification {
+        category: spec.category,
+        rule: format!("override:{}", spec.id),
+        confidence: 1.0,
+        evidence: vec![format!("manual override: {} {}", spec.kind.as_str(), spec.pattern)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_override_matching() {
+        let exe = ClassificationOverride::new(
+            OverrideKind::Exe,
+            "trainer".to_string(),
+            WorkloadCategory::MlTraining,
+            None,
+        )
+        .unwrap();
+        let pid = ClassificationOverride::new(OverrideKind::Pid, "77".to_string(), WorkloadCategory::Gaming, None).unwrap();
+        let set = OverrideSet::new(vec![exe.clone(), pid]);
+
+        let classification = set
+            .classify(12, "trainer --epochs 3", Some(Path::new("/opt/team/bin/trainer")))
+            .unwrap();
+        assert_eq!(classification.category, WorkloadCategory::MlTraining);
+        assert_eq!(classification.rule, format!("override:{}", exe.id));
+        assert_eq!(classification.confidence, 1.0);
+
+        assert_eq!(set.classify(77, "", None).unwrap().category, WorkloadCategory::Gaming);
+        assert!(set.classify(78, "", None).is_none());
+        let before = chrono::Utc::now() - chrono::Duration::hours(1);
+        assert!(set.classify_at(77, "", None, before).is_none());
+        assert!(set.classify_at(12, "trainer", Some(Path::new("trainer")), before).is_some());
+    }
+
+    #[test]
+    fn test_invalid_overrides_rejected() {
+        assert!(ClassificationOverride::new(OverrideKind::Pid, "abc".to_string(), WorkloadCategory::Gaming, None).is_err());
+        assert!(ClassificationOverride::new(OverrideKind::Cmdline, "(".to_string(), WorkloadCategory::Gaming, None).is_err());
+    }
+}
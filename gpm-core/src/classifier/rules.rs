@@ -0,0 +1,512 @@
+use super::WorkloadCategory;
+use crate::error::{GpmError, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+const ML_KEYWORDS: &str =
+    r"transformers|torch|tensorflow|keras|pytorch|jax|flax|diffusers|vllm|llama|huggingface|model\.py|train\.py";
+const INFERENCE_KEYWORDS: &str = r"generate|inference|predict|serve|api";
+
+/// A user-definable classification rule. Every matcher that is set must
+/// match for the rule to apply; rules are tried highest priority first and
+/// the first match wins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassificationRule {
+    pub name: String,
+    pub category: WorkloadCategory,
+
+    #[serde(default)]
+    pub priority: i32,
+
+    /// Regex on the process name
+    #[serde(default)]
+    pub process_name: Option<String>,
+
+    /// Regex on the full command line
+    #[serde(default)]
+    pub cmdline: Option<String>,
+
+    /// Regex on the executable path
+    #[serde(default)]
+    pub exe_path: Option<String>,
+
+    /// Regex on the working directory
+    #[serde(default)]
+    pub cwd: Option<String>,
+
+    /// `KEY` (variable is set) or `KEY=regex` (value matches)
+    #[serde(default)]
+    pub env: Option<String>,
+
+    /// Regex on the parent process name
+    #[serde(default)]
+    pub parent: Option<String>,
+
+    #[serde(default)]
+    pub min_gpu_utilization: Option<u32>,
+
+    /// 0.0-1.0; derived from the number of matchers when unset
+    #[serde(default)]
+    pub confidence: Option<f64>,
+}
+
+/// Outcome of running a process through the rules: the category plus why.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Classification {
+    pub category: WorkloadCategory,
+    /// Name of the matching rule, or `default` when nothing matched
+    pub rule: String,
+    pub confidence: f64,
+    /// What the matching rule saw, one entry per matcher
+    pub evidence: Vec<String>,
+}
+
+impl Classification {
+    pub fn unmatched() -> Self {
+        Self {
+            category: WorkloadCategory::GeneralCompute,
+            rule: "default".to_string(),
+            confidence: 0.2,
+            evidence: vec!["no classification rule matched".to_string()],
+        }
+    }
+}
+
+/// What is known about a process when rules are evaluated.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessFacts<'a> {
+    pub name: &'a str,
+    pub cmdline: &'a str,
+    pub exe_path: Option<&'a Path>,
+    pub cwd: Option<&'a Path>,
+    pub environ: &'a [OsString],
+    pub parent_name: Option<&'a str>,
+    pub gpu_utilization: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    rules: Vec<ClassificationRule>,
+}
+
+struct CompiledRule {
+    rule: ClassificationRule,
+    process_name: Option<Regex>,
+    cmdline: Option<Regex>,
+    exe_path: Option<Regex>,
+    cwd: Option<Regex>,
+    env: Option<(String, Option<Regex>)>,
+    parent: Option<Regex>,
+}
+
+impl CompiledRule {
+    fn compile(rule: ClassificationRule) -> Result<Self> {
+        let pattern = |field: &str, value: &Option<String>| -> Result<Option<Regex>> {
+            value
+                .as_deref()
+                .map(|p| {
+                    Regex::new(p).map_err(|e| {
+                        invalid(format!("rule '{}': invalid {} pattern: {}", rule.name, field, e))
+                    })
+                })
+                .transpose()
+        };
+
+        let process_name = pattern("process_name", &rule.process_name)?;
+        let cmdline = pattern("cmdline", &rule.cmdline)?;
+        let exe_path = pattern("exe_path", &rule.exe_path)?;
+        let cwd = pattern("cwd", &rule.cwd)?;
+        let parent = pattern("parent", &rule.parent)?;
+
+        let env = match rule.env.as_deref() {
+            None => None,
+            Some(spec) => {
+                let (key, value) = match spec.split_once('=') {
+                    Some((key, value)) => (key, Some(value)),
+                    None => (spec, None),
+                };
+                if key.is_empty() {
+                    return Err(invalid(format!("rule '{}': env matcher needs a variable name", rule.name)));
+                }
+                let value = pattern("env", &value.map(str::to_string))?;
+                Some((key.to_string(), value))
+            }
+        };
+
+        if rule.name.is_empty() {
+            return Err(invalid("classification rule without a name".to_string()));
+        }
+
+        let has_matcher = process_name.is_some()
+            || cmdline.is_some()
+            || exe_path.is_some()
+            || cwd.is_some()
+            || env.is_some()
+            || parent.is_some()
+            || rule.min_gpu_utilization.is_some();
+        if !has_matcher {
+            return Err(invalid(format!("rule '{}' has no matchers", rule.name)));
+        }
+
+        if rule.confidence.is_some_and(|c| !(0.0..=1.0).contains(&c)) {
+            return Err(invalid(format!("rule '{}': confidence must be between 0 and 1", rule.name)));
+        }
+
+        Ok(Self {
+            rule,
+            process_name,
+            cmdline,
+            exe_path,
+            cwd,
+            env,
+            parent,
+        })
+    }
+
+    /// Evidence for each matcher if all of them match, `None` otherwise.
+    fn evidence(&self, facts: &ProcessFacts) -> Option<Vec<String>> {
+        fn check(evidence: &mut Vec<String>, field: &str, regex: &Option<Regex>, value: Option<&str>) -> bool {
+            let Some(regex) = regex else {
+                return true;
+            };
+            match value.and_then(|v| regex.find(v)) {
+                Some(m) => {
+                    evidence.push(format!("{} matched \"{}\" (/{}/)", field, m.as_str(), regex.as_str()));
+                    true
+                }
+                None => false,
+            }
+        }
+
+        let mut evidence = Vec::new();
+
+        if let Some(min) = self.rule.min_gpu_utilization {
+            if facts.gpu_utilization < min {
+                return None;
+            }
+            evidence.push(format!("gpu_utilization {}% >= {}%", facts.gpu_utilization, min));
+        }
+
+        let exe_path = facts.exe_path.map(|p| p.to_string_lossy());
+        let cwd = facts.cwd.map(|p| p.to_string_lossy());
+
+        let matched = check(&mut evidence, "process_name", &self.process_name, Some(facts.name))
+            && check(&mut evidence, "cmdline", &self.cmdline, Some(facts.cmdline))
+            && check(&mut evidence, "exe_path", &self.exe_path, exe_path.as_deref())
+            && check(&mut evidence, "cwd", &self.cwd, cwd.as_deref())
+            && check(&mut evidence, "parent", &self.parent, facts.parent_name)
+            && self.env_matches(facts.environ, &mut evidence);
+
+        matched.then_some(evidence)
+    }
+
+    fn env_matches(&self, environ: &[OsString], evidence: &mut Vec<String>) -> bool {
+        let Some((key, value)) = &self.env else {
+            return true;
+        };
+
+        let found = environ.iter().find_map(|entry| {
+            let entry = entry.to_string_lossy();
+            match entry.split_once('=') {
+                Some((k, v)) if k == key && value.as_ref().is_none_or(|re| re.is_match(v)) => {
+                    Some(entry.to_string())
+                }
+                _ => None,
+            }
+        });
+
+        match found {
+            Some(entry) => {
+                evidence.push(format!("env {}", entry));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn confidence(&self, matchers: usize) -> f64 {
+        self.rule
+            .confidence
+            .unwrap_or_else(|| (0.5 + 0.1 * matchers as f64).min(0.9))
+    }
+}
+
+/// The effective, priority-ordered rule list.
+pub struct RuleSet {
+    rules: Vec<CompiledRule>,
+}
+
+impl RuleSet {
+    /// Merge `user` rules over `builtin` ones (a user rule replaces the
+    /// built-in rule with the same name) and validate every pattern.
+    pub fn merge(builtin: Vec<ClassificationRule>, user: Vec<ClassificationRule>) -> Result<Self> {
+        let mut merged: Vec<ClassificationRule> = builtin
+            .into_iter()
+            .filter(|b| !user.iter().any(|u| u.name == b.name))
+            .collect();
+        // User rules go first so they win ties against built-ins
+        merged.splice(0..0, user);
+
+        let mut rules = merged
+            .into_iter()
+            .map(CompiledRule::compile)
+            .collect::<Result<Vec<_>>>()?;
+        rules.sort_by_key(|r| std::cmp::Reverse(r.rule.priority));
+
+        Ok(Self { rules })
+    }
+
+    /// Classify with the first matching rule, falling back to
+    /// `GeneralCompute` when nothing matches.
+    pub fn classify(&self, facts: &ProcessFacts) -> Classification {
+        self.rules
+            .iter()
+            .find_map(|r| {
+                r.evidence(facts).map(|evidence| Classification {
+                    category: r.rule.category,
+                    rule: r.rule.name.clone(),
+                    confidence: r.confidence(evidence.len()),
+                    evidence,
+                })
+            })
+            .unwrap_or_else(Classification::unmatched)
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// Read a `rules = [...]` list from a TOML or YAML file (by extension).
+pub fn load_rules_file(path: &Path) -> Result<Vec<ClassificationRule>> {
+    if !path.exists() {
+        return Err(invalid(format!("classification rules file {} not found", path.display())));
+    }
+
+    let file: RulesFile = config::Config::builder()
+        .add_source(config::File::from(path))
+        .build()?
+        .try_deserialize()?;
+
+    Ok(file.rules)
+}
+
+/// The heuristics GPM ships with, expressed as rules so that config rules
+/// can override them by name or outrank them by priority.
+pub fn builtin_rules(steam_library_paths: &[PathBuf]) -> Vec<ClassificationRule> {
+    let rule = |name: &str, category, priority| ClassificationRule {
+        name: name.to_string(),
+        category,
+        priority,
+        process_name: None,
+        cmdline: None,
+        exe_path: None,
+        cwd: None,
+        env: None,
+        parent: None,
+        min_gpu_utilization: None,
+        confidence: None,
+    };
+
+    let mut rules = vec![
+        ClassificationRule {
+            process_name: Some(r"(?i)ollama".to_string()),
+            confidence: Some(0.95),
+            ..rule("ollama", WorkloadCategory::LlmInference, 100)
+        },
+        ClassificationRule {
+            cmdline: Some(r"(?i)tensorflow|torch|jax|mxnet|deepspeed".to_string()),
+            confidence: Some(0.8),
+            ..rule("ml_framework", WorkloadCategory::MlTraining, 90)
+        },
+        ClassificationRule {
+            process_name: Some(r"(?i)python".to_string()),
+            cmdline: Some(format!(
+                r"(?i)({ml}).*({inf})|({inf}).*({ml})",
+                ml = ML_KEYWORDS,
+                inf = INFERENCE_KEYWORDS
+            )),
+            confidence: Some(0.6),
+            ..rule("python_ml_inference", WorkloadCategory::LlmInference, 80)
+        },
+        ClassificationRule {
+            process_name: Some(r"(?i)python".to_string()),
+            cmdline: Some(format!(r"(?i){}", ML_KEYWORDS)),
+            confidence: Some(0.7),
+            ..rule("python_ml_training", WorkloadCategory::MlTraining, 70)
+        },
+    ];
+
+    if !steam_library_paths.is_empty() {
+        let libraries = steam_library_paths
+            .iter()
+            .map(|p| regex::escape(&p.to_string_lossy()))
+            .collect::<Vec<_>>()
+            .join("|");
+
+        rules.push(ClassificationRule {
+            exe_path: Some(format!("^({})", libraries)),
+            confidence: Some(0.9),
+            ..rule("steam_library", WorkloadCategory::Gaming, 60)
+        });
+    }
+
+    rules.push(ClassificationRule {
+        exe_path: Some(r"(?i)game".to_string()),
+        confidence: Some(0.6),
+        ..rule("game_path", WorkloadCategory::Gaming, 50)
+    });
+    rules.push(ClassificationRule {
+        process_name: Some(r"(?i)\.exe$".to_string()),
+        min_gpu_utilization: Some(61),
+        confidence: Some(0.5),
+        ..rule("game_executable", WorkloadCategory::Gaming, 40)
+    });
+
+    rules
+}
+
+fn invalid(message: String) -> GpmError {
+    GpmError::ConfigError(config::ConfigError::Message(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_rule(name: &str, category: WorkloadCategory, priority: i32) -> ClassificationRule {
+        ClassificationRule {
+            name: name.to_string(),
+            category,
+            priority,
+            process_name: None,
+            cmdline: None,
+            exe_path: None,
+            cwd: None,
+            env: None,
+            parent: None,
+            min_gpu_utilization: None,
+            confidence: None,
+        }
+    }
+
+    #[test]
+    fn test_user_rules_outrank_and_replace_builtins() {
+        let deepspeed = ClassificationRule {
+            cmdline: Some("deepspeed".to_string()),
+            ..user_rule("deepspeed_serving", WorkloadCategory::LlmInference, 95)
+        };
+        let ollama = ClassificationRule {
+            process_name: Some("ollama".to_string()),
+            ..user_rule("ollama", WorkloadCategory::GeneralCompute, 100)
+        };
+        let rules = RuleSet::merge(builtin_rules(&[]), vec![deepspeed, ollama]).unwrap();
+
+        let facts = ProcessFacts {
+            name: "python3",
+            cmdline: "deepspeed --num_gpus 2 serve.py",
+            ..Default::default()
+        };
+        assert_eq!(rules.classify(&facts).rule, "deepspeed_serving");
+
+        let facts = ProcessFacts { name: "ollama", ..Default::default() };
+        assert_eq!(rules.classify(&facts).category, WorkloadCategory::GeneralCompute);
+    }
+
+    #[test]
+    fn test_deepspeed_launcher_is_ml_training() {
+        let rules = RuleSet::merge(builtin_rules(&[]), Vec::new()).unwrap();
+
+        let facts = ProcessFacts {
+            name: "deepspeed",
+            cmdline: "/usr/bin/deepspeed --num_gpus 8 finetune.py --zero_stage 3",
+            ..Default::default()
+        };
+        let classification = rules.classify(&facts);
+        assert_eq!(classification.rule, "ml_framework");
+        assert_eq!(classification.category, WorkloadCategory::MlTraining);
+    }
+
+    #[test]
+    fn test_env_cwd_and_parent_matchers() {
+        let rule = ClassificationRule {
+            env: Some("SLURM_JOB_ID".to_string()),
+            cwd: Some("^/scratch/".to_string()),
+            parent: Some("^(torchrun|srun)$".to_string()),
+            ..user_rule("slurm_training", WorkloadCategory::MlTraining, 10)
+        };
+        let rules = RuleSet::merge(Vec::new(), vec![rule]).unwrap();
+
+        let environ = vec![OsString::from("PATH=/usr/bin"), OsString::from("SLURM_JOB_ID=1234")];
+        let mut facts = ProcessFacts {
+            name: "trainer",
+            cwd: Some(Path::new("/scratch/run1")),
+            environ: &environ,
+            parent_name: Some("torchrun"),
+            ..Default::default()
+        };
+        let classification = rules.classify(&facts);
+        assert_eq!(classification.rule, "slurm_training");
+        assert!(classification.evidence.contains(&"env SLURM_JOB_ID=1234".to_string()));
+        assert_eq!(classification.confidence, 0.8);
+
+        facts.parent_name = Some("bash");
+        assert_eq!(rules.classify(&facts), Classification::unmatched());
+    }
+
+    #[test]
+    fn test_classification_explains_match() {
+        let rules = RuleSet::merge(builtin_rules(&[]), Vec::new()).unwrap();
+        let facts = ProcessFacts {
+            name: "python3",
+            cmdline: "python3 loader.py --source s3://datasets --api-key x --model llama",
+            ..Default::default()
+        };
+
+        let classification = rules.classify(&facts);
+        assert_eq!(classification.rule, "python_ml_inference");
+        assert_eq!(classification.confidence, 0.6);
+        assert!(classification.evidence[1].starts_with("cmdline matched \"api-key x --model llama\""));
+    }
+
+    #[test]
+    fn test_invalid_rules_are_rejected() {
+        let bad_regex = ClassificationRule {
+            cmdline: Some("(unclosed".to_string()),
+            ..user_rule("bad", WorkloadCategory::MlTraining, 0)
+        };
+        assert!(RuleSet::merge(Vec::new(), vec![bad_regex]).is_err());
+
+        let no_matchers = user_rule("empty", WorkloadCategory::MlTraining, 0);
+        assert!(RuleSet::merge(Vec::new(), vec![no_matchers]).is_err());
+    }
+
+    #[test]
+    fn test_load_rules_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rules.toml");
+        std::fs::write(
+            &path,
+            r#"
+            [[rules]]
+            name = "torchrun"
+            category = "ml_training"
+            priority = 95
+            parent = "^torchrun$"
+            "#,
+        )
+        .unwrap();
+
+        let rules = load_rules_file(&path).unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].category, WorkloadCategory::MlTraining);
+        assert!(load_rules_file(&dir.path().join("missing.toml")).is_err());
+    }
+}
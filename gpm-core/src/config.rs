@@ -1,3 +1,6 @@
+use crate::alerts::notify::NotificationSinkConfig;
+use crate::alerts::AlertRule;
+use crate::classifier::rules::ClassificationRule;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -9,6 +12,12 @@ pub struct GpmConfig {
     pub storage: StorageConfig,
     pub telemetry: TelemetryConfig,
     pub alerts: AlertConfig,
+
+    #[serde(default)]
+    pub classifier: ClassifierConfig,
+
+    #[serde(default)]
+    pub energy: EnergyConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +36,111 @@ pub struct GpuConfig {
 
     #[serde(default)]
     pub fallback_to_nvidia_smi: bool,
+
+    #[serde(default)]
+    pub backend: GpuBackendKind,
+
+    #[serde(default)]
+    pub simulated: SimulatedGpuConfig,
+
+    #[serde(default)]
+    pub replay_path: Option<PathBuf>,
+
+    #[serde(default = "default_true")]
+    pub replay_loop: bool,
+
+    /// Where the AMD and Intel backends find `sys` and `proc`; set it to the
+    /// host's mount point when GPM runs in a container
+    #[serde(default = "default_root_prefix")]
+    pub root_prefix: PathBuf,
+
+    /// How often NVML re-reads the device list to notice hot-plugged or
+    /// removed GPUs; 0 only re-enumerates after a device fails
+    #[serde(default = "default_reenumerate_interval_secs")]
+    pub reenumerate_interval_secs: u64,
+
+    /// Record NVIDIA Xid, ECC and state-change events, from NVML or else
+    /// NVRM lines in the kernel log
+    #[serde(default = "default_true")]
+    pub watch_events: bool,
+}
+
+/// Which GPU backend to use. `Auto` picks NVML, then nvidia-smi when
+/// `fallback_to_nvidia_smi` is set and the NVIDIA driver is loaded, then AMD
+/// sysfs when amdgpu cards are present, then nvidia-smi per
+/// `fallback_to_nvidia_smi`, then Intel unless the NVIDIA driver is loaded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuBackendKind {
+    #[default]
+    Auto,
+    Nvml,
+    NvidiaSmi,
+    /// amdgpu sysfs/hwmon, with per-process VRAM from DRM fdinfo
+    Amd,
+    /// i915/xe sysfs, with engine busy time from DRM fdinfo
+    Intel,
+    Simulated,
+    Replay,
+}
+
+/// Parameters for the synthetic GPU backend used in tests and demos.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatedGpuConfig {
+    #[serde(default = "default_sim_gpu_count")]
+    pub gpu_count: u32,
+
+    #[serde(default = "default_sim_gpu_name")]
+    pub gpu_name: String,
+
+    #[serde(default = "default_sim_memory_total_mb")]
+    pub memory_total_mb: u64,
+
+    #[serde(default = "default_sim_base_utilization")]
+    pub base_utilization: u32,
+
+    #[serde(default = "default_sim_utilization_jitter")]
+    pub utilization_jitter: u32,
+
+    #[serde(default = "default_sim_idle_temperature")]
+    pub idle_temperature: u32,
+
+    #[serde(default = "default_sim_max_power")]
+    pub max_power_watts: u32,
+
+    #[serde(default)]
+    pub processes: Vec<SimulatedProcessConfig>,
+
+    #[serde(default)]
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatedProcessConfig {
+    pub pid: u32,
+    pub name: String,
+
+    #[serde(default)]
+    pub gpu_id: u32,
+
+    #[serde(default)]
+    pub memory_mb: u64,
+}
+
+impl Default for SimulatedGpuConfig {
+    fn default() -> Self {
+        Self {
+            gpu_count: default_sim_gpu_count(),
+            gpu_name: default_sim_gpu_name(),
+            memory_total_mb: default_sim_memory_total_mb(),
+            base_utilization: default_sim_base_utilization(),
+            utilization_jitter: default_sim_utilization_jitter(),
+            idle_temperature: default_sim_idle_temperature(),
+            max_power_watts: default_sim_max_power(),
+            processes: Vec::new(),
+            seed: 0,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +162,16 @@ pub struct OllamaConfig {
 
     #[serde(default = "default_ollama_backend")]
     pub backend_url: String,
+
+    /// GPU (index or UUID) whose samples are attributed to LLM sessions;
+    /// by default, the GPUs an Ollama process was last seen on
+    #[serde(default)]
+    pub gpu: Option<String>,
+
+    /// How long a proxied response may pass no data before its session is
+    /// recorded as abandoned
+    #[serde(default = "default_session_timeout_secs")]
+    pub session_timeout_secs: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -87,6 +211,86 @@ pub struct AlertConfig {
 
     #[serde(default)]
     pub enable_desktop_notifications: bool,
+
+    #[serde(default = "default_alert_duration")]
+    pub duration_secs: u64,
+
+    #[serde(default = "default_temp_hysteresis")]
+    pub temp_hysteresis_celsius: f64,
+
+    #[serde(default = "default_mem_hysteresis")]
+    pub memory_hysteresis_percent: f64,
+
+    #[serde(default)]
+    pub rules: Vec<AlertRule>,
+
+    #[serde(default)]
+    pub sinks: Vec<NotificationSinkConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassifierConfig {
+    /// Extra rules in a separate TOML or YAML file (`[[rules]]` entries)
+    #[serde(default)]
+    pub rules_file: Option<PathBuf>,
+
+    /// Re-read `rules_file` when it changes; inline `rules` are only read at startup
+    #[serde(default = "default_true")]
+    pub hot_reload: bool,
+
+    #[serde(default)]
+    pub rules: Vec<ClassificationRule>,
+}
+
+impl Default for ClassifierConfig {
+    fn default() -> Self {
+        Self {
+            rules_file: None,
+            hot_reload: true,
+            rules: Vec::new(),
+        }
+    }
+}
+
+/// Electricity tariff and carbon factor for energy reports.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnergyConfig {
+    /// Price per kWh outside any `time_of_use` window (the flat rate)
+    #[serde(default)]
+    pub price_per_kwh: f64,
+
+    #[serde(default = "default_currency")]
+    pub currency: String,
+
+    /// Time-of-use windows in local time; the first window containing the
+    /// hour sets the price
+    #[serde(default)]
+    pub time_of_use: Vec<TariffWindow>,
+
+    /// Grams of CO2e emitted per kWh, if known
+    #[serde(default)]
+    pub carbon_intensity_g_per_kwh: Option<f64>,
+}
+
+impl Default for EnergyConfig {
+    fn default() -> Self {
+        Self {
+            price_per_kwh: 0.0,
+            currency: default_currency(),
+            time_of_use: Vec::new(),
+            carbon_intensity_g_per_kwh: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TariffWindow {
+    /// First hour of the window, 0-23
+    pub start_hour: u32,
+    /// Hour the window ends (exclusive); may be less than `start_hour` to
+    /// wrap past midnight
+    pub end_hour: u32,
+    pub price_per_kwh: f64,
 }
 
 impl Default for GpmConfig {
@@ -99,6 +303,13 @@ impl Default for GpmConfig {
             gpu: GpuConfig {
                 enable_nvml: true,
                 fallback_to_nvidia_smi: false,
+                backend: GpuBackendKind::Auto,
+                simulated: SimulatedGpuConfig::default(),
+                replay_path: None,
+                replay_loop: true,
+                root_prefix: default_root_prefix(),
+                reenumerate_interval_secs: default_reenumerate_interval_secs(),
+                watch_events: true,
             },
             ollama: OllamaConfig {
                 enabled: true,
@@ -107,6 +318,8 @@ impl Default for GpmConfig {
                 enable_proxy: true,
                 proxy_port: default_proxy_port(),
                 backend_url: default_ollama_backend(),
+                gpu: None,
+                session_timeout_secs: default_session_timeout_secs(),
             },
             storage: StorageConfig {
                 retention_days: default_retention_days(),
@@ -123,7 +336,14 @@ impl Default for GpmConfig {
                 temp_threshold_celsius: default_temp_threshold(),
                 memory_threshold_percent: default_mem_threshold(),
                 enable_desktop_notifications: false,
+                duration_secs: default_alert_duration(),
+                temp_hysteresis_celsius: default_temp_hysteresis(),
+                memory_hysteresis_percent: default_mem_hysteresis(),
+                rules: Vec::new(),
+                sinks: Vec::new(),
             },
+            classifier: ClassifierConfig::default(),
+            energy: EnergyConfig::default(),
         }
     }
 }
@@ -178,8 +398,22 @@ fn default_ollama_backend() -> String { "http://localhost:11435".to_string() }
 fn default_metrics_port() -> u16 { 9090 }
 fn default_temp_threshold() -> f64 { 85.0 }
 fn default_mem_threshold() -> f64 { 90.0 }
+fn default_alert_duration() -> u64 { 30 }
+fn default_temp_hysteresis() -> f64 { 5.0 }
+fn default_mem_hysteresis() -> f64 { 5.0 }
 fn default_otlp_endpoint() -> String { "http://localhost:4317".to_string() }
 fn default_true() -> bool { true }
+fn default_root_prefix() -> PathBuf { PathBuf::from("/") }
+fn default_reenumerate_interval_secs() -> u64 { 60 }
+fn default_session_timeout_secs() -> u64 { 300 }
+fn default_currency() -> String { "USD".to_string() }
+fn default_sim_gpu_count() -> u32 { 1 }
+fn default_sim_gpu_name() -> String { "Simulated GPU".to_string() }
+fn default_sim_memory_total_mb() -> u64 { 24576 }
+fn default_sim_base_utilization() -> u32 { 50 }
+fn default_sim_utilization_jitter() -> u32 { 20 }
+fn default_sim_idle_temperature() -> u32 { 40 }
+fn default_sim_max_power() -> u32 { 300 }
 
 fn default_data_dir() -> PathBuf {
     dirs::data_local_dir()
@@ -43,6 +43,9 @@ pub enum GpmError {
 
     #[error("Proxy error: {0}")]
     ProxyError(String),
+
+    #[error("Notification error: {0}")]
+    NotificationError(String),
 }
 
 impl From<prometheus::Error> for GpmError {
@@ -0,0 +1,280 @@
+use super::fdinfo::{process_name, scan_drm_clients, DrmClient};
+use super::sysfs::{find_cards, hwmon_dir, pcie_gen, read_string, read_u64, DrmCard};
+use super::{GpuBackend, GpuIdentity, GpuMetrics, GpuProcess, GpuTelemetry};
+use crate::error::{GpmError, Result};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+const AMD_VENDOR_ID: &str = "0x1002";
+
+/// Reads AMD GPUs through the amdgpu driver's sysfs and hwmon files, and
+/// attributes VRAM to processes from their DRM fdinfo.
+///
+/// Cards are enumerated once, in `cardN` order, when the backend is created.
+pub struct AmdBackend {
+    proc_root: PathBuf,
+    cards: Vec<DrmCard>,
+}
+
+impl AmdBackend {
+    pub fn new() -> Result<Self> {
+        Self::with_root_prefix(Path::new("/"))
+    }
+
+    /// Read `sys` and `proc` under `prefix`, e.g. where a container mounts the host's.
+    pub fn with_root_prefix(prefix: &Path) -> Result<Self> {
+        Self::with_roots(prefix.join("sys/class/drm"), prefix.join("proc"))
+    }
+
+    pub fn with_roots<D: AsRef<Path>, P: Into<PathBuf>>(drm_root: D, proc_root: P) -> Result<Self> {
+        let cards = find_cards(drm_root.as_ref(), AMD_VENDOR_ID)?;
+        if cards.is_empty() {
+            return Err(GpmError::ServiceUnavailable("No AMD GPUs found".to_string()));
+        }
+
+        info!("AMD sysfs backend found {} GPU(s)", cards.len());
+
+        Ok(Self {
+            proc_root: proc_root.into(),
+            cards,
+        })
+    }
+
+    fn collect_card_metrics(&self, index: u32, card: &DrmCard, processes: Vec<GpuProcess>) -> Result<GpuMetrics> {
+        let device = &card.device;
+        let hwmon = hwmon_dir(device);
+        let hwmon_u64 = |file: &str| hwmon.as_ref().and_then(|h| read_u64(&h.join(file)));
+
+        let utilization_gpu = read_u64(&device.join("gpu_busy_percent"))
+            .ok_or_else(|| GpmError::InvalidData(format!("{} has no gpu_busy_percent", device.display())))?;
+
+        let name = read_string(&device.join("product_name"))
+            .filter(|n| !n.is_empty())
+            .or_else(|| read_string(&device.join("device")).map(|id| format!("AMD GPU {}", id)))
+            .unwrap_or_else(|| format!("AMD GPU {}", index));
+
+        let telemetry = GpuTelemetry {
+            // hwmon reports the shader and memory clocks in Hz
+            clock_graphics_mhz: hwmon_u64("freq1_input")
+                .map(|hz| (hz / 1_000_000) as u32)
+                .or_else(|| active_dpm_clock(&device.join("pp_dpm_sclk"))),
+            clock_memory_mhz: hwmon_u64("freq2_input")
+                .map(|hz| (hz / 1_000_000) as u32)
+                .or_else(|| active_dpm_clock(&device.join("pp_dpm_mclk"))),
+            fan_speed_percent: hwmon_u64("pwm1").map(|pwm| (pwm * 100 / 255) as u32),
+            pcie_link_gen: read_string(&device.join("current_link_speed")).and_then(|s| pcie_gen(&s)),
+            pcie_link_width: read_u64(&device.join("current_link_width")).map(|w| w as u32),
+            power_limit_watts: hwmon_u64("power1_cap").map(|uw| (uw / 1_000_000) as u32),
+            ..Default::default()
+        };
+
+        let identity = GpuIdentity {
+            uuid: read_string(&device.join("unique_id")).filter(|id| !id.is_empty()),
+            serial: read_string(&device.join("serial_number")).filter(|s| !s.is_empty()),
+            pci_bus_id: card.pci_bus_id.clone(),
+            vbios_version: read_string(&device.join("vbios_version")),
+            driver_version: read_string(&device.join("driver/module/version")),
+        };
+
+        let metrics = GpuMetrics {
+            timestamp: chrono::Utc::now(),
+            gpu_id: index,
+            name,
+            utilization_gpu: utilization_gpu as u32,
+            utilization_memory: read_u64(&device.join("mem_busy_percent")).unwrap_or(0) as u32,
+            memory_used: read_u64(&device.join("mem_info_vram_used")).unwrap_or(0),
+            memory_total: read_u64(&device.join("mem_info_vram_total")).unwrap_or(0),
+            // Edge sensor, in millidegrees
+            temperature: hwmon_u64("temp1_input").map(|t| t / 1000).unwrap_or(0) as u32,
+            // Newer kernels only expose the instantaneous reading, in microwatts
+            power_usage: hwmon_u64("power1_average")
+                .or_else(|| hwmon_u64("power1_input"))
+                .map(|uw| uw / 1_000_000)
+                .unwrap_or(0) as u32,
+            processes,
+            telemetry,
+            identity,
+            energy_wh: None,
+            mig_devices: Vec::new(),
+        };
+
+        debug!(
+            "AMD GPU {} metrics: util={}%, temp={}°C, power={}W, processes={}",
+            index,
+            metrics.utilization_gpu,
+            metrics.temperature,
+            metrics.power_usage,
+            metrics.processes.len()
+        );
+
+        Ok(metrics)
+    }
+
+    /// VRAM held by each process on the card at `pci_bus_id`.
+    fn card_processes(&self, clients: &[DrmClient], pci_bus_id: Option<&str>) -> Vec<GpuProcess> {
+        let mut processes: Vec<GpuProcess> = Vec::new();
+
+        // Without a bus ID a lone card owns every client
+        for client in clients
+            .iter()
+            .filter(|c| pci_bus_id.is_none_or(|id| c.pdev.eq_ignore_ascii_case(id)))
+        {
+            match processes.iter_mut().find(|p| p.pid == client.pid) {
+                Some(process) => process.used_gpu_memory += client.device_memory(),
+                None => processes.push(GpuProcess {
+                    pid: client.pid,
+                    name: process_name(&self.proc_root, client.pid),
+                    used_gpu_memory: client.device_memory(),
+                    utilization: None,
+                    mig_device: None,
+                }),
+            }
+        }
+
+        processes.sort_by_key(|p| std::cmp::Reverse(p.used_gpu_memory));
+        processes
+    }
+}
+
+impl GpuBackend for AmdBackend {
+    fn name(&self) -> &'static str {
+        "amd_sysfs"
+    }
+
+    fn device_count(&self) -> u32 {
+        self.cards.len() as u32
+    }
+
+    fn collect_metrics(&self) -> Result<Vec<GpuMetrics>> {
+        let clients = scan_drm_clients(&self.proc_root, &["amdgpu"]);
+        let single_card = self.cards.len() == 1;
+
+        let mut all_metrics = Vec::new();
+
+        for (index, card) in self.cards.iter().enumerate() {
+            let bus_id = card.pci_bus_id.as_deref().filter(|_| !single_card);
+            let processes = self.card_processes(&clients, bus_id);
+
+            match self.collect_card_metrics(index as u32, card, processes) {
+                Ok(metrics) => all_metrics.push(metrics),
+                Err(e) => warn!("Failed to collect metrics for GPU {}: {}", index, e),
+            }
+        }
+
+        if all_metrics.is_empty() {
+            return Err(GpmError::ServiceUnavailable(
+                "Failed to collect metrics from any AMD GPU".to_string(),
+            ));
+        }
+
+        Ok(all_metrics)
+    }
+}
+
+/// The level marked active in a `pp_dpm_*` table, e.g. `1: 1800Mhz *`.
+fn active_dpm_clock(path: &Path) -> Option<u32> {
+    let table = read_string(path)?;
+    let line = table.lines().find(|l| l.trim_end().ends_with('*'))?;
+    let (_, rest) = line.split_once(':')?;
+    rest.trim().trim_end_matches('*').trim().to_lowercase().strip_suffix("mhz")?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu::sysfs::testing::{self, write};
+
+    fn fake_card(drm: &Path, card: &str, vendor: &str, slot: &str) -> PathBuf {
+        let device = testing::fake_card(drm, card, vendor, slot);
+        write(&device.join("device"), "0x73bf\n");
+        write(&device.join("gpu_busy_percent"), "87\n");
+        write(&device.join("mem_busy_percent"), "12\n");
+        write(&device.join("mem_info_vram_used"), &format!("{}\n", 3u64 << 30));
+        write(&device.join("mem_info_vram_total"), &format!("{}\n", 16u64 << 30));
+        write(&device.join("current_link_speed"), "16.0 GT/s PCIe\n");
+        write(&device.join("current_link_width"), "16\n");
+        write(&device.join("pp_dpm_sclk"), "0: 500Mhz\n1: 2250Mhz *\n");
+        write(&device.join("pp_dpm_mclk"), "0: 96Mhz\n1: 1000Mhz *\n");
+        write(&device.join("unique_id"), "a1b2c3d4e5f60718\n");
+        write(&device.join("vbios_version"), "113-D4120100-100\n");
+
+        let hwmon = device.join("hwmon/hwmon4");
+        write(&hwmon.join("temp1_input"), "65000\n");
+        write(&hwmon.join("power1_average"), "215000000\n");
+        write(&hwmon.join("power1_cap"), "300000000\n");
+        write(&hwmon.join("pwm1"), "128\n");
+        write(&hwmon.join("freq2_input"), "1250000000\n");
+        device
+    }
+
+    fn fake_client(proc_root: &Path, pid: u32, fds: &[u32], slot: &str, vram_kib: u64) {
+        let dir = proc_root.join(pid.to_string());
+        write(&dir.join("comm"), "ollama_llama_se\n");
+        std::fs::create_dir_all(dir.join("fd")).unwrap();
+        for fd in fds {
+            std::os::unix::fs::symlink("/dev/dri/renderD128", dir.join("fd").join(fd.to_string())).unwrap();
+            write(
+                &dir.join("fdinfo").join(fd.to_string()),
+                &format!(
+                    "pos:\t0\ndrm-driver:\tamdgpu\ndrm-pdev:\t{}\ndrm-client-id:\t{}\ndrm-memory-vram:\t{} KiB\n",
+                    slot, pid, vram_kib
+                ),
+            );
+        }
+        std::os::unix::fs::symlink("/dev/null", dir.join("fd/0")).unwrap();
+    }
+
+    #[test]
+    fn test_amd_backend_reads_fake_sysfs() {
+        let dir = tempfile::tempdir().unwrap();
+        let drm = dir.path().join("drm");
+        let proc_root = dir.path().join("proc");
+
+        fake_card(&drm, "card1", AMD_VENDOR_ID, "0000:03:00.0");
+        fake_card(&drm, "card0", AMD_VENDOR_ID, "0000:0c:00.0");
+        fake_card(&drm, "card2", "0x10de", "0000:21:00.0");
+        std::fs::create_dir_all(drm.join("card1-DP-1")).unwrap();
+
+        // Two descriptors for the same client count once
+        fake_client(&proc_root, 4242, &[5, 6], "0000:03:00.0", 2 << 20);
+        fake_client(&proc_root, 4343, &[7], "0000:0c:00.0", 1024);
+
+        let backend = AmdBackend::with_roots(&drm, &proc_root).unwrap();
+        assert_eq!(backend.device_count(), 2);
+
+        let metrics = backend.collect_metrics().unwrap();
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics[0].identity.pci_bus_id.as_deref(), Some("0000:0c:00.0"));
+
+        let m = &metrics[1];
+        assert_eq!(m.gpu_id, 1);
+        assert_eq!(m.name, "AMD GPU 0x73bf");
+        assert_eq!(m.utilization_gpu, 87);
+        assert_eq!(m.utilization_memory, 12);
+        assert_eq!(m.memory_used, 3 << 30);
+        assert_eq!(m.memory_total, 16 << 30);
+        assert_eq!(m.temperature, 65);
+        assert_eq!(m.power_usage, 215);
+        assert_eq!(m.telemetry.power_limit_watts, Some(300));
+        assert_eq!(m.telemetry.fan_speed_percent, Some(50));
+        assert_eq!(m.telemetry.clock_graphics_mhz, Some(2250));
+        assert_eq!(m.telemetry.clock_memory_mhz, Some(1250));
+        assert_eq!(m.telemetry.pcie_link_gen, Some(4));
+        assert_eq!(m.telemetry.pcie_link_width, Some(16));
+        assert_eq!(m.identity.uuid.as_deref(), Some("a1b2c3d4e5f60718"));
+        assert_eq!(m.identity.vbios_version.as_deref(), Some("113-D4120100-100"));
+
+        assert_eq!(m.processes.len(), 1);
+        assert_eq!(m.processes[0].pid, 4242);
+        assert_eq!(m.processes[0].name, "ollama_llama_se");
+        assert_eq!(m.processes[0].used_gpu_memory, 2 << 30);
+        assert_eq!(metrics[0].processes[0].used_gpu_memory, 1 << 20);
+    }
+
+    #[test]
+    fn test_no_amd_gpus() {
+        let dir = tempfile::tempdir().unwrap();
+        fake_card(dir.path(), "card0", "0x8086", "0000:00:02.0");
+        assert!(AmdBackend::with_roots(dir.path(), dir.path()).is_err());
+    }
+}
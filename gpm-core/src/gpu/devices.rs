@@ -0,0 +1,196 @@
+use super::GpuMetrics;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceEventKind {
+    Added,
+    Removed,
+}
+
+impl DeviceEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "added" => Some(Self::Added),
+            "removed" => Some(Self::Removed),
+            _ => None,
+        }
+    }
+}
+
+/// A GPU appearing in or disappearing from the device list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceEvent {
+    pub id: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub kind: DeviceEventKind,
+    /// Enumeration index when the event happened (when last seen, for removals)
+    pub gpu_id: u32,
+    pub uuid: Option<String>,
+    pub name: String,
+    pub pci_bus_id: Option<String>,
+    /// Why a device was removed, e.g. the collection error
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct KnownDevice {
+    gpu_id: u32,
+    uuid: Option<String>,
+    name: String,
+    pci_bus_id: Option<String>,
+}
+
+/// Compares the GPUs of each poll with the previous one and reports
+/// hot-plugged, lost and returning devices.
+///
+/// Devices are keyed by `GpuMetrics::stable_id`, so a card that comes back
+/// under a different index after a reset is recognised. The first successful
+/// poll only establishes the baseline.
+#[derive(Default)]
+pub struct DeviceTracker {
+    devices: HashMap<String, KnownDevice>,
+    baseline: bool,
+}
+
+impl DeviceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Observe the GPUs of one poll. A failed collection (`error`) counts as
+    /// every known device being gone.
+    pub fn observe(
+        &mut self,
+        metrics: &[GpuMetrics],
+        error: Option<&str>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Vec<DeviceEvent> {
+        if !self.baseline {
+            if error.is_none() {
+                self.devices = metrics.iter().map(|m| (m.stable_id(), Self::known(m))).collect();
+                self.baseline = true;
+            }
+            return Vec::new();
+        }
+
+        let mut events = Vec::new();
+        let mut current = HashMap::with_capacity(metrics.len());
+
+        for m in metrics {
+            let key = m.stable_id();
+            let device = Self::known(m);
+
+            if !self.devices.contains_key(&key) {
+                info!("GPU {} ({}) added", device.gpu_id, device.name);
+                events.push(Self::event(&device, DeviceEventKind::Added, None, now));
+            }
+            current.insert(key, device);
+        }
+
+        for (key, device) in &self.devices {
+            if !current.contains_key(key) {
+                let detail = error.unwrap_or("no longer enumerated");
+                warn!("GPU {} ({}) removed: {}", device.gpu_id, device.name, detail);
+                events.push(Self::event(device, DeviceEventKind::Removed, Some(detail.to_string()), now));
+            }
+        }
+
+        self.devices = current;
+        events
+    }
+
+    fn known(metrics: &GpuMetrics) -> KnownDevice {
+        KnownDevice {
+            gpu_id: metrics.gpu_id,
+            uuid: metrics.identity.uuid.clone(),
+            name: metrics.name.clone(),
+            pci_bus_id: metrics.identity.pci_bus_id.clone(),
+        }
+    }
+
+    fn event(
+        device: &KnownDevice,
+        kind: DeviceEventKind,
+        detail: Option<String>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> DeviceEvent {
+        DeviceEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: now,
+            kind,
+            gpu_id: device.gpu_id,
+            uuid: device.uuid.clone(),
+            name: device.name.clone(),
+            pci_bus_id: device.pci_bus_id.clone(),
+            detail,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu::GpuIdentity;
+
+    fn gpu(gpu_id: u32, uuid: &str) -> GpuMetrics {
+        GpuMetrics {
+            timestamp: chrono::Utc::now(),
+            gpu_id,
+            name: "RTX 4090".to_string(),
+            utilization_gpu: 0,
+            utilization_memory: 0,
+            memory_used: 0,
+            memory_total: 24 << 30,
+            temperature: 40,
+            power_usage: 30,
+            processes: Vec::new(),
+            telemetry: Default::default(),
+            identity: GpuIdentity {
+                uuid: Some(uuid.to_string()),
+                ..Default::default()
+            },
+            energy_wh: None,
+            mig_devices: Vec::new(),
+        }
+    }
+
+    fn kinds(events: &[DeviceEvent]) -> Vec<(DeviceEventKind, Option<&str>)> {
+        events.iter().map(|e| (e.kind, e.uuid.as_deref())).collect()
+    }
+
+    #[test]
+    fn test_device_added_and_removed() {
+        let mut tracker = DeviceTracker::new();
+        let now = chrono::Utc::now();
+
+        // Failures before the first successful poll report nothing
+        assert!(tracker.observe(&[], Some("NVML unavailable"), now).is_empty());
+        assert!(tracker.observe(&[gpu(0, "GPU-a")], None, now).is_empty());
+        assert!(tracker.observe(&[gpu(0, "GPU-a")], None, now).is_empty());
+
+        // eGPU attached
+        let events = tracker.observe(&[gpu(0, "GPU-a"), gpu(1, "GPU-b")], None, now);
+        assert_eq!(kinds(&events), vec![(DeviceEventKind::Added, Some("GPU-b"))]);
+
+        // Driver reload: every device is gone, with the error as the reason
+        let events = tracker.observe(&[], Some("Failed to collect metrics from any GPU"), now);
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| e.kind == DeviceEventKind::Removed));
+        assert_eq!(events[0].detail.as_deref(), Some("Failed to collect metrics from any GPU"));
+
+        // One comes back under a different index
+        let events = tracker.observe(&[gpu(0, "GPU-b")], None, now);
+        assert_eq!(kinds(&events), vec![(DeviceEventKind::Added, Some("GPU-b"))]);
+        assert_eq!(events[0].gpu_id, 0);
+    }
+}
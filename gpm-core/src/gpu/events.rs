@@ -0,0 +1,481 @@
+use super::NvmlMonitor;
+use crate::error::{GpmError, Result};
+use nvml_wrapper::bitmasks::event::EventTypes;
+use nvml_wrapper::enums::event::XidError;
+use nvml_wrapper::error::NvmlError;
+use nvml_wrapper::{Device, EventSet, Nvml};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// How long one NVML wait blocks before checking for shutdown.
+const WAIT_TIMEOUT_MS: u32 = 1000;
+
+/// Pause before re-registering after NVML events stopped (driver reload).
+const REREGISTER_DELAY: Duration = Duration::from_secs(5);
+
+/// Xids that mean the GPU or its memory is in trouble, rather than one
+/// application misbehaving.
+const CRITICAL_XIDS: &[u64] = &[48, 62, 64, 74, 79, 95, 119, 120];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuEventKind {
+    Xid,
+    SingleBitEcc,
+    DoubleBitEcc,
+    PstateChange,
+    ClockChange,
+    PowerSourceChange,
+}
+
+impl GpuEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Xid => "xid",
+            Self::SingleBitEcc => "single_bit_ecc",
+            Self::DoubleBitEcc => "double_bit_ecc",
+            Self::PstateChange => "pstate_change",
+            Self::ClockChange => "clock_change",
+            Self::PowerSourceChange => "power_source_change",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "xid" => Some(Self::Xid),
+            "single_bit_ecc" => Some(Self::SingleBitEcc),
+            "double_bit_ecc" => Some(Self::DoubleBitEcc),
+            "pstate_change" => Some(Self::PstateChange),
+            "clock_change" => Some(Self::ClockChange),
+            "power_source_change" => Some(Self::PowerSourceChange),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuEventSource {
+    Nvml,
+    KernelLog,
+}
+
+impl GpuEventSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Nvml => "nvml",
+            Self::KernelLog => "kernel_log",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "nvml" => Some(Self::Nvml),
+            "kernel_log" => Some(Self::KernelLog),
+            _ => None,
+        }
+    }
+}
+
+/// An Xid error, ECC error or state change reported by the driver.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GpuEvent {
+    pub id: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub kind: GpuEventKind,
+    /// Kernel log lines only name the PCI device; the collector fills in
+    /// the index and UUID from the GPU inventory
+    pub gpu_id: Option<u32>,
+    pub uuid: Option<String>,
+    pub pci_bus_id: Option<String>,
+    pub xid: Option<u64>,
+    /// Processes on the GPU when the event arrived
+    pub pids: Vec<u32>,
+    pub source: GpuEventSource,
+    pub message: String,
+}
+
+impl GpuEvent {
+    /// Xid errors that usually need a reset or a reboot.
+    pub fn is_critical(&self) -> bool {
+        match self.kind {
+            GpuEventKind::Xid => self.xid.is_some_and(|xid| CRITICAL_XIDS.contains(&xid)),
+            GpuEventKind::DoubleBitEcc => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the event deserves an alert at all; state changes are only
+    /// recorded.
+    pub fn is_error(&self) -> bool {
+        matches!(self.kind, GpuEventKind::Xid | GpuEventKind::DoubleBitEcc)
+    }
+}
+
+/// Short description of the common Xids, from NVIDIA's Xid catalog.
+pub fn xid_description(xid: u64) -> Option<&'static str> {
+    Some(match xid {
+        13 => "Graphics engine exception",
+        31 => "GPU memory page fault",
+        43 => "GPU stopped processing",
+        45 => "Preemptive cleanup, due to previous errors",
+        48 => "Double bit ECC error",
+        61 => "Internal micro-controller breakpoint/warning",
+        62 => "Internal micro-controller halt",
+        63 => "ECC page retirement or row remapping recording event",
+        64 => "ECC page retirement or row remapper recording failure",
+        74 => "NVLink error",
+        79 => "GPU has fallen off the bus",
+        92 => "High single-bit ECC error rate",
+        94 => "Contained ECC error",
+        95 => "Uncontained ECC error",
+        119 => "GSP RPC timeout",
+        120 => "GSP error",
+        _ => return None,
+    })
+}
+
+/// Watches for driver events on a background thread: NVML event sets when
+/// the devices support them, else NVRM Xid lines from the kernel log.
+pub struct GpuEventWatcher {
+    kernel_log: PathBuf,
+    stop: Arc<AtomicBool>,
+}
+
+impl GpuEventWatcher {
+    /// `root_prefix` is where `dev/kmsg` is found, as for `gpu.root_prefix`.
+    pub fn new(root_prefix: &Path) -> Self {
+        Self {
+            kernel_log: root_prefix.join("dev/kmsg"),
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Start watching. The thread blocks in the driver and is not joined;
+    /// it exits once `stop` is called or the receiver is dropped.
+    pub fn spawn(&self) -> mpsc::Receiver<GpuEvent> {
+        let (tx, rx) = mpsc::channel(256);
+        let kernel_log = self.kernel_log.clone();
+        let stop = Arc::clone(&self.stop);
+
+        std::thread::Builder::new()
+            .name("gpm-gpu-events".to_string())
+            .spawn(move || {
+                match watch_nvml(&tx, &stop) {
+                    Ok(()) => return,
+                    Err(e) => info!("NVML events unavailable ({}), reading Xid errors from the kernel log", e),
+                }
+
+                if let Err(e) = watch_kernel_log(&kernel_log, &tx, &stop) {
+                    warn!("GPU event monitoring disabled: {}", e);
+                }
+            })
+            .expect("failed to spawn GPU event thread");
+
+        rx
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Returns `Ok` when stopped, or `Err` if NVML events cannot be used at
+/// all; after a driver reload the devices are registered again.
+fn watch_nvml(tx: &mpsc::Sender<GpuEvent>, stop: &AtomicBool) -> Result<()> {
+    let mut watching = false;
+
+    while !stop.load(Ordering::Relaxed) {
+        let nvml = match NvmlMonitor::init_nvml() {
+            Ok(nvml) => nvml,
+            Err(e) if !watching => return Err(e),
+            Err(e) => {
+                debug!("NVML not back yet: {}", e);
+                std::thread::sleep(REREGISTER_DELAY);
+                continue;
+            }
+        };
+
+        let set = match register_events(&nvml) {
+            Ok(set) => set,
+            Err(e) if !watching => return Err(e),
+            Err(e) => {
+                debug!("Cannot register NVML events yet: {}", e);
+                std::thread::sleep(REREGISTER_DELAY);
+                continue;
+            }
+        };
+        watching = true;
+
+        match wait_for_events(&set, tx, stop) {
+            Ok(()) => return Ok(()),
+            Err(e) => warn!("NVML event wait failed ({}), registering again", e),
+        }
+
+        drop(set);
+        drop(nvml);
+        std::thread::sleep(REREGISTER_DELAY);
+    }
+
+    Ok(())
+}
+
+fn register_events(nvml: &Nvml) -> Result<EventSet<'_>> {
+    let wanted = EventTypes::CRITICAL_XID_ERROR
+        | EventTypes::DOUBLE_BIT_ECC_ERROR
+        | EventTypes::SINGLE_BIT_ECC_ERROR
+        | EventTypes::PSTATE_CHANGE
+        | EventTypes::CLOCK_CHANGE
+        | EventTypes::POWER_SOURCE_CHANGE;
+
+    let count = nvml.device_count().map_err(nvml_error)?;
+    let mut set = nvml.create_event_set().map_err(nvml_error)?;
+    let mut registered = 0;
+
+    for index in 0..count {
+        let Ok(device) = nvml.device_by_index(index) else {
+            continue;
+        };
+        let events = device.supported_event_types().unwrap_or(EventTypes::empty()) & wanted;
+        if events.is_empty() {
+            continue;
+        }
+
+        set = device
+            .register_events(events, set)
+            .map_err(|e| GpmError::NvmlError(format!("Failed to register events for GPU {}: {:?}", index, e.error)))?;
+        registered += 1;
+    }
+
+    if registered == 0 {
+        return Err(GpmError::ServiceUnavailable("no GPU supports NVML events".to_string()));
+    }
+
+    info!("Watching NVML events on {} GPU(s)", registered);
+    Ok(set)
+}
+
+/// Forward events until stopped; `Err` when NVML stops answering.
+fn wait_for_events(set: &EventSet, tx: &mpsc::Sender<GpuEvent>, stop: &AtomicBool) -> Result<()> {
+    while !stop.load(Ordering::Relaxed) {
+        let data = match set.wait(WAIT_TIMEOUT_MS) {
+            Ok(data) => data,
+            Err(NvmlError::Timeout) => continue,
+            Err(e) => return Err(nvml_error(e)),
+        };
+
+        for event in nvml_events(&data.device, data.event_type, data.event_data) {
+            if tx.blocking_send(event).is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn nvml_error(e: NvmlError) -> GpmError {
+    GpmError::NvmlError(format!("{:?}", e))
+}
+
+fn nvml_events(device: &Device, types: EventTypes, xid: Option<XidError>) -> Vec<GpuEvent> {
+    const KINDS: &[(EventTypes, GpuEventKind)] = &[
+        (EventTypes::CRITICAL_XID_ERROR, GpuEventKind::Xid),
+        (EventTypes::DOUBLE_BIT_ECC_ERROR, GpuEventKind::DoubleBitEcc),
+        (EventTypes::SINGLE_BIT_ECC_ERROR, GpuEventKind::SingleBitEcc),
+        (EventTypes::PSTATE_CHANGE, GpuEventKind::PstateChange),
+        (EventTypes::CLOCK_CHANGE, GpuEventKind::ClockChange),
+        (EventTypes::POWER_SOURCE_CHANGE, GpuEventKind::PowerSourceChange),
+    ];
+
+    let gpu_id = device.index().ok();
+    let xid = match xid {
+        Some(XidError::Value(xid)) => Some(xid),
+        _ => None,
+    };
+    // A GPU that fell off the bus cannot list its processes any more
+    let pids: Vec<u32> = device
+        .running_compute_processes()
+        .unwrap_or_default()
+        .into_iter()
+        .chain(device.running_graphics_processes().unwrap_or_default())
+        .map(|p| p.pid)
+        .collect();
+
+    KINDS
+        .iter()
+        .filter(|(flag, _)| types.contains(*flag))
+        .map(|(_, kind)| {
+            let message = match (kind, xid) {
+                (GpuEventKind::Xid, Some(xid)) => {
+                    format!("Xid {}: {}", xid, xid_description(xid).unwrap_or("see NVIDIA's Xid catalog"))
+                }
+                _ => kind.as_str().replace('_', " "),
+            };
+
+            GpuEvent {
+                id: uuid::Uuid::new_v4().to_string(),
+                timestamp: chrono::Utc::now(),
+                kind: *kind,
+                gpu_id,
+                uuid: device.uuid().ok(),
+                pci_bus_id: device.pci_info().ok().map(|p| p.bus_id),
+                xid: if *kind == GpuEventKind::Xid { xid } else { None },
+                pids: pids.clone(),
+                source: GpuEventSource::Nvml,
+                message,
+            }
+        })
+        .collect()
+}
+
+/// Follow `/dev/kmsg`, or `journalctl -k` when it cannot be read, for NVRM
+/// Xid lines. Blocks until the log ends or `stop` is set.
+fn watch_kernel_log(kmsg: &Path, tx: &mpsc::Sender<GpuEvent>, stop: &AtomicBool) -> Result<()> {
+    let reader: Box<dyn BufRead> = match std::fs::File::open(kmsg) {
+        Ok(mut file) => {
+            // Skip the messages logged before we started
+            file.seek(SeekFrom::End(0))?;
+            info!("Reading Xid errors from {}", kmsg.display());
+            Box::new(BufReader::new(file))
+        }
+        Err(e) => {
+            debug!("Cannot read {}: {}", kmsg.display(), e);
+            let child = std::process::Command::new("journalctl")
+                .args(["-k", "-f", "-n", "0", "-o", "cat"])
+                .stdout(std::process::Stdio::piped())
+                .stderr(std::process::Stdio::null())
+                .spawn()
+                .map_err(|e| GpmError::ServiceUnavailable(format!("no readable kernel log: {}", e)))?;
+            info!("Reading Xid errors from journalctl -k");
+            Box::new(BufReader::new(child.stdout.expect("stdout is piped")))
+        }
+    };
+
+    let pattern = xid_pattern();
+
+    for line in reader.lines() {
+        // /dev/kmsg fails reads with EPIPE when records were overwritten
+        let Ok(line) = line else {
+            continue;
+        };
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+
+        if let Some(event) = parse_xid_line(&pattern, &line) {
+            if tx.blocking_send(event).is_err() {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn xid_pattern() -> Regex {
+    Regex::new(r"NVRM: Xid \((?:PCI:)?([0-9A-Fa-f:.]+)\): (\d+),\s*(.*)").expect("valid Xid pattern")
+}
+
+/// Parse an `NVRM: Xid (PCI:0000:01:00): 79, pid=1234, name=python3, ...`
+/// line, as printed by the driver, optionally with a `/dev/kmsg` record
+/// prefix.
+fn parse_xid_line(pattern: &Regex, line: &str) -> Option<GpuEvent> {
+    let caps = pattern.captures(line)?;
+    let xid: u64 = caps[2].parse().ok()?;
+    let detail = caps[3].trim();
+
+    let pids = detail
+        .split(", ")
+        .find_map(|part| part.strip_prefix("pid="))
+        .and_then(|pid| pid.trim_matches('\'').parse().ok())
+        .into_iter()
+        .collect();
+
+    // Drop the pid=/name= fields the driver puts before the description
+    let description = detail
+        .split(", ")
+        .filter(|part| !part.starts_with("pid=") && !part.starts_with("name="))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Some(GpuEvent {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp: chrono::Utc::now(),
+        kind: GpuEventKind::Xid,
+        gpu_id: None,
+        uuid: None,
+        pci_bus_id: Some(caps[1].to_string()),
+        xid: Some(xid),
+        pids,
+        source: GpuEventSource::KernelLog,
+        message: format!(
+            "Xid {}: {}",
+            xid,
+            xid_description(xid).map(str::to_string).unwrap_or(description)
+        ),
+    })
+}
+
+/// Whether a kernel PCI address (`0000:01:00`) and an NVML bus ID
+/// (`00000000:01:00.0`) name the same device.
+pub fn same_pci_device(a: &str, b: &str) -> bool {
+    fn parts(id: &str) -> Option<(u32, u32, u32)> {
+        let id = id.split('.').next()?;
+        let mut fields = id.rsplit(':');
+        let device = u32::from_str_radix(fields.next()?, 16).ok()?;
+        let bus = u32::from_str_radix(fields.next()?, 16).ok()?;
+        let domain = fields.next().map_or(Some(0), |d| u32::from_str_radix(d, 16).ok())?;
+        Some((domain, bus, device))
+    }
+
+    parts(a).is_some() && parts(a) == parts(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_xid_line() {
+        let pattern = xid_pattern();
+
+        let event = parse_xid_line(
+            &pattern,
+            "4,1523,98765432,-;NVRM: Xid (PCI:0000:01:00): 79, pid=1234, name=python3, GPU has fallen off the bus.",
+        )
+        .unwrap();
+        assert_eq!(event.xid, Some(79));
+        assert_eq!(event.pids, vec![1234]);
+        assert_eq!(event.pci_bus_id.as_deref(), Some("0000:01:00"));
+        assert_eq!(event.message, "Xid 79: GPU has fallen off the bus");
+        assert!(event.is_critical());
+
+        // Older drivers, unknown pid and an Xid without a description here
+        let event = parse_xid_line(
+            &pattern,
+            "NVRM: Xid (0000:3b:00): 109, pid='<unknown>', name=<unknown>, Ch 00000010, errorString CTX SWITCH TIMEOUT",
+        )
+        .unwrap();
+        assert!(event.pids.is_empty());
+        assert_eq!(event.message, "Xid 109: Ch 00000010, errorString CTX SWITCH TIMEOUT");
+        assert!(!event.is_critical());
+
+        assert!(parse_xid_line(&pattern, "NVRM: loading NVIDIA UNIX x86_64 Kernel Module 550.54.14").is_none());
+    }
+
+    #[test]
+    fn test_same_pci_device() {
+        assert!(same_pci_device("0000:01:00", "00000000:01:00.0"));
+        assert!(same_pci_device("3b:00", "00000000:3B:00.0"));
+        assert!(!same_pci_device("0000:02:00", "00000000:01:00.0"));
+        assert!(!same_pci_device("garbage", "garbage"));
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// One open DRM client (GPU context) as reported in `/proc/<pid>/fdinfo`,
+/// following the kernel's DRM client usage stats format.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DrmClient {
+    pub pid: u32,
+    pub driver: String,
+    /// PCI address of the device, e.g. `0000:03:00.0`
+    pub pdev: String,
+    pub client_id: u64,
+    /// Resident memory per region (`vram`, `gtt`, `local0`, ...) in bytes
+    pub memory: BTreeMap<String, u64>,
+    /// Busy time per engine since the client was opened, in nanoseconds
+    pub engines: BTreeMap<String, u64>,
+    /// Number of engines of each kind, when more than one
+    pub engine_capacity: BTreeMap<String, u64>,
+    /// xe reports busy and total GPU cycles per engine class instead of time
+    pub cycles: BTreeMap<String, u64>,
+    pub total_cycles: BTreeMap<String, u64>,
+}
+
+impl DrmClient {
+    /// Device-local memory: the `vram*` and `local*` regions.
+    pub fn device_memory(&self) -> u64 {
+        self.memory
+            .iter()
+            .filter(|(region, _)| region.starts_with("vram") || region.starts_with("local"))
+            .map(|(_, bytes)| bytes)
+            .sum()
+    }
+
+    /// Memory in every region, for integrated GPUs that only use system memory.
+    pub fn total_memory(&self) -> u64 {
+        self.memory.values().sum()
+    }
+}
+
+/// Parse one fdinfo file. `None` unless it describes a DRM client.
+pub fn parse_fdinfo(pid: u32, contents: &str) -> Option<DrmClient> {
+    let mut client = DrmClient {
+        pid,
+        ..Default::default()
+    };
+    let mut client_id = None;
+    // Older kernels only report `drm-memory-*`; newer ones add `drm-resident-*`
+    let mut legacy_memory = BTreeMap::new();
+
+    for line in contents.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+
+        if key == "drm-driver" {
+            client.driver = value.to_string();
+        } else if key == "drm-pdev" {
+            client.pdev = value.to_string();
+        } else if key == "drm-client-id" {
+            client_id = value.parse().ok();
+        } else if let Some(region) = key.strip_prefix("drm-resident-") {
+            if let Some(bytes) = parse_size(value) {
+                client.memory.insert(region.to_string(), bytes);
+            }
+        } else if let Some(region) = key.strip_prefix("drm-memory-") {
+            if let Some(bytes) = parse_size(value) {
+                legacy_memory.insert(region.to_string(), bytes);
+            }
+        } else if let Some(engine) = key.strip_prefix("drm-engine-capacity-") {
+            if let Ok(count) = value.parse() {
+                client.engine_capacity.insert(engine.to_string(), count);
+            }
+        } else if let Some(engine) = key.strip_prefix("drm-engine-") {
+            if let Some(ns) = value.strip_suffix("ns").and_then(|v| v.trim().parse().ok()) {
+                client.engines.insert(engine.to_string(), ns);
+            }
+        } else if let Some(engine) = key.strip_prefix("drm-total-cycles-") {
+            if let Ok(cycles) = value.parse() {
+                client.total_cycles.insert(engine.to_string(), cycles);
+            }
+        } else if let Some(engine) = key.strip_prefix("drm-cycles-") {
+            if let Ok(cycles) = value.parse() {
+                client.cycles.insert(engine.to_string(), cycles);
+            }
+        }
+    }
+
+    if client.driver.is_empty() {
+        return None;
+    }
+    client.client_id = client_id?;
+
+    if client.memory.is_empty() {
+        client.memory = legacy_memory;
+    }
+
+    Some(client)
+}
+
+/// `1024 KiB`, `12 MiB` or a plain byte count.
+fn parse_size(value: &str) -> Option<u64> {
+    let mut parts = value.split_whitespace();
+    let number: u64 = parts.next()?.parse().ok()?;
+    let scale = match parts.next() {
+        None => 1,
+        Some("KiB") => 1 << 10,
+        Some("MiB") => 1 << 20,
+        Some("GiB") => 1 << 30,
+        Some(_) => return None,
+    };
+    Some(number * scale)
+}
+
+/// Every DRM client of one of `drivers` under `proc_root`. A client shared by
+/// several file descriptors (dup, fork) is reported once.
+pub fn scan_drm_clients(proc_root: &Path, drivers: &[&str]) -> Vec<DrmClient> {
+    let Ok(entries) = std::fs::read_dir(proc_root) else {
+        return Vec::new();
+    };
+
+    let mut seen = HashSet::new();
+    let mut clients = Vec::new();
+
+    for entry in entries.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) else {
+            continue;
+        };
+
+        // Processes we may not inspect or that exited mid-scan are skipped
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+
+        for fd in fds.flatten() {
+            let is_drm = std::fs::read_link(fd.path()).is_ok_and(|target| target.starts_with("/dev/dri"));
+            if !is_drm {
+                continue;
+            }
+
+            let Ok(contents) = std::fs::read_to_string(entry.path().join("fdinfo").join(fd.file_name())) else {
+                continue;
+            };
+
+            if let Some(client) = parse_fdinfo(pid, &contents).filter(|c| drivers.contains(&c.driver.as_str())) {
+                if seen.insert((pid, client.pdev.clone(), client.client_id)) {
+                    clients.push(client);
+                }
+            }
+        }
+    }
+
+    clients
+}
+
+/// Read the short command name of `pid` from `<proc_root>/<pid>/comm`.
+pub fn process_name(proc_root: &Path, pid: u32) -> String {
+    std::fs::read_to_string(proc_root.join(pid.to_string()).join("comm"))
+        .map(|n| n.trim().to_string())
+        .unwrap_or_else(|_| format!("pid_{}", pid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fdinfo() {
+        let client = parse_fdinfo(
+            42,
+            "pos:\t0\nflags:\t02100002\ndrm-driver:\tamdgpu\ndrm-pdev:\t0000:03:00.0\ndrm-client-id:\t7\n\
+             drm-memory-vram:\t2048 KiB\ndrm-memory-gtt:\t4 MiB\ndrm-engine-gfx:\t1500 ns\n\
+             drm-engine-capacity-gfx:\t2\n",
+        )
+        .unwrap();
+
+        assert_eq!(client.driver, "amdgpu");
+        assert_eq!(client.pdev, "0000:03:00.0");
+        assert_eq!(client.client_id, 7);
+        assert_eq!(client.device_memory(), 2 << 20);
+        assert_eq!(client.memory["gtt"], 4 << 20);
+        assert_eq!(client.engines.len(), 1);
+        assert_eq!(client.engines["gfx"], 1500);
+        assert_eq!(client.engine_capacity["gfx"], 2);
+
+        // Resident figures win over the legacy ones
+        let client = parse_fdinfo(
+            42,
+            "drm-driver: i915\ndrm-client-id: 3\ndrm-memory-local0: 8 MiB\ndrm-resident-local0: 6 MiB\n",
+        )
+        .unwrap();
+        assert_eq!(client.device_memory(), 6 << 20);
+
+        let client = parse_fdinfo(42, "drm-driver: xe\ndrm-client-id: 9\ndrm-cycles-rcs: 250\ndrm-total-cycles-rcs: 1000\n")
+            .unwrap();
+        assert_eq!(client.cycles["rcs"], 250);
+        assert_eq!(client.total_cycles["rcs"], 1000);
+
+        assert!(parse_fdinfo(42, "pos:\t0\nflags:\t0100002\n").is_none());
+    }
+}
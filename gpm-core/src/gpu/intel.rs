@@ -0,0 +1,387 @@
+use super::fdinfo::{process_name, scan_drm_clients, DrmClient};
+use super::sysfs::{find_cards, hwmon_dir, read_string, read_u64, DrmCard};
+use super::{GpuBackend, GpuIdentity, GpuMetrics, GpuProcess, GpuTelemetry, ProcessUtilization};
+use crate::error::{GpmError, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{debug, info, warn};
+
+const INTEL_VENDOR_ID: &str = "0x8086";
+
+/// Engine class for each i915 engine name and xe engine class.
+const ENGINE_CLASSES: &[(&str, &str)] = &[
+    ("render", "render"),
+    ("rcs", "render"),
+    ("copy", "copy"),
+    ("bcs", "copy"),
+    ("video", "video"),
+    ("vcs", "video"),
+    ("video-enhance", "video-enhance"),
+    ("vecs", "video-enhance"),
+    ("compute", "compute"),
+    ("ccs", "compute"),
+];
+
+type ClientKey = (u32, String, u64);
+
+#[derive(Default)]
+struct IntelState {
+    /// Previous stats of each DRM client, to turn its counters into rates
+    clients: HashMap<ClientKey, (chrono::DateTime<chrono::Utc>, DrmClient)>,
+    /// Previous hwmon energy reading per card, in microjoules
+    energy: HashMap<usize, (chrono::DateTime<chrono::Utc>, u64)>,
+}
+
+/// Reads Intel GPUs (i915 and xe) from sysfs and hwmon. Engine busy
+/// percentages come from the DRM client stats in `/proc/<pid>/fdinfo`, so
+/// they are only known from the second poll on.
+pub struct IntelBackend {
+    proc_root: PathBuf,
+    cards: Vec<DrmCard>,
+    state: Mutex<IntelState>,
+}
+
+impl IntelBackend {
+    pub fn new() -> Result<Self> {
+        Self::with_root_prefix(Path::new("/"))
+    }
+
+    /// Read `sys` and `proc` under `prefix`, e.g. where a container mounts the host's.
+    pub fn with_root_prefix(prefix: &Path) -> Result<Self> {
+        let proc_root = prefix.join("proc");
+        let cards = find_cards(&prefix.join("sys/class/drm"), INTEL_VENDOR_ID)?;
+        if cards.is_empty() {
+            return Err(GpmError::ServiceUnavailable("No Intel GPUs found".to_string()));
+        }
+
+        info!("Intel backend found {} GPU(s)", cards.len());
+
+        Ok(Self {
+            proc_root,
+            cards,
+            state: Mutex::new(IntelState::default()),
+        })
+    }
+
+    fn collect_metrics_at(&self, now: chrono::DateTime<chrono::Utc>) -> Result<Vec<GpuMetrics>> {
+        let clients = scan_drm_clients(&self.proc_root, &["i915", "xe"]);
+        let mut state = self.state.lock().unwrap();
+
+        // Busy percentage per engine class, `None` for clients seen for the first time
+        let mut samples = Vec::with_capacity(clients.len());
+        let mut seen = HashMap::with_capacity(clients.len());
+        for client in clients {
+            let key = (client.pid, client.pdev.clone(), client.client_id);
+            let busy = state.clients.get(&key).map(|(at, prev)| engine_busy(prev, &client, now - *at));
+            seen.insert(key, (now, client.clone()));
+            samples.push((client, busy));
+        }
+        state.clients = seen;
+
+        let single_card = self.cards.len() == 1;
+        let mut all_metrics = Vec::new();
+
+        for (index, card) in self.cards.iter().enumerate() {
+            // Without a bus ID a lone card owns every client
+            let bus_id = card.pci_bus_id.as_deref().filter(|_| !single_card);
+            let card_samples: Vec<_> = samples
+                .iter()
+                .filter(|(c, _)| bus_id.is_none_or(|id| c.pdev.eq_ignore_ascii_case(id)))
+                .collect();
+
+            let energy_uj = hwmon_dir(&card.device).and_then(|h| read_u64(&h.join("energy1_input")));
+            let power_watts = match (energy_uj, state.energy.get(&index)) {
+                (Some(uj), Some((at, prev))) if uj >= *prev && now > *at => {
+                    let secs = (now - *at).num_milliseconds() as f64 / 1000.0;
+                    Some(((uj - prev) as f64 / 1_000_000.0 / secs).round() as u32)
+                }
+                _ => None,
+            };
+            if let Some(uj) = energy_uj {
+                state.energy.insert(index, (now, uj));
+            }
+
+            match self.collect_card_metrics(index as u32, card, &card_samples, power_watts, now) {
+                Ok(metrics) => all_metrics.push(metrics),
+                Err(e) => warn!("Failed to collect metrics for GPU {}: {}", index, e),
+            }
+        }
+
+        if all_metrics.is_empty() {
+            return Err(GpmError::ServiceUnavailable(
+                "Failed to collect metrics from any Intel GPU".to_string(),
+            ));
+        }
+
+        Ok(all_metrics)
+    }
+
+    fn collect_card_metrics(
+        &self,
+        index: u32,
+        card: &DrmCard,
+        samples: &[&(DrmClient, Option<BTreeMap<&'static str, f64>>)],
+        power_watts: Option<u32>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<GpuMetrics> {
+        let device = &card.device;
+        let hwmon = hwmon_dir(device);
+        let hwmon_u64 = |file: &str| hwmon.as_ref().and_then(|h| read_u64(&h.join(file)));
+
+        let mut device_busy: BTreeMap<&str, f64> = BTreeMap::new();
+        let mut processes: Vec<(GpuProcess, Option<BTreeMap<&str, f64>>)> = Vec::new();
+
+        for (client, busy) in samples {
+            // Integrated GPUs have no local memory, only system memory
+            let memory = match client.device_memory() {
+                0 => client.total_memory(),
+                local => local,
+            };
+
+            let entry = match processes.iter_mut().position(|(p, _)| p.pid == client.pid) {
+                Some(i) => &mut processes[i],
+                None => {
+                    processes.push((
+                        GpuProcess {
+                            pid: client.pid,
+                            name: process_name(&self.proc_root, client.pid),
+                            used_gpu_memory: 0,
+                            utilization: None,
+                            mig_device: None,
+                        },
+                        None,
+                    ));
+                    processes.last_mut().unwrap()
+                }
+            };
+            entry.0.used_gpu_memory += memory;
+
+            if let Some(busy) = busy {
+                let process_busy = entry.1.get_or_insert_with(BTreeMap::new);
+                for (class, percent) in busy {
+                    *process_busy.entry(class).or_default() += percent;
+                    *device_busy.entry(class).or_default() += percent;
+                }
+            }
+        }
+
+        let percent = |busy: &BTreeMap<&str, f64>, class: &str| busy.get(class).copied().unwrap_or(0.0).min(100.0) as u32;
+
+        let mut processes: Vec<GpuProcess> = processes
+            .into_iter()
+            .map(|(mut process, busy)| {
+                process.utilization = busy.map(|busy| ProcessUtilization {
+                    sm: percent(&busy, "render").max(percent(&busy, "compute")),
+                    memory: 0,
+                    // Intel's video engines both encode and decode
+                    encoder: percent(&busy, "video"),
+                    decoder: percent(&busy, "video"),
+                });
+                process
+            })
+            .collect();
+        processes.sort_by_key(|p| std::cmp::Reverse(p.used_gpu_memory));
+
+        // Busiest engine class, as intel_gpu_top reports overall load
+        let utilization_gpu = device_busy.keys().map(|class| percent(&device_busy, class)).max().unwrap_or(0);
+
+        let name = read_string(&device.join("product_name"))
+            .filter(|n| !n.is_empty())
+            .or_else(|| read_string(&device.join("device")).map(|id| format!("Intel GPU {}", id)))
+            .unwrap_or_else(|| format!("Intel GPU {}", index));
+
+        // i915 exposes the GT frequency on the card, xe per tile and GT
+        let clock_graphics_mhz = read_u64(&card.card.join("gt_act_freq_mhz"))
+            .or_else(|| read_u64(&device.join("tile0/gt0/freq0/act_freq")))
+            .map(|mhz| mhz as u32);
+
+        let telemetry = GpuTelemetry {
+            clock_graphics_mhz,
+            power_limit_watts: hwmon_u64("power1_max")
+                .or_else(|| hwmon_u64("power1_cap"))
+                .map(|uw| (uw / 1_000_000) as u32),
+            total_energy_mj: hwmon_u64("energy1_input").map(|uj| uj / 1000),
+            ..Default::default()
+        };
+
+        let identity = GpuIdentity {
+            uuid: None,
+            serial: None,
+            pci_bus_id: card.pci_bus_id.clone(),
+            vbios_version: None,
+            driver_version: read_string(&device.join("driver/module/version")),
+        };
+
+        let metrics = GpuMetrics {
+            timestamp: now,
+            gpu_id: index,
+            name,
+            utilization_gpu,
+            utilization_memory: 0,
+            memory_used: processes.iter().map(|p| p.used_gpu_memory).sum(),
+            memory_total: read_u64(&device.join("lmem_total_bytes")).unwrap_or(0),
+            temperature: hwmon_u64("temp1_input").map(|t| t / 1000).unwrap_or(0) as u32,
+            power_usage: power_watts
+                .or_else(|| hwmon_u64("power1_input").map(|uw| (uw / 1_000_000) as u32))
+                .unwrap_or(0),
+            processes,
+            telemetry,
+            identity,
+            energy_wh: None,
+            mig_devices: Vec::new(),
+        };
+
+        debug!(
+            "Intel GPU {} metrics: busy={}%, power={}W, processes={}",
+            index,
+            metrics.utilization_gpu,
+            metrics.power_usage,
+            metrics.processes.len()
+        );
+
+        Ok(metrics)
+    }
+}
+
+impl GpuBackend for IntelBackend {
+    fn name(&self) -> &'static str {
+        "intel"
+    }
+
+    fn device_count(&self) -> u32 {
+        self.cards.len() as u32
+    }
+
+    fn collect_metrics(&self) -> Result<Vec<GpuMetrics>> {
+        self.collect_metrics_at(chrono::Utc::now())
+    }
+}
+
+fn engine_class(engine: &str) -> Option<&'static str> {
+    ENGINE_CLASSES.iter().find(|(name, _)| *name == engine).map(|(_, class)| *class)
+}
+
+/// Busy percentage per engine class between two stats of one client,
+/// `elapsed` apart. Engines with several instances are averaged over them.
+fn engine_busy(prev: &DrmClient, cur: &DrmClient, elapsed: chrono::Duration) -> BTreeMap<&'static str, f64> {
+    let mut busy = BTreeMap::new();
+    let capacity = |engine: &str| cur.engine_capacity.get(engine).copied().unwrap_or(1).max(1) as f64;
+
+    if let Some(elapsed_ns) = elapsed.num_nanoseconds().filter(|ns| *ns > 0) {
+        for (engine, ns) in &cur.engines {
+            if let (Some(class), Some(prev_ns)) = (engine_class(engine), prev.engines.get(engine)) {
+                let percent = ns.saturating_sub(*prev_ns) as f64 / elapsed_ns as f64 / capacity(engine) * 100.0;
+                busy.insert(class, percent.min(100.0));
+            }
+        }
+    }
+
+    // xe: busy cycles out of the cycles the GPU ran in the same window
+    for (engine, cycles) in &cur.cycles {
+        let (Some(class), Some(prev_cycles)) = (engine_class(engine), prev.cycles.get(engine)) else {
+            continue;
+        };
+        let total = cur.total_cycles.get(engine).zip(prev.total_cycles.get(engine));
+        if let Some((total, prev_total)) = total.filter(|(t, p)| t > p) {
+            let percent = cycles.saturating_sub(*prev_cycles) as f64 / (total - prev_total) as f64 / capacity(engine) * 100.0;
+            busy.insert(class, percent.min(100.0));
+        }
+    }
+
+    busy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu::sysfs::testing::{fake_card, write};
+
+    fn write_client(root: &Path, pid: u32, render_ns: u64, video_ns: u64) {
+        let dir = root.join("proc").join(pid.to_string());
+        write(&dir.join("comm"), "ffmpeg\n");
+        write(
+            &dir.join("fdinfo/4"),
+            &format!(
+                "pos:\t0\ndrm-driver:\ti915\ndrm-pdev:\t0000:00:02.0\ndrm-client-id:\t{}\n\
+                 drm-engine-render:\t{} ns\ndrm-engine-video:\t{} ns\ndrm-engine-capacity-video:\t2\n\
+                 drm-total-system0:\t64 MiB\ndrm-resident-system0:\t48 MiB\n",
+                pid, render_ns, video_ns
+            ),
+        );
+        if dir.join("fd/4").symlink_metadata().is_err() {
+            std::fs::create_dir_all(dir.join("fd")).unwrap();
+            std::os::unix::fs::symlink("/dev/dri/renderD128", dir.join("fd/4")).unwrap();
+        }
+    }
+
+    fn at(millis: i64) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::from_timestamp_millis(1_700_000_000_000 + millis).unwrap()
+    }
+
+    #[test]
+    fn test_intel_backend_reads_fixture() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        let drm = root.join("sys/class/drm");
+        let card = drm.join("card0");
+        fake_card(&drm, "card0", "0x8086", "0000:00:02.0");
+        write(&card.join("device/device"), "0x56a0\n");
+        write(&card.join("gt_act_freq_mhz"), "1950\n");
+        write(&card.join("device/hwmon/hwmon2/power1_max"), "190000000\n");
+        write(&card.join("device/hwmon/hwmon2/energy1_input"), "1000000000\n");
+        fake_card(&drm, "card1", "0x1002", "0000:03:00.0");
+
+        write_client(root, 700, 0, 0);
+
+        let backend = IntelBackend::with_root_prefix(root).unwrap();
+        assert_eq!(backend.device_count(), 1);
+
+        let first = backend.collect_metrics_at(at(0)).unwrap();
+        assert_eq!(first[0].name, "Intel GPU 0x56a0");
+        assert_eq!(first[0].utilization_gpu, 0);
+        assert_eq!(first[0].processes[0].utilization, None);
+        assert_eq!(first[0].processes[0].used_gpu_memory, 48 << 20);
+
+        // Over 2 s: render busy 1.5 s, one of two video engines busy 1 s
+        write_client(root, 700, 1_500_000_000, 1_000_000_000);
+        write(&card.join("device/hwmon/hwmon2/energy1_input"), "1100000000\n");
+
+        let m = &backend.collect_metrics_at(at(2000)).unwrap()[0];
+        assert_eq!(m.utilization_gpu, 75);
+        assert_eq!(m.power_usage, 50);
+        assert_eq!(m.memory_used, 48 << 20);
+        assert_eq!(m.telemetry.clock_graphics_mhz, Some(1950));
+        assert_eq!(m.telemetry.power_limit_watts, Some(190));
+        assert_eq!(m.telemetry.total_energy_mj, Some(1_100_000));
+        assert_eq!(m.identity.pci_bus_id.as_deref(), Some("0000:00:02.0"));
+
+        let process = &m.processes[0];
+        assert_eq!(process.pid, 700);
+        assert_eq!(process.name, "ffmpeg");
+        assert_eq!(
+            process.utilization,
+            Some(ProcessUtilization {
+                sm: 75,
+                memory: 0,
+                encoder: 25,
+                decoder: 25,
+            })
+        );
+    }
+
+    #[test]
+    fn test_xe_cycle_counters() {
+        let parse = |cycles, total| {
+            crate::gpu::fdinfo::parse_fdinfo(
+                1,
+                &format!("drm-driver: xe\ndrm-client-id: 1\ndrm-cycles-ccs: {}\ndrm-total-cycles-ccs: {}\n", cycles, total),
+            )
+            .unwrap()
+        };
+
+        let busy = engine_busy(&parse(100, 1000), &parse(400, 2000), chrono::Duration::seconds(1));
+        assert_eq!(busy["compute"], 30.0);
+    }
+}
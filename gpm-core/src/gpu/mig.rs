@@ -0,0 +1,145 @@
+use super::nvml::NVML_LIB_PATHS;
+use nvml_wrapper::{Device, Nvml};
+use nvml_wrapper_sys::bindings::{
+    nvmlDeviceAttributes_t, nvmlDevice_t, nvmlReturn_enum_NVML_SUCCESS, NvmlLib, NVML_DEVICE_MIG_ENABLE,
+};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// One MIG device (a compute instance inside a GPU instance) of a parent
+/// GPU.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MigDevice {
+    /// e.g. `MIG-5a1c3e0b-...`
+    pub uuid: String,
+    /// Index among the parent's MIG devices
+    pub index: u32,
+    pub gpu_instance_id: u32,
+    pub compute_instance_id: u32,
+    /// NVIDIA profile name, e.g. `1g.10gb` or `1c.3g.20gb`
+    pub profile: String,
+    pub multiprocessor_count: u32,
+    pub memory_used: u64,
+    pub memory_total: u64,
+    /// Most drivers do not report utilization per MIG device
+    pub utilization_gpu: Option<u32>,
+}
+
+/// The MIG part of NVML, which nvml-wrapper does not cover. Calls the
+/// library directly with the device handles nvml-wrapper hands out.
+pub(crate) struct MigApi {
+    lib: NvmlLib,
+}
+
+impl MigApi {
+    /// `None` if the library cannot be loaded or predates MIG.
+    pub fn load() -> Option<Self> {
+        NVML_LIB_PATHS.iter().find_map(|path| {
+            // SAFETY: this is the library nvml-wrapper loaded; loading it again
+            // only takes another reference and runs no initialization
+            let lib = unsafe { NvmlLib::new(path) }.ok()?;
+
+            let complete = lib.nvmlDeviceGetMigMode.is_ok()
+                && lib.nvmlDeviceGetMaxMigDeviceCount.is_ok()
+                && lib.nvmlDeviceGetMigDeviceHandleByIndex.is_ok()
+                && lib.nvmlDeviceGetGpuInstanceId.is_ok()
+                && lib.nvmlDeviceGetComputeInstanceId.is_ok()
+                && lib.nvmlDeviceGetAttributes_v2.is_ok();
+
+            complete.then_some(Self { lib })
+        })
+    }
+
+    pub fn is_enabled(&self, device: &Device) -> bool {
+        let (mut current, mut pending) = (0, 0);
+        // SAFETY: the handle comes from a live `Device`, the out pointers are valid
+        let ret = unsafe { self.lib.nvmlDeviceGetMigMode(device.handle(), &mut current, &mut pending) };
+        ret == nvmlReturn_enum_NVML_SUCCESS && current == NVML_DEVICE_MIG_ENABLE
+    }
+
+    /// The MIG devices of `parent`, as nvml-wrapper devices (for memory and
+    /// processes) together with their description.
+    pub fn devices<'nvml>(&self, nvml: &'nvml Nvml, parent: &Device<'nvml>) -> Vec<(Device<'nvml>, MigDevice)> {
+        let mut max_count = 0;
+        // SAFETY: as above, for every call in this function
+        let ret = unsafe { self.lib.nvmlDeviceGetMaxMigDeviceCount(parent.handle(), &mut max_count) };
+        if ret != nvmlReturn_enum_NVML_SUCCESS {
+            return Vec::new();
+        }
+
+        let mut devices = Vec::new();
+
+        for index in 0..max_count {
+            let mut handle: nvmlDevice_t = std::ptr::null_mut();
+            // Unused slots report NOT_FOUND
+            let ret = unsafe { self.lib.nvmlDeviceGetMigDeviceHandleByIndex(parent.handle(), index, &mut handle) };
+            if ret != nvmlReturn_enum_NVML_SUCCESS {
+                continue;
+            }
+
+            let (mut gpu_instance_id, mut compute_instance_id) = (0, 0);
+            let mut attributes: nvmlDeviceAttributes_t = unsafe { std::mem::zeroed() };
+            let described = unsafe {
+                self.lib.nvmlDeviceGetGpuInstanceId(handle, &mut gpu_instance_id) == nvmlReturn_enum_NVML_SUCCESS
+                    && self.lib.nvmlDeviceGetComputeInstanceId(handle, &mut compute_instance_id)
+                        == nvmlReturn_enum_NVML_SUCCESS
+                    && self.lib.nvmlDeviceGetAttributes_v2(handle, &mut attributes) == nvmlReturn_enum_NVML_SUCCESS
+            };
+            if !described {
+                debug!("Skipping MIG device {} that could not be described", index);
+                continue;
+            }
+
+            // SAFETY: a MIG handle is a device handle for the same library instance
+            let device = unsafe { Device::new(handle, nvml) };
+            let memory = device.memory_info().ok();
+
+            let mig = MigDevice {
+                uuid: device.uuid().unwrap_or_else(|_| format!("MIG-{}-{}", gpu_instance_id, compute_instance_id)),
+                index,
+                gpu_instance_id,
+                compute_instance_id,
+                profile: profile_name(
+                    attributes.gpuInstanceSliceCount,
+                    attributes.computeInstanceSliceCount,
+                    attributes.memorySizeMB,
+                ),
+                multiprocessor_count: attributes.multiprocessorCount,
+                memory_used: memory.as_ref().map_or(0, |m| m.used),
+                memory_total: memory.map_or(attributes.memorySizeMB << 20, |m| m.total),
+                utilization_gpu: device.utilization_rates().ok().map(|u| u.gpu),
+            };
+
+            devices.push((device, mig));
+        }
+
+        devices
+    }
+}
+
+/// NVIDIA's profile naming: `<gpu slices>g.<memory>gb`, prefixed with
+/// `<compute slices>c.` when the compute instance uses part of the GPU
+/// instance.
+fn profile_name(gpu_slices: u32, compute_slices: u32, memory_mb: u64) -> String {
+    let memory_gb = memory_mb.div_ceil(1024);
+
+    if compute_slices > 0 && compute_slices < gpu_slices {
+        format!("{}c.{}g.{}gb", compute_slices, gpu_slices, memory_gb)
+    } else {
+        format!("{}g.{}gb", gpu_slices, memory_gb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_name() {
+        // A100 40GB
+        assert_eq!(profile_name(1, 1, 4864), "1g.5gb");
+        assert_eq!(profile_name(3, 3, 19968), "3g.20gb");
+        assert_eq!(profile_name(7, 7, 40192), "7g.40gb");
+        assert_eq!(profile_name(3, 1, 19968), "1c.3g.20gb");
+    }
+}
@@ -1,59 +1,227 @@
+pub mod amd;
+pub mod devices;
+pub mod events;
+pub mod fdinfo;
+pub mod intel;
+pub mod mig;
 pub mod nvml;
+pub mod replay;
+pub mod simulated;
+pub mod sysfs;
 
-pub use nvml::{GpuMetrics, GpuProcess, NvmlMonitor, NvmlFallbackMonitor};
+pub use amd::AmdBackend;
+pub use devices::{DeviceEvent, DeviceEventKind, DeviceTracker};
+pub use events::{GpuEvent, GpuEventKind, GpuEventSource, GpuEventWatcher};
+pub use intel::IntelBackend;
+pub use mig::MigDevice;
+pub use nvml::{
+    GpuIdentity, GpuMetrics, GpuProcess, GpuSelector, GpuTelemetry, NvmlMonitor, NvmlFallbackMonitor, ProcessUtilization,
+};
+pub use replay::ReplayBackend;
+pub use simulated::SimulatedBackend;
 
-use crate::{config::GpmConfig, error::Result};
+use crate::{
+    config::{GpmConfig, GpuBackendKind},
+    error::{GpmError, Result},
+};
+use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 
-pub enum GpuMonitorBackend {
-    Nvml(NvmlMonitor),
-    Fallback,
+/// A GPU in the `gpus` inventory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnownGpu {
+    pub uuid: String,
+    /// Enumeration index when last seen
+    pub gpu_index: u32,
+    pub name: String,
+    pub serial: Option<String>,
+    pub pci_bus_id: Option<String>,
+    pub vbios_version: Option<String>,
+    pub driver_version: Option<String>,
+    pub memory_total: u64,
+    pub first_seen: chrono::DateTime<chrono::Utc>,
+    pub last_seen: chrono::DateTime<chrono::Utc>,
+}
+
+/// A source of GPU samples. Implementations must be cheap to call once per
+/// poll interval and safe to share between the collector and the API.
+pub trait GpuBackend: Send + Sync {
+    /// Short identifier used in logs and the dashboard info endpoint.
+    fn name(&self) -> &'static str;
+
+    /// Whether the devices are driven by the NVIDIA driver, which is what
+    /// reports Xid and ECC events.
+    fn is_nvidia(&self) -> bool {
+        false
+    }
+
+    fn collect_metrics(&self) -> Result<Vec<GpuMetrics>>;
+
+    fn device_count(&self) -> u32;
+}
+
+pub struct GpuMonitorBackend {
+    backend: Box<dyn GpuBackend>,
 }
 
 impl GpuMonitorBackend {
+    pub fn new(backend: Box<dyn GpuBackend>) -> Self {
+        Self { backend }
+    }
+
     pub fn initialize(config: &GpmConfig) -> Result<Self> {
+        match config.gpu.backend {
+            GpuBackendKind::Auto => Self::initialize_auto(config),
+            GpuBackendKind::Nvml => {
+                info!("Using NVML backend (by configuration)");
+                let monitor = NvmlMonitor::new()?.with_reenumerate_interval(reenumerate_interval(config));
+                Ok(Self::new(Box::new(monitor)))
+            }
+            GpuBackendKind::NvidiaSmi => {
+                info!("Using nvidia-smi backend (by configuration)");
+                Ok(Self::new(Box::new(NvmlFallbackMonitor)))
+            }
+            GpuBackendKind::Amd => {
+                info!("Using AMD sysfs backend (by configuration)");
+                Ok(Self::new(Box::new(AmdBackend::with_root_prefix(&config.gpu.root_prefix)?)))
+            }
+            GpuBackendKind::Intel => {
+                info!("Using Intel backend (by configuration)");
+                Ok(Self::new(Box::new(IntelBackend::with_root_prefix(&config.gpu.root_prefix)?)))
+            }
+            GpuBackendKind::Simulated => {
+                info!("Using simulated GPU backend");
+                Ok(Self::new(Box::new(SimulatedBackend::new(config.gpu.simulated.clone()))))
+            }
+            GpuBackendKind::Replay => {
+                let path = config.gpu.replay_path.as_ref().ok_or_else(|| {
+                    GpmError::ConfigError(config::ConfigError::Message(
+                        "gpu.replay_path is required for the replay backend".to_string(),
+                    ))
+                })?;
+                info!("Using replay GPU backend from {}", path.display());
+                let backend = ReplayBackend::from_file(path)?.with_loop(config.gpu.replay_loop);
+                Ok(Self::new(Box::new(backend)))
+            }
+        }
+    }
+
+    fn initialize_auto(config: &GpmConfig) -> Result<Self> {
+        let mut nvml_error = None;
+
         if config.gpu.enable_nvml {
             match NvmlMonitor::new() {
                 Ok(monitor) => {
                     info!("Using NVML backend");
-                    return Ok(Self::Nvml(monitor));
+                    let monitor = monitor.with_reenumerate_interval(reenumerate_interval(config));
+                    return Ok(Self::new(Box::new(monitor)));
                 }
                 Err(e) => {
                     warn!("NVML initialization failed: {}", e);
-                    if config.gpu.fallback_to_nvidia_smi {
-                        info!("Falling back to nvidia-smi");
-                        return Ok(Self::Fallback);
-                    }
-                    return Err(e);
+                    nvml_error = Some(e);
                 }
             }
         }
 
+        // With the NVIDIA driver loaded, NVML failing means its library is
+        // broken (e.g. mid driver update), not that the GPUs are another vendor's
+        let nvidia_driver = nvidia_driver_loaded(&config.gpu.root_prefix);
+        if config.gpu.fallback_to_nvidia_smi && nvidia_driver {
+            warn!("Falling back to nvidia-smi");
+            return Ok(Self::new(Box::new(NvmlFallbackMonitor)));
+        }
+
+        if let Ok(amd) = AmdBackend::with_root_prefix(&config.gpu.root_prefix) {
+            info!("Using AMD sysfs backend");
+            return Ok(Self::new(Box::new(amd)));
+        }
+
         if config.gpu.fallback_to_nvidia_smi {
-            info!("Using nvidia-smi backend (by configuration)");
-            Ok(Self::Fallback)
-        } else {
-            Err(crate::error::GpmError::ServiceUnavailable(
-                "No GPU monitoring backend available".to_string()
-            ))
+            info!("Falling back to nvidia-smi");
+            return Ok(Self::new(Box::new(NvmlFallbackMonitor)));
+        }
+
+        // Never in place of NVIDIA GPUs that cannot be read: most NVIDIA
+        // machines also have an Intel iGPU. `backend = "intel"` still picks it.
+        if nvidia_driver {
+            warn!("NVIDIA driver is loaded but no NVIDIA backend is usable; not falling back to Intel");
+        } else if let Ok(intel) = IntelBackend::with_root_prefix(&config.gpu.root_prefix) {
+            info!("Using Intel backend");
+            return Ok(Self::new(Box::new(intel)));
         }
+
+        Err(nvml_error.unwrap_or_else(|| {
+            GpmError::ServiceUnavailable("No GPU monitoring backend available".to_string())
+        }))
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    pub fn is_nvidia(&self) -> bool {
+        self.backend.is_nvidia()
     }
 
     pub fn collect_metrics(&self) -> Result<Vec<GpuMetrics>> {
-        match self {
-            Self::Nvml(monitor) => monitor.collect_metrics(),
-            Self::Fallback => NvmlFallbackMonitor::collect_metrics(),
-        }
+        self.backend.collect_metrics()
     }
 
     pub fn device_count(&self) -> u32 {
-        match self {
-            Self::Nvml(monitor) => monitor.device_count(),
-            Self::Fallback => {
-                NvmlFallbackMonitor::collect_metrics()
-                    .map(|m| m.len() as u32)
-                    .unwrap_or(0)
-            }
-        }
+        self.backend.device_count()
+    }
+}
+
+/// Whether the NVIDIA kernel driver is loaded, whether or not NVML works.
+fn nvidia_driver_loaded(root_prefix: &std::path::Path) -> bool {
+    root_prefix.join("proc/driver/nvidia").is_dir()
+}
+
+fn reenumerate_interval(config: &GpmConfig) -> std::time::Duration {
+    std::time::Duration::from_secs(config.gpu.reenumerate_interval_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auto_config(root: &std::path::Path) -> GpmConfig {
+        let mut config = GpmConfig::default();
+        config.gpu.enable_nvml = false;
+        config.gpu.root_prefix = root.to_path_buf();
+        config
+    }
+
+    fn fake_card(root: &std::path::Path, card: &str, vendor: &str) {
+        sysfs::testing::fake_card(&root.join("sys/class/drm"), card, vendor, "0000:03:00.0");
+    }
+
+    #[test]
+    fn test_auto_prefers_nvidia_smi_while_driver_loaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fake_card(root, "card0", "0x1002");
+        std::fs::create_dir_all(root.join("proc/driver/nvidia")).unwrap();
+
+        let mut config = auto_config(root);
+        config.gpu.fallback_to_nvidia_smi = true;
+        assert_eq!(GpuMonitorBackend::initialize(&config).unwrap().backend_name(), "nvidia-smi");
+
+        std::fs::remove_dir_all(root.join("proc/driver/nvidia")).unwrap();
+        assert_eq!(GpuMonitorBackend::initialize(&config).unwrap().backend_name(), "amd_sysfs");
+    }
+
+    #[test]
+    fn test_auto_skips_igpu_next_to_nvidia_driver() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fake_card(root, "card0", "0x8086");
+        std::fs::create_dir_all(root.join("proc/driver/nvidia")).unwrap();
+
+        let config = auto_config(root);
+        assert!(GpuMonitorBackend::initialize(&config).is_err());
+
+        std::fs::remove_dir_all(root.join("proc/driver/nvidia")).unwrap();
+        assert_eq!(GpuMonitorBackend::initialize(&config).unwrap().backend_name(), "intel");
     }
 }
@@ -1,21 +1,31 @@
+use super::mig::{MigApi, MigDevice};
+use super::GpuBackend;
 use crate::error::{GpmError, Result};
 use nvml_wrapper::{Device, Nvml};
-use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::ffi::OsStr;
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 
 /// NVML library names/paths to try, in order of preference.
 /// Most systems have libnvidia-ml.so.1 (versioned), not libnvidia-ml.so.
-const NVML_LIB_PATHS: &[&str] = &[
+pub(crate) const NVML_LIB_PATHS: &[&str] = &[
     "libnvidia-ml.so",              // Default (works if symlink exists)
     "libnvidia-ml.so.1",            // Versioned library (most common)
     "/usr/lib/x86_64-linux-gnu/libnvidia-ml.so.1",  // Full path (common Debian/Ubuntu)
     "/usr/lib64/libnvidia-ml.so.1", // Full path (common Fedora/RHEL)
 ];
 
-static NVML_INSTANCE: OnceCell<Arc<Nvml>> = OnceCell::new();
+/// NVML only enumerates devices in `nvmlInit`, so hot-plugged cards are
+/// picked up by re-initializing this often.
+const DEFAULT_REENUMERATE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Backoff bounds for re-initializing NVML after it went away (driver
+/// reload, GPU reset).
+const MIN_RETRY_DELAY: Duration = Duration::from_secs(5);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GpuMetrics {
@@ -29,6 +39,113 @@ pub struct GpuMetrics {
     pub temperature: u32,
     pub power_usage: u32,
     pub processes: Vec<GpuProcess>,
+    #[serde(default)]
+    pub telemetry: GpuTelemetry,
+    #[serde(default)]
+    pub identity: GpuIdentity,
+    /// Energy used since the previous sample of this GPU, filled in by the
+    /// collector (see `accounting::EnergyMeter`)
+    #[serde(default)]
+    pub energy_wh: Option<f64>,
+    /// MIG devices carved out of this GPU, when MIG mode is enabled
+    #[serde(default)]
+    pub mig_devices: Vec<MigDevice>,
+}
+
+impl GpuMetrics {
+    /// Key that survives re-enumeration: the UUID when the backend reports
+    /// one, else the index.
+    pub fn stable_id(&self) -> String {
+        self.identity.uuid.clone().unwrap_or_else(|| self.gpu_id.to_string())
+    }
+}
+
+/// Identifies the physical card behind an enumeration index, which can
+/// change when cards are added or `CUDA_VISIBLE_DEVICES` reorders them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GpuIdentity {
+    /// e.g. `GPU-5a1c3e0b-...`
+    pub uuid: Option<String>,
+    pub serial: Option<String>,
+    /// e.g. `00000000:01:00.0`
+    pub pci_bus_id: Option<String>,
+    pub vbios_version: Option<String>,
+    pub driver_version: Option<String>,
+}
+
+/// A GPU given by enumeration index or UUID, as accepted by the API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpuSelector {
+    Index(u32),
+    Uuid(String),
+}
+
+impl GpuSelector {
+    pub fn parse(s: &str) -> Self {
+        match s.trim().parse() {
+            Ok(index) => Self::Index(index),
+            Err(_) => Self::Uuid(s.trim().to_string()),
+        }
+    }
+
+    pub fn matches(&self, metrics: &GpuMetrics) -> bool {
+        match self {
+            Self::Index(index) => metrics.gpu_id == *index,
+            Self::Uuid(uuid) => metrics
+                .identity
+                .uuid
+                .as_deref()
+                .is_some_and(|u| u.eq_ignore_ascii_case(uuid)),
+        }
+    }
+}
+
+/// Secondary device readings. Each is `None` when the device or backend
+/// does not report it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GpuTelemetry {
+    pub clock_graphics_mhz: Option<u32>,
+    pub clock_sm_mhz: Option<u32>,
+    pub clock_memory_mhz: Option<u32>,
+    pub fan_speed_percent: Option<u32>,
+    /// PCIe throughput in KB/s
+    pub pcie_tx_kbps: Option<u32>,
+    pub pcie_rx_kbps: Option<u32>,
+    pub pcie_link_gen: Option<u32>,
+    pub pcie_link_width: Option<u32>,
+    /// Active clock throttle reasons, e.g. `sw_power_cap`, `hw_thermal_slowdown`
+    #[serde(default)]
+    pub throttle_reasons: Vec<String>,
+    /// Performance state, 0 (P0, maximum) to 15 (P15, minimum)
+    pub performance_state: Option<u32>,
+    pub power_limit_watts: Option<u32>,
+    /// Volatile (since driver load) ECC error counts
+    pub ecc_corrected_errors: Option<u64>,
+    pub ecc_uncorrected_errors: Option<u64>,
+    /// Energy counter since the driver was loaded, in millijoules
+    pub total_energy_mj: Option<u64>,
+}
+
+/// NVML clock throttle reason bits and the names GPM reports for them.
+/// nvidia-smi prints the same bitmask as `clocks_throttle_reasons.active`.
+pub const THROTTLE_REASONS: &[(u64, &str)] = &[
+    (0x1, "gpu_idle"),
+    (0x2, "applications_clocks_setting"),
+    (0x4, "sw_power_cap"),
+    (0x8, "hw_slowdown"),
+    (0x10, "sync_boost"),
+    (0x20, "sw_thermal_slowdown"),
+    (0x40, "hw_thermal_slowdown"),
+    (0x80, "hw_power_brake_slowdown"),
+    (0x100, "display_clock_setting"),
+];
+
+pub fn throttle_reason_names(bits: u64) -> Vec<String> {
+    THROTTLE_REASONS
+        .iter()
+        .filter(|(bit, _)| bits & bit != 0)
+        .map(|(_, name)| name.to_string())
+        .collect()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,18 +153,76 @@ pub struct GpuProcess {
     pub pid: u32,
     pub name: String,
     pub used_gpu_memory: u64,
+    /// Per-process engine utilization from the driver, if it reports it
+    #[serde(default)]
+    pub utilization: Option<ProcessUtilization>,
+    /// UUID of the MIG device the process runs on
+    #[serde(default)]
+    pub mig_device: Option<String>,
+}
+
+/// Utilization of each GPU engine by one process, in percent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessUtilization {
+    pub sm: u32,
+    pub memory: u32,
+    pub encoder: u32,
+    pub decoder: u32,
 }
 
 pub struct NvmlMonitor {
-    nvml: Arc<Nvml>,
+    state: Mutex<NvmlState>,
+    reenumerate_interval: Duration,
+    /// Newest process utilization sample timestamp read per device
+    utilization_seen: Mutex<HashMap<u32, u64>>,
+    /// `None` when the driver predates MIG. Reloaded along with NVML
+    mig: Mutex<Option<MigApi>>,
+    /// Held for a whole collection, so no poll still holds the old handle
+    /// when `connection` re-initializes NVML
+    collecting: Mutex<()>,
+}
+
+struct NvmlState {
+    /// `None` while NVML is unavailable and waiting for `retry_at`
+    nvml: Option<Arc<Nvml>>,
     device_count: u32,
+    /// `None` forces a re-enumeration on the next poll
+    enumerated_at: Option<Instant>,
+    /// Consecutive failed initializations
+    failures: u32,
+    retry_at: Instant,
 }
 
 impl NvmlMonitor {
     pub fn new() -> Result<Self> {
-        let nvml = NVML_INSTANCE.get_or_try_init(|| {
-            Self::init_nvml()
-        })?;
+        let (nvml, device_count) = Self::connect()?;
+
+        info!("NVML initialized successfully with {} device(s)", device_count);
+
+        Ok(Self {
+            state: Mutex::new(NvmlState {
+                nvml: Some(nvml),
+                device_count,
+                enumerated_at: Some(Instant::now()),
+                failures: 0,
+                retry_at: Instant::now(),
+            }),
+            reenumerate_interval: DEFAULT_REENUMERATE_INTERVAL,
+            utilization_seen: Mutex::new(HashMap::new()),
+            mig: Mutex::new(MigApi::load()),
+            collecting: Mutex::new(()),
+        })
+    }
+
+    /// How often to re-read the device list. Zero only re-enumerates after
+    /// a device fails.
+    pub fn with_reenumerate_interval(mut self, interval: Duration) -> Self {
+        self.reenumerate_interval = interval;
+        self
+    }
+
+    fn connect() -> Result<(Arc<Nvml>, u32)> {
+        let nvml = Self::init_nvml()?;
 
         let device_count = nvml.device_count()
             .map_err(|e| {
@@ -55,23 +230,77 @@ impl NvmlMonitor {
                 GpmError::NvmlError(format!("Failed to get device count: {:?}", e))
             })?;
 
-        info!("NVML initialized successfully with {} device(s)", device_count);
+        Ok((nvml, device_count))
+    }
 
-        Ok(Self {
-            nvml: Arc::clone(nvml),
-            device_count,
-        })
+    /// The NVML handle and device count to poll with, re-initializing when
+    /// the device list is due for a refresh or NVML was lost. Callers must
+    /// hold `collecting`.
+    fn connection(&self) -> Result<(Arc<Nvml>, u32)> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        let due = match state.enumerated_at {
+            Some(at) => !self.reenumerate_interval.is_zero() && now.duration_since(at) >= self.reenumerate_interval,
+            None => true,
+        };
+
+        if let (Some(nvml), false) = (&state.nvml, due) {
+            return Ok((Arc::clone(nvml), state.device_count));
+        }
+
+        if state.nvml.is_none() && now < state.retry_at {
+            return Err(GpmError::NvmlError(format!(
+                "NVML unavailable, retrying in {}s",
+                (state.retry_at - now).as_secs()
+            )));
+        }
+
+        // nvmlInit/nvmlShutdown are reference counted and the MIG bindings
+        // keep the library loaded, so both have to go before a fresh init sees
+        // the current devices. Collections are serialized, so this is the
+        // last clone of the handle.
+        let mut mig = self.mig.lock().unwrap();
+        *mig = None;
+        let was_connected = state.nvml.take().is_some();
+
+        match Self::connect() {
+            Ok((nvml, device_count)) => {
+                if !was_connected {
+                    info!("NVML re-initialized with {} device(s)", device_count);
+                } else if device_count != state.device_count {
+                    info!("GPU count changed from {} to {}", state.device_count, device_count);
+                }
+
+                // Indexes may now refer to different cards
+                self.utilization_seen.lock().unwrap().clear();
+
+                state.nvml = Some(Arc::clone(&nvml));
+                state.device_count = device_count;
+                state.enumerated_at = Some(now);
+                state.failures = 0;
+                *mig = MigApi::load();
+                Ok((nvml, device_count))
+            }
+            Err(e) => {
+                state.failures += 1;
+                let delay = retry_delay(state.failures);
+                state.retry_at = now + delay;
+                warn!("NVML initialization failed ({}), retrying in {}s", e, delay.as_secs());
+                Err(e)
+            }
+        }
     }
 
     /// Initialize NVML by trying multiple library paths.
     /// This handles systems where only the versioned library (libnvidia-ml.so.1) exists.
-    fn init_nvml() -> Result<Arc<Nvml>> {
-        info!("Initializing NVML");
+    pub(crate) fn init_nvml() -> Result<Arc<Nvml>> {
+        debug!("Initializing NVML");
 
         // First try the default initialization (works if libnvidia-ml.so symlink exists)
         match Nvml::init() {
             Ok(nvml) => {
-                info!("NVML initialized with default library");
+                debug!("NVML initialized with default library");
                 return Ok(Arc::new(nvml));
             }
             Err(default_err) => {
@@ -87,7 +316,7 @@ impl NvmlMonitor {
                 .init()
             {
                 Ok(nvml) => {
-                    info!("NVML initialized successfully with library: {}", lib_path);
+                    debug!("NVML initialized successfully with library: {}", lib_path);
                     return Ok(Arc::new(nvml));
                 }
                 Err(e) => {
@@ -97,46 +326,27 @@ impl NvmlMonitor {
         }
 
         // All attempts failed
-        error!("Failed to initialize NVML with all available library paths");
         Err(GpmError::NvmlInitError(
             "Could not find libnvidia-ml.so or libnvidia-ml.so.1".to_string()
         ))
     }
 
-    pub fn device_count(&self) -> u32 {
-        self.device_count
-    }
-
-    pub fn collect_metrics(&self) -> Result<Vec<GpuMetrics>> {
-        let mut all_metrics = Vec::new();
-
-        for i in 0..self.device_count {
-            match self.collect_device_metrics(i) {
-                Ok(metrics) => all_metrics.push(metrics),
-                Err(e) => {
-                    warn!("Failed to collect metrics for GPU {}: {}", i, e);
-                }
-            }
-        }
-
-        if all_metrics.is_empty() && self.device_count > 0 {
-            return Err(GpmError::NvmlError(
-                "Failed to collect metrics from any GPU".to_string()
-            ));
-        }
-
-        Ok(all_metrics)
-    }
-
-    fn collect_device_metrics(&self, index: u32) -> Result<GpuMetrics> {
-        let device = self.nvml.device_by_index(index)
+    fn collect_device_metrics(&self, nvml: &Nvml, index: u32) -> Result<GpuMetrics> {
+        let device = nvml.device_by_index(index)
             .map_err(|e| GpmError::NvmlError(format!("Failed to get device {}: {:?}", index, e)))?;
 
         let name = device.name()
             .unwrap_or_else(|_| format!("GPU {}", index));
 
-        let utilization = device.utilization_rates()
-            .map_err(|e| GpmError::NvmlError(format!("Failed to get utilization: {:?}", e)))?;
+        let mig = self.mig.lock().unwrap();
+        let mig_enabled = mig.as_ref().is_some_and(|mig| mig.is_enabled(&device));
+
+        // MIG-enabled GPUs do not report device utilization
+        let utilization = match device.utilization_rates() {
+            Ok(utilization) => utilization,
+            Err(_) if mig_enabled => nvml_wrapper::struct_wrappers::device::Utilization { gpu: 0, memory: 0 },
+            Err(e) => return Err(GpmError::NvmlError(format!("Failed to get utilization: {:?}", e))),
+        };
 
         let memory_info = device.memory_info()
             .map_err(|e| GpmError::NvmlError(format!("Failed to get memory info: {:?}", e)))?;
@@ -148,7 +358,19 @@ impl NvmlMonitor {
             .map(|p| p / 1000)
             .unwrap_or(0);
 
-        let processes = self.get_running_processes(&device)?;
+        let mut processes = self.get_running_processes(index, &device)?;
+        let mig_devices = match mig.as_ref() {
+            Some(mig) if mig_enabled => Self::collect_mig_devices(mig, nvml, &device, &mut processes),
+            _ => Vec::new(),
+        };
+        let telemetry = Self::collect_telemetry(&device);
+        let identity = GpuIdentity {
+            uuid: device.uuid().ok(),
+            serial: device.serial().ok(),
+            pci_bus_id: device.pci_info().ok().map(|p| p.bus_id),
+            vbios_version: device.vbios_version().ok(),
+            driver_version: nvml.sys_driver_version().ok(),
+        };
 
         debug!(
             "GPU {} metrics: util={}%, mem={}%, temp={}°C, power={}W, processes={}",
@@ -171,10 +393,83 @@ impl NvmlMonitor {
             temperature,
             power_usage,
             processes,
+            telemetry,
+            identity,
+            energy_wh: None,
+            mig_devices,
         })
     }
 
-    fn get_running_processes(&self, device: &Device) -> Result<Vec<GpuProcess>> {
+    /// The MIG devices of `device`, attributing each process in `processes`
+    /// to the MIG device it runs on.
+    fn collect_mig_devices(mig: &MigApi, nvml: &Nvml, device: &Device, processes: &mut Vec<GpuProcess>) -> Vec<MigDevice> {
+        let mut mig_devices = Vec::new();
+
+        for (handle, mig_device) in mig.devices(nvml, device) {
+            for proc in handle.running_compute_processes().unwrap_or_default() {
+                let used_gpu_memory = match proc.used_gpu_memory {
+                    nvml_wrapper::enums::device::UsedGpuMemory::Used(bytes) => bytes,
+                    nvml_wrapper::enums::device::UsedGpuMemory::Unavailable => 0,
+                };
+
+                match processes.iter_mut().find(|p| p.pid == proc.pid) {
+                    Some(p) => {
+                        p.mig_device = Some(mig_device.uuid.clone());
+                        // The parent often cannot tell a MIG process's memory
+                        if p.used_gpu_memory == 0 {
+                            p.used_gpu_memory = used_gpu_memory;
+                        }
+                    }
+                    None => processes.push(GpuProcess {
+                        pid: proc.pid,
+                        name: Self::get_process_name(proc.pid),
+                        used_gpu_memory,
+                        utilization: None,
+                        mig_device: Some(mig_device.uuid.clone()),
+                    }),
+                }
+            }
+
+            mig_devices.push(mig_device);
+        }
+
+        processes.sort_by_key(|p| std::cmp::Reverse(p.used_gpu_memory));
+        mig_devices
+    }
+
+    /// Best-effort secondary readings; unsupported queries are left empty
+    /// rather than failing the whole sample.
+    fn collect_telemetry(device: &Device) -> GpuTelemetry {
+        use nvml_wrapper::enum_wrappers::device::{Clock, EccCounter, MemoryError, PcieUtilCounter};
+
+        let performance_state = device
+            .performance_state()
+            .ok()
+            .map(|p| p.as_c())
+            .filter(|p| *p < 16);
+
+        GpuTelemetry {
+            clock_graphics_mhz: device.clock_info(Clock::Graphics).ok(),
+            clock_sm_mhz: device.clock_info(Clock::SM).ok(),
+            clock_memory_mhz: device.clock_info(Clock::Memory).ok(),
+            fan_speed_percent: device.fan_speed(0).ok(),
+            pcie_tx_kbps: device.pcie_throughput(PcieUtilCounter::Send).ok(),
+            pcie_rx_kbps: device.pcie_throughput(PcieUtilCounter::Receive).ok(),
+            pcie_link_gen: device.current_pcie_link_gen().ok(),
+            pcie_link_width: device.current_pcie_link_width().ok(),
+            throttle_reasons: device
+                .current_throttle_reasons()
+                .map(|r| throttle_reason_names(r.bits()))
+                .unwrap_or_default(),
+            performance_state,
+            power_limit_watts: device.enforced_power_limit().ok().map(|p| p / 1000),
+            ecc_corrected_errors: device.total_ecc_errors(MemoryError::Corrected, EccCounter::Volatile).ok(),
+            ecc_uncorrected_errors: device.total_ecc_errors(MemoryError::Uncorrected, EccCounter::Volatile).ok(),
+            total_energy_mj: device.total_energy_consumption().ok(),
+        }
+    }
+
+    fn get_running_processes(&self, index: u32, device: &Device) -> Result<Vec<GpuProcess>> {
         let compute_processes = device.running_compute_processes()
             .unwrap_or_else(|_| Vec::new());
 
@@ -195,13 +490,44 @@ impl NvmlMonitor {
                 pid,
                 name,
                 used_gpu_memory,
+                utilization: None,
+                mig_device: None,
             });
         }
 
+        // Processes without samples were idle during the window
+        if let Some(utilization) = self.process_utilization(index, device) {
+            for proc in &mut all_processes {
+                proc.utilization = Some(utilization.get(&proc.pid).copied().unwrap_or_default());
+            }
+        }
+
         all_processes.sort_by_key(|p| std::cmp::Reverse(p.used_gpu_memory));
         Ok(all_processes)
     }
 
+    /// Average per-process utilization over the samples the driver took
+    /// since the previous poll. `None` if the device does not support it.
+    fn process_utilization(&self, index: u32, device: &Device) -> Option<HashMap<u32, ProcessUtilization>> {
+        let mut seen = self.utilization_seen.lock().unwrap();
+
+        let samples = match device.process_utilization_stats(seen.get(&index).copied()) {
+            Ok(samples) => samples,
+            // No process was busy since the last sample we read
+            Err(nvml_wrapper::error::NvmlError::NotFound) => Vec::new(),
+            Err(e) => {
+                debug!("Process utilization unavailable for GPU {}: {:?}", index, e);
+                return None;
+            }
+        };
+
+        if let Some(latest) = samples.iter().map(|s| s.timestamp).max() {
+            seen.insert(index, latest);
+        }
+
+        Some(average_process_samples(&samples))
+    }
+
     fn get_process_name(pid: u32) -> String {
         use sysinfo::{System, ProcessesToUpdate};
 
@@ -216,27 +542,113 @@ impl NvmlMonitor {
     }
 }
 
+impl GpuBackend for NvmlMonitor {
+    fn name(&self) -> &'static str {
+        "nvml"
+    }
+
+    fn is_nvidia(&self) -> bool {
+        true
+    }
+
+    fn device_count(&self) -> u32 {
+        self.state.lock().unwrap().device_count
+    }
+
+    fn collect_metrics(&self) -> Result<Vec<GpuMetrics>> {
+        let _collecting = self.collecting.lock().unwrap();
+        let (nvml, device_count) = self.connection()?;
+        let mut all_metrics = Vec::new();
+
+        for i in 0..device_count {
+            match self.collect_device_metrics(&nvml, i) {
+                Ok(metrics) => all_metrics.push(metrics),
+                Err(e) => {
+                    warn!("Failed to collect metrics for GPU {}: {}", i, e);
+                }
+            }
+        }
+
+        if all_metrics.len() < device_count as usize {
+            let mut state = self.state.lock().unwrap();
+
+            if all_metrics.is_empty() {
+                // Driver reload or reset: drop the handle and re-initialize
+                // on the next poll
+                drop(nvml);
+                state.nvml = None;
+                state.retry_at = Instant::now();
+                return Err(GpmError::NvmlError(
+                    "Failed to collect metrics from any GPU".to_string()
+                ));
+            }
+
+            // A lost or removed card: refresh the device list next poll
+            state.enumerated_at = None;
+        }
+
+        Ok(all_metrics)
+    }
+}
+
+/// Exponential backoff between NVML initialization attempts.
+fn retry_delay(failures: u32) -> Duration {
+    MIN_RETRY_DELAY
+        .saturating_mul(1 << failures.saturating_sub(1).min(16))
+        .min(MAX_RETRY_DELAY)
+}
+
+fn average_process_samples(
+    samples: &[nvml_wrapper::struct_wrappers::device::ProcessUtilizationSample],
+) -> HashMap<u32, ProcessUtilization> {
+    let mut sums: HashMap<u32, ([u64; 4], u64)> = HashMap::new();
+
+    for s in samples {
+        let (sum, count) = sums.entry(s.pid).or_default();
+        for (total, value) in sum.iter_mut().zip([s.sm_util, s.mem_util, s.enc_util, s.dec_util]) {
+            *total += value as u64;
+        }
+        *count += 1;
+    }
+
+    sums.into_iter()
+        .map(|(pid, (sum, count))| {
+            let avg = |i: usize| (sum[i] / count) as u32;
+            (pid, ProcessUtilization { sm: avg(0), memory: avg(1), encoder: avg(2), decoder: avg(3) })
+        })
+        .collect()
+}
+
 pub struct NvmlFallbackMonitor;
 
-impl NvmlFallbackMonitor {
-    pub fn collect_metrics() -> Result<Vec<GpuMetrics>> {
-        warn!("Using nvidia-smi fallback - performance may be degraded");
+impl GpuBackend for NvmlFallbackMonitor {
+    fn name(&self) -> &'static str {
+        "nvidia-smi"
+    }
 
-        let output = std::process::Command::new("nvidia-smi")
-            .args([
-                "--query-gpu=index,name,utilization.gpu,utilization.memory,memory.used,memory.total,temperature.gpu,power.draw",
-                "--format=csv,noheader,nounits"
-            ])
-            .output()
-            .map_err(|e| GpmError::NvmlError(format!("Failed to run nvidia-smi: {}", e)))?;
+    fn is_nvidia(&self) -> bool {
+        true
+    }
 
-        if !output.status.success() {
-            return Err(GpmError::NvmlError(
-                "nvidia-smi command failed".to_string()
-            ));
+    fn collect_metrics(&self) -> Result<Vec<GpuMetrics>> {
+        warn!("Using nvidia-smi fallback - performance may be degraded");
+
+        // The XML report has everything, processes included, in one call
+        match Self::run(&["-q", "-x"]).and_then(|xml| Self::parse_nvidia_smi_xml(&xml)) {
+            Ok(metrics) if !metrics.is_empty() => return Ok(metrics),
+            Ok(_) => debug!("nvidia-smi XML report has no GPUs, using CSV queries"),
+            Err(e) => debug!("nvidia-smi XML report failed ({}), using CSV queries", e),
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
+        // Older drivers reject unknown fields, so retry with the basic set
+        let stdout = match Self::query(&[NVIDIA_SMI_FIELDS, NVIDIA_SMI_EXTENDED_FIELDS].join(",")) {
+            Ok(stdout) => stdout,
+            Err(e) => {
+                debug!("Extended nvidia-smi query failed ({}), using basic fields", e);
+                Self::query(NVIDIA_SMI_FIELDS)?
+            }
+        };
+
         let mut metrics = Vec::new();
 
         for line in stdout.lines() {
@@ -245,9 +657,53 @@ impl NvmlFallbackMonitor {
             }
         }
 
+        match Self::run(&[&format!("--query-compute-apps={}", NVIDIA_SMI_APP_FIELDS), "--format=csv,noheader,nounits"]) {
+            Ok(apps) => Self::attach_compute_apps(&mut metrics, &apps),
+            Err(e) => debug!("nvidia-smi process query failed: {}", e),
+        }
+
         Ok(metrics)
     }
 
+    fn device_count(&self) -> u32 {
+        self.collect_metrics()
+            .map(|m| m.len() as u32)
+            .unwrap_or(0)
+    }
+}
+
+const NVIDIA_SMI_FIELDS: &str =
+    "index,name,utilization.gpu,utilization.memory,memory.used,memory.total,temperature.gpu,power.draw";
+
+/// Parsed from columns 8.. when present. PCIe throughput is not exposed
+/// by `--query-gpu`.
+const NVIDIA_SMI_EXTENDED_FIELDS: &str = "clocks.gr,clocks.sm,clocks.mem,fan.speed,pcie.link.gen.current,\
+pcie.link.width.current,clocks_throttle_reasons.active,pstate,power.limit,\
+ecc.errors.corrected.volatile.total,ecc.errors.uncorrected.volatile.total,\
+uuid,pci.bus_id,serial,vbios_version,driver_version";
+
+const NVIDIA_SMI_APP_FIELDS: &str = "gpu_uuid,pid,process_name,used_memory";
+
+impl NvmlFallbackMonitor {
+    fn query(fields: &str) -> Result<String> {
+        Self::run(&[&format!("--query-gpu={}", fields), "--format=csv,noheader,nounits"])
+    }
+
+    fn run(args: &[&str]) -> Result<String> {
+        let output = std::process::Command::new("nvidia-smi")
+            .args(args)
+            .output()
+            .map_err(|e| GpmError::NvmlError(format!("Failed to run nvidia-smi: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(GpmError::NvmlError(
+                "nvidia-smi command failed".to_string()
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
     fn parse_nvidia_smi_line(line: &str) -> Option<GpuMetrics> {
         let parts: Vec<&str> = line.split(',').map(|s| s.trim()).collect();
 
@@ -255,6 +711,39 @@ impl NvmlFallbackMonitor {
             return None;
         }
 
+        // "[N/A]" / "[Not Supported]" fail to parse and become None
+        let field = |i: usize| parts.get(i).copied();
+        let number = |i: usize| field(i).and_then(|v| v.parse::<f64>().ok());
+        let text = |i: usize| field(i).filter(|v| !v.is_empty() && !v.starts_with('[')).map(str::to_string);
+
+        let telemetry = GpuTelemetry {
+            clock_graphics_mhz: number(8).map(|v| v as u32),
+            clock_sm_mhz: number(9).map(|v| v as u32),
+            clock_memory_mhz: number(10).map(|v| v as u32),
+            fan_speed_percent: number(11).map(|v| v as u32),
+            pcie_tx_kbps: None,
+            pcie_rx_kbps: None,
+            pcie_link_gen: number(12).map(|v| v as u32),
+            pcie_link_width: number(13).map(|v| v as u32),
+            throttle_reasons: field(14)
+                .and_then(|v| u64::from_str_radix(v.trim_start_matches("0x"), 16).ok())
+                .map(throttle_reason_names)
+                .unwrap_or_default(),
+            performance_state: field(15).and_then(|v| v.strip_prefix('P')).and_then(|v| v.parse().ok()),
+            power_limit_watts: number(16).map(|v| v as u32),
+            ecc_corrected_errors: number(17).map(|v| v as u64),
+            ecc_uncorrected_errors: number(18).map(|v| v as u64),
+            total_energy_mj: None,
+        };
+
+        let identity = GpuIdentity {
+            uuid: text(19),
+            pci_bus_id: text(20),
+            serial: text(21),
+            vbios_version: text(22),
+            driver_version: text(23),
+        };
+
         Some(GpuMetrics {
             timestamp: chrono::Utc::now(),
             gpu_id: parts[0].parse().ok()?,
@@ -265,9 +754,185 @@ impl NvmlFallbackMonitor {
             memory_total: parts[5].parse::<u64>().ok()? * 1024 * 1024,
             temperature: parts[6].parse().ok()?,
             power_usage: parts[7].parse::<f64>().ok()? as u32,
+            // Filled in from --query-compute-apps
             processes: Vec::new(),
+            telemetry,
+            identity,
+            energy_wh: None,
+            mig_devices: Vec::new(),
         })
     }
+
+    /// Add the processes from `--query-compute-apps` output to the GPUs they
+    /// run on, matched by UUID. Without UUIDs (basic query) a lone GPU gets
+    /// them all.
+    fn attach_compute_apps(metrics: &mut [GpuMetrics], apps: &str) {
+        let single_gpu = metrics.len() == 1;
+
+        for line in apps.lines() {
+            let parts: Vec<&str> = line.split(',').map(|s| s.trim()).collect();
+            let [uuid, pid, name, used_memory] = parts[..] else {
+                continue;
+            };
+            let Ok(pid) = pid.parse() else {
+                continue;
+            };
+
+            let gpu = metrics.iter_mut().find(|m| {
+                m.identity.uuid.as_deref().map_or(single_gpu, |id| id.eq_ignore_ascii_case(uuid))
+            });
+            if let Some(gpu) = gpu {
+                gpu.processes.push(GpuProcess {
+                    pid,
+                    name: short_process_name(name),
+                    used_gpu_memory: used_memory.parse::<u64>().unwrap_or(0) * 1024 * 1024,
+                    utilization: None,
+                    mig_device: None,
+                });
+            }
+        }
+
+        for gpu in metrics {
+            gpu.processes.sort_by_key(|p| std::cmp::Reverse(p.used_gpu_memory));
+        }
+    }
+
+    /// Parse the `nvidia-smi -q -x` report. GPUs are listed in the same
+    /// order as NVML indexes them.
+    fn parse_nvidia_smi_xml(xml: &str) -> Result<Vec<GpuMetrics>> {
+        let options = roxmltree::ParsingOptions {
+            allow_dtd: true,
+            ..Default::default()
+        };
+        let doc = roxmltree::Document::parse_with_options(xml, options)
+            .map_err(|e| GpmError::InvalidData(format!("Invalid nvidia-smi XML: {}", e)))?;
+        let root = doc.root_element();
+        let driver_version = xml_text(root, &["driver_version"]);
+
+        root.children()
+            .filter(|n| n.has_tag_name("gpu"))
+            .enumerate()
+            .map(|(index, gpu)| Self::parse_xml_gpu(index as u32, gpu, driver_version.clone()))
+            .collect()
+    }
+
+    fn parse_xml_gpu(index: u32, gpu: roxmltree::Node, driver_version: Option<String>) -> Result<GpuMetrics> {
+        let number = |path: &[&str]| xml_number(gpu, path);
+        let required = |path: &[&str]| {
+            number(path).ok_or_else(|| GpmError::InvalidData(format!("nvidia-smi XML lacks {}", path.join("/"))))
+        };
+        // Newer drivers moved power readings and renamed throttle to event reasons
+        let power = |field: &str| number(&["gpu_power_readings", field]).or_else(|| number(&["power_readings", field]));
+
+        let throttle_reasons = gpu
+            .children()
+            .filter(|n| n.has_tag_name("clocks_event_reasons") || n.has_tag_name("clocks_throttle_reasons"))
+            .flat_map(|n| n.children())
+            .filter(|n| n.text().map(str::trim) == Some("Active"))
+            .filter_map(|n| {
+                let name = n.tag_name().name();
+                let name = name
+                    .strip_prefix("clocks_event_reason_")
+                    .or_else(|| name.strip_prefix("clocks_throttle_reason_"))?;
+                let name = if name == "display_clocks_setting" { "display_clock_setting" } else { name };
+                THROTTLE_REASONS.iter().find(|(_, n)| *n == name).map(|(_, n)| n.to_string())
+            })
+            .collect();
+
+        // Older drivers report single/double bit totals, newer ones SRAM and DRAM
+        let ecc = |legacy: &str, sram: &str, dram: &str| {
+            number(&["ecc_errors", "volatile", legacy, "total"]).or_else(|| {
+                let sram = number(&["ecc_errors", "volatile", sram]);
+                let dram = number(&["ecc_errors", "volatile", dram]);
+                (sram.is_some() || dram.is_some()).then(|| sram.unwrap_or(0.0) + dram.unwrap_or(0.0))
+            })
+        };
+
+        let telemetry = GpuTelemetry {
+            clock_graphics_mhz: number(&["clocks", "graphics_clock"]).map(|v| v as u32),
+            clock_sm_mhz: number(&["clocks", "sm_clock"]).map(|v| v as u32),
+            clock_memory_mhz: number(&["clocks", "mem_clock"]).map(|v| v as u32),
+            fan_speed_percent: number(&["fan_speed"]).map(|v| v as u32),
+            pcie_tx_kbps: number(&["pci", "tx_util"]).map(|v| v as u32),
+            pcie_rx_kbps: number(&["pci", "rx_util"]).map(|v| v as u32),
+            pcie_link_gen: number(&["pci", "pci_gpu_link_info", "pcie_gen", "current_link_gen"]).map(|v| v as u32),
+            pcie_link_width: number(&["pci", "pci_gpu_link_info", "link_widths", "current_link_width"])
+                .map(|v| v as u32),
+            throttle_reasons,
+            performance_state: xml_text(gpu, &["performance_state"])
+                .and_then(|p| p.strip_prefix('P').and_then(|p| p.parse().ok())),
+            power_limit_watts: power("current_power_limit").or_else(|| power("power_limit")).map(|v| v as u32),
+            ecc_corrected_errors: ecc("single_bit", "sram_correctable", "dram_correctable").map(|v| v as u64),
+            ecc_uncorrected_errors: ecc("double_bit", "sram_uncorrectable", "dram_uncorrectable").map(|v| v as u64),
+            total_energy_mj: None,
+        };
+
+        let identity = GpuIdentity {
+            uuid: xml_text(gpu, &["uuid"]),
+            serial: xml_text(gpu, &["serial"]),
+            pci_bus_id: xml_text(gpu, &["pci", "pci_bus_id"]),
+            vbios_version: xml_text(gpu, &["vbios_version"]),
+            driver_version,
+        };
+
+        let mut processes: Vec<GpuProcess> = xml_child(gpu, &["processes"])
+            .into_iter()
+            .flat_map(|p| p.children().filter(|n| n.has_tag_name("process_info")))
+            .filter_map(|p| {
+                Some(GpuProcess {
+                    pid: xml_number(p, &["pid"])? as u32,
+                    name: short_process_name(&xml_text(p, &["process_name"]).unwrap_or_default()),
+                    used_gpu_memory: xml_number(p, &["used_memory"]).unwrap_or(0.0) as u64 * 1024 * 1024,
+                    utilization: None,
+                    mig_device: None,
+                })
+            })
+            .collect();
+        processes.sort_by_key(|p| std::cmp::Reverse(p.used_gpu_memory));
+
+        Ok(GpuMetrics {
+            timestamp: chrono::Utc::now(),
+            gpu_id: index,
+            name: xml_text(gpu, &["product_name"]).unwrap_or_else(|| format!("GPU {}", index)),
+            utilization_gpu: required(&["utilization", "gpu_util"])? as u32,
+            utilization_memory: number(&["utilization", "memory_util"]).unwrap_or(0.0) as u32,
+            memory_used: required(&["fb_memory_usage", "used"])? as u64 * 1024 * 1024,
+            memory_total: required(&["fb_memory_usage", "total"])? as u64 * 1024 * 1024,
+            temperature: number(&["temperature", "gpu_temp"]).unwrap_or(0.0) as u32,
+            power_usage: power("power_draw").unwrap_or(0.0) as u32,
+            processes,
+            telemetry,
+            identity,
+            energy_wh: None,
+            mig_devices: Vec::new(),
+        })
+    }
+}
+
+fn xml_child<'a, 'input>(node: roxmltree::Node<'a, 'input>, path: &[&str]) -> Option<roxmltree::Node<'a, 'input>> {
+    path.iter()
+        .try_fold(node, |node, tag| node.children().find(|n| n.has_tag_name(*tag)))
+}
+
+/// Element text, without `N/A`-style placeholders.
+fn xml_text(node: roxmltree::Node, path: &[&str]) -> Option<String> {
+    let text = xml_child(node, path)?.text()?.trim();
+    (!text.is_empty() && !matches!(text, "N/A" | "[N/A]" | "Not Supported" | "[Not Supported]"))
+        .then(|| text.to_string())
+}
+
+/// Leading number of a value such as `71 C`, `380.20 W` or `16x`.
+fn xml_number(node: roxmltree::Node, path: &[&str]) -> Option<f64> {
+    let text = xml_text(node, path)?;
+    text.split_whitespace().next()?.trim_end_matches('x').parse().ok()
+}
+
+/// nvidia-smi reports the full executable path; NVML callers see the name.
+fn short_process_name(name: &str) -> String {
+    std::path::Path::new(name)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| name.to_string())
 }
 
 #[cfg(test)]
@@ -284,5 +949,124 @@ mod tests {
         assert_eq!(metrics.utilization_gpu, 45);
         assert_eq!(metrics.utilization_memory, 30);
         assert_eq!(metrics.temperature, 65);
+        assert_eq!(metrics.telemetry, GpuTelemetry::default());
+    }
+
+    #[test]
+    fn test_average_process_samples() {
+        use nvml_wrapper::struct_wrappers::device::ProcessUtilizationSample;
+
+        let sample = |pid, timestamp, sm_util, enc_util| ProcessUtilizationSample {
+            pid,
+            timestamp,
+            sm_util,
+            mem_util: 10,
+            enc_util,
+            dec_util: 0,
+        };
+        let averaged = average_process_samples(&[sample(7, 1, 80, 0), sample(7, 2, 40, 30), sample(8, 2, 5, 0)]);
+
+        assert_eq!(averaged[&7], ProcessUtilization { sm: 60, memory: 10, encoder: 15, decoder: 0 });
+        assert_eq!(averaged[&8].sm, 5);
+    }
+
+    #[test]
+    fn test_parse_nvidia_smi_extended_line() {
+        let line = "1, NVIDIA A100-SXM4-40GB, 98, 60, 30000, 40960, 71, 380.2, 1410, 1410, 1215, [N/A], 4, 16, \
+                    0x0000000000000024, P0, 400.00, 0, 2, GPU-7d2b9c2e-1f3a-4e5b-8c6d-0a1b2c3d4e5f, \
+                    00000000:07:00.0, [N/A], 92.00.36.00.01, 550.54.15";
+        let metrics = NvmlFallbackMonitor::parse_nvidia_smi_line(line).unwrap();
+        let telemetry = metrics.telemetry;
+
+        assert_eq!(telemetry.clock_sm_mhz, Some(1410));
+        assert_eq!(telemetry.clock_memory_mhz, Some(1215));
+        assert_eq!(telemetry.fan_speed_percent, None);
+        assert_eq!(telemetry.pcie_link_gen, Some(4));
+        assert_eq!(telemetry.pcie_link_width, Some(16));
+        assert_eq!(telemetry.throttle_reasons, vec!["sw_power_cap", "sw_thermal_slowdown"]);
+        assert_eq!(telemetry.performance_state, Some(0));
+        assert_eq!(telemetry.power_limit_watts, Some(400));
+        assert_eq!(telemetry.ecc_uncorrected_errors, Some(2));
+
+        let identity = metrics.identity;
+        assert_eq!(identity.uuid.as_deref(), Some("GPU-7d2b9c2e-1f3a-4e5b-8c6d-0a1b2c3d4e5f"));
+        assert_eq!(identity.pci_bus_id.as_deref(), Some("00000000:07:00.0"));
+        assert_eq!(identity.serial, None);
+        assert_eq!(identity.driver_version.as_deref(), Some("550.54.15"));
+    }
+
+    #[test]
+    fn test_parse_nvidia_smi_xml() {
+        let metrics = NvmlFallbackMonitor::parse_nvidia_smi_xml(include_str!("testdata/nvidia-smi-q.xml")).unwrap();
+        assert_eq!(metrics.len(), 2);
+
+        let a100 = &metrics[0];
+        assert_eq!(a100.gpu_id, 0);
+        assert_eq!(a100.name, "NVIDIA A100-SXM4-40GB");
+        assert_eq!(a100.utilization_gpu, 98);
+        assert_eq!(a100.utilization_memory, 60);
+        assert_eq!(a100.memory_used, 30000 * 1024 * 1024);
+        assert_eq!(a100.memory_total, 40960 * 1024 * 1024);
+        assert_eq!(a100.temperature, 71);
+        assert_eq!(a100.power_usage, 380);
+        assert_eq!(a100.identity.uuid.as_deref(), Some("GPU-7d2b9c2e-1f3a-4e5b-8c6d-0a1b2c3d4e5f"));
+        assert_eq!(a100.identity.driver_version.as_deref(), Some("550.54.15"));
+
+        let telemetry = &a100.telemetry;
+        assert_eq!(telemetry.clock_memory_mhz, Some(1215));
+        assert_eq!(telemetry.fan_speed_percent, None);
+        assert_eq!(telemetry.pcie_tx_kbps, Some(1200));
+        assert_eq!(telemetry.pcie_link_width, Some(16));
+        assert_eq!(telemetry.throttle_reasons, vec!["sw_power_cap", "sw_thermal_slowdown"]);
+        assert_eq!(telemetry.power_limit_watts, Some(400));
+        assert_eq!(telemetry.ecc_corrected_errors, Some(3));
+        assert_eq!(telemetry.ecc_uncorrected_errors, Some(2));
+
+        assert_eq!(a100.processes.len(), 2);
+        assert_eq!(a100.processes[0].pid, 4242);
+        assert_eq!(a100.processes[0].name, "python3");
+        assert_eq!(a100.processes[0].used_gpu_memory, 28000 * 1024 * 1024);
+
+        // Older driver layout
+        let rtx = &metrics[1];
+        assert_eq!(rtx.gpu_id, 1);
+        assert_eq!(rtx.identity.serial, None);
+        assert_eq!(rtx.telemetry.performance_state, Some(8));
+        assert_eq!(rtx.telemetry.throttle_reasons, vec!["gpu_idle"]);
+        assert_eq!(rtx.telemetry.power_limit_watts, Some(320));
+        assert_eq!(rtx.telemetry.ecc_corrected_errors, None);
+        assert!(rtx.processes.is_empty());
+    }
+
+    #[test]
+    fn test_attach_compute_apps() {
+        let mut metrics: Vec<_> = ["0, GPU, 0, 0, 0, 0, 0, 0", "1, GPU, 0, 0, 0, 0, 0, 0"]
+            .iter()
+            .map(|l| NvmlFallbackMonitor::parse_nvidia_smi_line(l).unwrap())
+            .collect();
+        metrics[0].identity.uuid = Some("GPU-aaaa".to_string());
+        metrics[1].identity.uuid = Some("GPU-bbbb".to_string());
+
+        NvmlFallbackMonitor::attach_compute_apps(
+            &mut metrics,
+            "GPU-bbbb, 4242, /usr/bin/python3, 2048\nGPU-bbbb, 4343, ollama, 4096\nGPU-cccc, 1, x, 1\n",
+        );
+
+        assert!(metrics[0].processes.is_empty());
+        let names: Vec<_> = metrics[1].processes.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["ollama", "python3"]);
+        assert_eq!(metrics[1].processes[1].used_gpu_memory, 2048 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_gpu_selector() {
+        let mut metrics = NvmlFallbackMonitor::parse_nvidia_smi_line("2, GPU, 0, 0, 0, 0, 0, 0").unwrap();
+        assert!(GpuSelector::parse("2").matches(&metrics));
+        assert!(!GpuSelector::parse("GPU-abc").matches(&metrics));
+        assert_eq!(metrics.stable_id(), "2");
+
+        metrics.identity.uuid = Some("GPU-ABC".to_string());
+        assert!(GpuSelector::parse("gpu-abc").matches(&metrics));
+        assert_eq!(metrics.stable_id(), "GPU-ABC");
     }
 }
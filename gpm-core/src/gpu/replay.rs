@@ -0,0 +1,189 @@
+use super::{GpuBackend, GpuMetrics};
+use crate::error::{GpmError, Result};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::info;
+
+/// Plays back a recorded JSONL trace of `GpuMetrics`.
+///
+/// Each line of the trace is one serialized `GpuMetrics`. Consecutive lines
+/// sharing a timestamp form one frame, and each call to `collect_metrics`
+/// returns the next frame with its timestamps rewritten to the current time.
+pub struct ReplayBackend {
+    frames: Vec<Vec<GpuMetrics>>,
+    position: Mutex<usize>,
+    loop_playback: bool,
+}
+
+impl ReplayBackend {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = std::fs::read_to_string(path.as_ref())?;
+        let backend = Self::from_jsonl(&content)?;
+
+        info!(
+            "Loaded replay trace {} with {} frame(s)",
+            path.as_ref().display(),
+            backend.frames.len()
+        );
+
+        Ok(backend)
+    }
+
+    pub fn from_jsonl(content: &str) -> Result<Self> {
+        let mut frames: Vec<Vec<GpuMetrics>> = Vec::new();
+
+        for (line_no, line) in content.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            let metrics: GpuMetrics = serde_json::from_str(trimmed).map_err(|e| {
+                GpmError::InvalidData(format!("Replay trace line {}: {}", line_no + 1, e))
+            })?;
+
+            match frames.last_mut() {
+                Some(frame) if frame[0].timestamp == metrics.timestamp => frame.push(metrics),
+                _ => frames.push(vec![metrics]),
+            }
+        }
+
+        if frames.is_empty() {
+            return Err(GpmError::InvalidData("Replay trace is empty".to_string()));
+        }
+
+        Ok(Self {
+            frames,
+            position: Mutex::new(0),
+            loop_playback: true,
+        })
+    }
+
+    pub fn with_loop(mut self, loop_playback: bool) -> Self {
+        self.loop_playback = loop_playback;
+        self
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Append one frame to a trace in the format `from_jsonl` reads.
+    pub fn write_frame<W: Write>(writer: &mut W, metrics: &[GpuMetrics]) -> Result<()> {
+        for m in metrics {
+            serde_json::to_writer(&mut *writer, m)?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+impl GpuBackend for ReplayBackend {
+    fn name(&self) -> &'static str {
+        "replay"
+    }
+
+    fn collect_metrics(&self) -> Result<Vec<GpuMetrics>> {
+        let mut position = self.position.lock().unwrap();
+
+        if *position >= self.frames.len() {
+            if !self.loop_playback {
+                return Ok(Vec::new());
+            }
+            *position = 0;
+        }
+
+        let now = chrono::Utc::now();
+        let frame = self.frames[*position]
+            .iter()
+            .cloned()
+            .map(|mut m| {
+                m.timestamp = now;
+                m
+            })
+            .collect();
+
+        *position += 1;
+        Ok(frame)
+    }
+
+    fn device_count(&self) -> u32 {
+        self.frames
+            .iter()
+            .map(|f| f.len() as u32)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu::GpuProcess;
+
+    fn sample(ts: &str, gpu_id: u32, util: u32) -> GpuMetrics {
+        GpuMetrics {
+            timestamp: chrono::DateTime::parse_from_rfc3339(ts).unwrap().with_timezone(&chrono::Utc),
+            gpu_id,
+            name: "NVIDIA GeForce RTX 3080".to_string(),
+            utilization_gpu: util,
+            utilization_memory: 10,
+            memory_used: 1024,
+            memory_total: 4096,
+            temperature: 60,
+            power_usage: 200,
+            processes: vec![GpuProcess {
+                pid: 1234,
+                name: "ollama".to_string(),
+                used_gpu_memory: 512,
+                utilization: None,
+                mig_device: None,
+            }],
+            telemetry: Default::default(),
+            identity: Default::default(),
+            energy_wh: None,
+            mig_devices: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_replay_groups_frames_and_loops() {
+        let mut trace = Vec::new();
+        ReplayBackend::write_frame(&mut trace, &[
+            sample("2024-01-01T00:00:00Z", 0, 10),
+            sample("2024-01-01T00:00:00Z", 1, 20),
+        ]).unwrap();
+        ReplayBackend::write_frame(&mut trace, &[sample("2024-01-01T00:00:02Z", 0, 30)]).unwrap();
+
+        let backend = ReplayBackend::from_jsonl(std::str::from_utf8(&trace).unwrap()).unwrap();
+        assert_eq!(backend.frame_count(), 2);
+        assert_eq!(backend.device_count(), 2);
+
+        let first = backend.collect_metrics().unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[1].utilization_gpu, 20);
+        assert_eq!(first[0].processes[0].name, "ollama");
+
+        let second = backend.collect_metrics().unwrap();
+        assert_eq!(second[0].utilization_gpu, 30);
+
+        let looped = backend.collect_metrics().unwrap();
+        assert_eq!(looped[0].utilization_gpu, 10);
+    }
+
+    #[test]
+    fn test_replay_without_loop_runs_dry() {
+        let line = serde_json::to_string(&sample("2024-01-01T00:00:00Z", 0, 10)).unwrap();
+        let backend = ReplayBackend::from_jsonl(&line).unwrap().with_loop(false);
+
+        assert_eq!(backend.collect_metrics().unwrap().len(), 1);
+        assert!(backend.collect_metrics().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_replay_rejects_bad_lines() {
+        assert!(ReplayBackend::from_jsonl("not json").is_err());
+        assert!(ReplayBackend::from_jsonl("").is_err());
+    }
+}
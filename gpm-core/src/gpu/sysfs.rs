@@ -0,0 +1,87 @@
+use std::path::{Path, PathBuf};
+
+/// A `/sys/class/drm/cardN` entry and its PCI device.
+pub struct DrmCard {
+    /// `<drm_root>/cardN`
+    pub card: PathBuf,
+    /// `<drm_root>/cardN/device`
+    pub device: PathBuf,
+    pub pci_bus_id: Option<String>,
+}
+
+/// The cards under `drm_root` whose PCI vendor is `vendor_id` (`0x1002`,
+/// `0x8086`, ...), in `cardN` order.
+pub fn find_cards(drm_root: &Path, vendor_id: &str) -> std::io::Result<Vec<DrmCard>> {
+    let mut cards: Vec<(u32, DrmCard)> = std::fs::read_dir(drm_root)?
+        .flatten()
+        .filter_map(|entry| {
+            // Connectors (`card0-DP-1`) sit next to the cards
+            let number = entry.file_name().to_str()?.strip_prefix("card")?.parse().ok()?;
+            let device = entry.path().join("device");
+            if read_string(&device.join("vendor"))? != vendor_id {
+                return None;
+            }
+
+            let pci_bus_id = read_string(&device.join("uevent"))
+                .and_then(|u| u.lines().find_map(|l| l.strip_prefix("PCI_SLOT_NAME=")).map(str::to_string));
+
+            Some((
+                number,
+                DrmCard {
+                    card: entry.path(),
+                    device,
+                    pci_bus_id,
+                },
+            ))
+        })
+        .collect();
+
+    cards.sort_by_key(|(number, _)| *number);
+    Ok(cards.into_iter().map(|(_, card)| card).collect())
+}
+
+pub fn read_string(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+pub fn read_u64(path: &Path) -> Option<u64> {
+    read_string(path)?.parse().ok()
+}
+
+/// The device's first `hwmon/hwmonN` directory.
+pub fn hwmon_dir(device: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(device.join("hwmon"))
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .find(|p| p.file_name().is_some_and(|n| n.to_string_lossy().starts_with("hwmon")))
+}
+
+/// PCIe generation from `current_link_speed`, e.g. `16.0 GT/s PCIe`.
+pub fn pcie_gen(speed: &str) -> Option<u32> {
+    let rate: f64 = speed.split_whitespace().next()?.parse().ok()?;
+    [2.5, 5.0, 8.0, 16.0, 32.0, 64.0]
+        .iter()
+        .position(|r| (rate - r).abs() < 0.1)
+        .map(|i| i as u32 + 1)
+}
+
+/// Fixture builders for the sysfs-backed backends' tests.
+#[cfg(test)]
+pub mod testing {
+    use std::path::{Path, PathBuf};
+
+    pub fn write(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    /// A bare `<drm_root>/<card>` entry with its vendor and PCI slot; returns
+    /// the `device` directory for the caller to fill in.
+    pub fn fake_card(drm_root: &Path, card: &str, vendor: &str, slot: &str) -> PathBuf {
+        let device = drm_root.join(card).join("device");
+        write(&device.join("vendor"), &format!("{}\n", vendor));
+        write(&device.join("uevent"), &format!("PCI_SLOT_NAME={}\n", slot));
+        device
+    }
+}
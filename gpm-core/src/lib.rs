@@ -1,3 +1,5 @@
+pub mod accounting;
+pub mod alerts;
 pub mod api;
 pub mod classifier;
 pub mod config;
@@ -8,6 +10,7 @@ pub mod proxy;
 pub mod service;
 pub mod storage;
 pub mod telemetry;
+pub mod tracker;
 
 pub use config::GpmConfig;
 pub use error::{GpmError, Result};
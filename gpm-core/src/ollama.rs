@@ -4,7 +4,10 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+/// Proxied calls kept while storage is not draining them
+const MAX_PENDING_REQUESTS: usize = 10_000;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LlmSession {
@@ -18,6 +21,93 @@ pub struct LlmSession {
     pub tokens_per_second: f64,
     pub time_to_first_token_ms: Option<u64>,
     pub time_per_output_token_ms: Option<f64>,
+    /// GPU cost of the session, attached once it has ended
+    #[serde(default)]
+    pub hardware: Option<SessionHardware>,
+    #[serde(default)]
+    pub api_flavor: ApiFlavor,
+    /// Ended by the idle reaper rather than by the response's final
+    /// chunk; counts and timings cover what was seen until then
+    #[serde(default)]
+    pub abandoned: bool,
+}
+
+/// Which of Ollama's APIs a session was served through.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ApiFlavor {
+    /// `/api/generate` and `/api/chat`
+    #[default]
+    #[serde(rename = "ollama")]
+    Ollama,
+    /// The OpenAI-compatible `/v1/completions` and `/v1/chat/completions`
+    #[serde(rename = "openai")]
+    OpenAi,
+}
+
+impl ApiFlavor {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ollama => "ollama",
+            Self::OpenAi => "openai",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "ollama" => Some(Self::Ollama),
+            "openai" => Some(Self::OpenAi),
+            _ => None,
+        }
+    }
+}
+
+/// One call through the Ollama proxy, whatever its endpoint.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProxyRequest {
+    pub id: String,
+    /// When the proxy received the request
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub method: String,
+    /// Request path, e.g. `/api/embed`
+    pub endpoint: String,
+    pub model: Option<String>,
+    pub status: u16,
+    /// Until the last byte of the response; the duration of a pull
+    pub latency_ms: u64,
+    pub request_bytes: u64,
+    pub response_bytes: u64,
+    pub client_addr: Option<String>,
+    /// The `LlmSession` of a generation call
+    pub session_id: Option<String>,
+    /// Embeddings returned
+    pub input_count: Option<u64>,
+    /// Tokens embedded, where the response reports them
+    pub prompt_tokens: Option<u64>,
+    /// Last status of a pull, e.g. `success` or the error
+    pub pull_status: Option<String>,
+    /// Bytes downloaded and to download, over all layers of a pull
+    pub pull_completed_bytes: Option<u64>,
+    pub pull_total_bytes: Option<u64>,
+}
+
+impl ProxyRequest {
+    /// Record how the call ended, timing it from `timestamp`.
+    pub fn finish(&mut self, status: u16, response_bytes: u64) {
+        self.status = status;
+        self.response_bytes = response_bytes;
+        self.latency_ms = (chrono::Utc::now() - self.timestamp).num_milliseconds().max(0) as u64;
+    }
+}
+
+/// What a session cost the GPU(s) hosting Ollama while it ran.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionHardware {
+    pub energy_joules: f64,
+    /// `None` when the session produced no tokens
+    pub joules_per_token: Option<f64>,
+    pub avg_power_watts: f64,
+    pub peak_gpu_memory_mb: u64,
+    pub avg_gpu_utilization: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,23 +126,150 @@ pub struct OllamaApiResponse {
     pub prompt_eval_duration: Option<u64>,
 }
 
+/// A chat or text completion from the OpenAI-compatible API: either the
+/// whole response or one chunk of a stream.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OpenAiResponse {
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub choices: Vec<OpenAiChoice>,
+    /// Streams only include it in the last chunk, and only when asked to
+    /// with `stream_options.include_usage`
+    #[serde(default)]
+    pub usage: Option<OpenAiUsage>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OpenAiChoice {
+    /// Streamed chat completions
+    #[serde(default)]
+    pub delta: Option<OpenAiMessage>,
+    /// Chat completions
+    #[serde(default)]
+    pub message: Option<OpenAiMessage>,
+    /// Text completions
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OpenAiMessage {
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct OpenAiUsage {
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+}
+
+impl OpenAiResponse {
+    /// Whether any choice carries generated text.
+    fn has_content(&self) -> bool {
+        self.choices.iter().any(|c| {
+            [&c.delta, &c.message]
+                .into_iter()
+                .flatten()
+                .filter_map(|m| m.content.as_deref())
+                .chain(c.text.as_deref())
+                .any(|t| !t.is_empty())
+        })
+    }
+}
+
+/// What the proxy sees of an OpenAI-compatible completion.
+#[derive(Debug, Clone)]
+pub enum OpenAiEvent {
+    /// One `data:` chunk of a stream
+    Chunk(OpenAiResponse),
+    /// The `data: [DONE]` that closes a stream
+    Done,
+    /// A response that was not streamed
+    Response(OpenAiResponse),
+}
+
 #[derive(Debug, Clone)]
 struct SessionTracker {
     session_id: String,
     model: String,
+    api_flavor: ApiFlavor,
     start_time: chrono::DateTime<chrono::Utc>,
+    /// When the proxy last passed on a chunk of the response
+    last_activity: chrono::DateTime<chrono::Utc>,
     first_token_time: Option<chrono::DateTime<chrono::Utc>>,
+    last_token_time: Option<chrono::DateTime<chrono::Utc>>,
+    /// Streamed chunks with text, which count the tokens when the response
+    /// reports none
+    content_chunks: u64,
     prompt_tokens: u64,
     completion_tokens: u64,
     prompt_eval_duration_ns: u64,
     eval_duration_ns: u64,
 }
 
+impl SessionTracker {
+    fn new(
+        session_id: String,
+        model: String,
+        api_flavor: ApiFlavor,
+        start_time: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        Self {
+            session_id,
+            model,
+            api_flavor,
+            start_time,
+            last_activity: start_time,
+            first_token_time: None,
+            last_token_time: None,
+            content_chunks: 0,
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            prompt_eval_duration_ns: 0,
+            eval_duration_ns: 0,
+        }
+    }
+
+    fn saw_text(&mut self, now: chrono::DateTime<chrono::Utc>) {
+        self.first_token_time.get_or_insert(now);
+        self.last_token_time = Some(now);
+        self.content_chunks += 1;
+    }
+
+    /// Fill in the counts and timings the response did not report (OpenAI
+    /// responses, and streams cut off before Ollama's final object) from
+    /// what passed the proxy until `end`.
+    fn settle(&mut self, end: chrono::DateTime<chrono::Utc>) {
+        // Ollama streams about one token per chunk
+        if self.completion_tokens == 0 {
+            self.completion_tokens = self.content_chunks;
+        }
+
+        if self.eval_duration_ns == 0 {
+            let generation = match (self.first_token_time, self.last_token_time) {
+                (Some(first), Some(last)) if last > first => last - first,
+                _ => end - self.start_time,
+            };
+            self.eval_duration_ns = generation.num_nanoseconds().unwrap_or(0).max(0) as u64;
+        }
+    }
+}
+
 pub struct OllamaMonitor {
     client: Client,
     api_url: String,
     active_sessions: Arc<RwLock<HashMap<String, SessionTracker>>>,
     completed_sessions: Arc<RwLock<Vec<LlmSession>>>,
+    /// Proxied calls not yet stored
+    proxy_requests: RwLock<Vec<ProxyRequest>>,
+    /// GPUs (by `GpuMetrics::stable_id`) an Ollama process was last seen on
+    host_gpus: RwLock<Vec<String>>,
 }
 
 impl OllamaMonitor {
@@ -65,9 +282,30 @@ impl OllamaMonitor {
             api_url,
             active_sessions: Arc::new(RwLock::new(HashMap::new())),
             completed_sessions: Arc::new(RwLock::new(Vec::new())),
+            proxy_requests: RwLock::new(Vec::new()),
+            host_gpus: RwLock::new(Vec::new()),
         }
     }
 
+    /// Record which GPUs are running Ollama, from the latest GPU samples.
+    /// An empty sample keeps the previous hosts, since the runner exits
+    /// between requests after its keep-alive.
+    pub async fn observe_gpus(&self, gpu_metrics: &[crate::gpu::GpuMetrics]) {
+        let hosts: Vec<String> = gpu_metrics
+            .iter()
+            .filter(|m| m.processes.iter().any(|p| p.name.to_lowercase().contains("ollama")))
+            .map(|m| m.stable_id())
+            .collect();
+
+        if !hosts.is_empty() {
+            *self.host_gpus.write().await = hosts;
+        }
+    }
+
+    pub async fn host_gpus(&self) -> Vec<String> {
+        self.host_gpus.read().await.clone()
+    }
+
     pub async fn is_ollama_running(&self) -> bool {
         match self.client
             .get(format!("{}/api/tags", self.api_url))
@@ -128,21 +366,13 @@ impl OllamaMonitor {
         let now = chrono::Utc::now();
         let mut sessions = self.active_sessions.write().await;
 
-        let tracker = sessions.entry(session_id.clone()).or_insert_with(|| {
-            SessionTracker {
-                session_id: session_id.clone(),
-                model: model.clone(),
-                start_time: now,
-                first_token_time: None,
-                prompt_tokens: 0,
-                completion_tokens: 0,
-                prompt_eval_duration_ns: 0,
-                eval_duration_ns: 0,
-            }
-        });
+        let tracker = sessions
+            .entry(session_id.clone())
+            .or_insert_with(|| SessionTracker::new(session_id.clone(), model, ApiFlavor::Ollama, now));
+        tracker.last_activity = now;
 
-        if tracker.first_token_time.is_none() && response.response.is_some() {
-            tracker.first_token_time = Some(now);
+        if response.response.as_deref().is_some_and(|r| !r.is_empty()) {
+            tracker.saw_text(now);
         }
 
         if let Some(count) = response.prompt_eval_count {
@@ -162,24 +392,115 @@ impl OllamaMonitor {
         }
 
         if response.done {
-            let tracker_clone = tracker.clone();
-            let session = self.finalize_session(&tracker_clone);
+            if let Some(tracker) = sessions.remove(&session_id) {
+                drop(sessions);
+                self.complete_session(tracker, now, false).await;
+            }
+        }
+    }
+
+    /// Track an OpenAI-compatible completion that was requested at
+    /// `started`. OpenAI responses carry no timings, so the generation time
+    /// is taken from when the first and last text chunks passed the proxy,
+    /// or the whole request for a response that was not streamed.
+    pub async fn track_openai(
+        &self,
+        session_id: String,
+        model: String,
+        started: chrono::DateTime<chrono::Utc>,
+        event: &OpenAiEvent,
+    ) {
+        let now = chrono::Utc::now();
+        let mut sessions = self.active_sessions.write().await;
+
+        let tracker = sessions
+            .entry(session_id.clone())
+            .or_insert_with(|| SessionTracker::new(session_id.clone(), model, ApiFlavor::OpenAi, started));
+        tracker.last_activity = now;
+
+        let response = match event {
+            OpenAiEvent::Chunk(chunk) => {
+                if chunk.has_content() {
+                    tracker.saw_text(now);
+                }
+                Some(chunk)
+            }
+            OpenAiEvent::Done => None,
+            OpenAiEvent::Response(response) => Some(response),
+        };
+
+        if let Some(usage) = response.and_then(|r| r.usage) {
+            tracker.prompt_tokens = usage.prompt_tokens;
+            tracker.completion_tokens = usage.completion_tokens;
+        }
+
+        if matches!(event, OpenAiEvent::Chunk(_)) {
+            return;
+        }
+
+        if let Some(mut tracker) = sessions.remove(&session_id) {
             drop(sessions);
+            tracker.settle(now);
+            self.complete_session(tracker, now, false).await;
+        }
+    }
 
-            let mut completed = self.completed_sessions.write().await;
-            completed.push(session);
+    /// End sessions whose response has passed no chunk for `timeout`, such
+    /// as a stream cut off before its final object or abandoned by the
+    /// client. They are recorded as abandoned at their last activity.
+    /// Returns how many were reaped.
+    pub async fn reap_idle_sessions(&self, timeout: chrono::Duration) -> usize {
+        let cutoff = chrono::Utc::now() - timeout;
+        let mut idle = Vec::new();
+
+        self.active_sessions.write().await.retain(|_, tracker| {
+            let active = tracker.last_activity > cutoff;
+            if !active {
+                idle.push(tracker.clone());
+            }
+            active
+        });
+
+        let count = idle.len();
+        for mut tracker in idle {
+            let end_time = tracker.last_activity;
+            tracker.settle(end_time);
+            self.complete_session(tracker, end_time, true).await;
+        }
+
+        count
+    }
 
+    async fn complete_session(&self, tracker: SessionTracker, end_time: chrono::DateTime<chrono::Utc>, abandoned: bool) {
+        let session = self.finalize_session(&tracker, end_time, abandoned);
+
+        if abandoned {
+            warn!(
+                "Abandoned LLM session idle since {}: model={} api={} tokens={}",
+                end_time,
+                session.model,
+                session.api_flavor.as_str(),
+                session.total_tokens
+            );
+        } else {
             info!(
-                "Completed LLM session: model={} tokens={} tps={:.2}",
-                model,
-                tracker_clone.prompt_tokens + tracker_clone.completion_tokens,
-                tracker_clone.completion_tokens as f64 * 1e9 / tracker_clone.eval_duration_ns as f64
+                "Completed LLM session: model={} api={} tokens={} tps={:.2}",
+                session.model,
+                session.api_flavor.as_str(),
+                session.total_tokens,
+                session.tokens_per_second
             );
         }
+
+        self.completed_sessions.write().await.push(session);
     }
 
-    fn finalize_session(&self, tracker: &SessionTracker) -> LlmSession {
-        let end_time = chrono::Utc::now();
+    fn finalize_session(
+        &self,
+        tracker: &SessionTracker,
+        end_time: chrono::DateTime<chrono::Utc>,
+        abandoned: bool,
+    ) -> LlmSession {
         let total_tokens = tracker.prompt_tokens + tracker.completion_tokens;
 
         let tokens_per_second = if tracker.eval_duration_ns > 0 {
@@ -209,6 +530,9 @@ impl OllamaMonitor {
             tokens_per_second,
             time_to_first_token_ms,
             time_per_output_token_ms,
+            hardware: None,
+            api_flavor: tracker.api_flavor,
+            abandoned,
         }
     }
 
@@ -216,8 +540,30 @@ impl OllamaMonitor {
         self.completed_sessions.read().await.clone()
     }
 
-    pub async fn clear_completed_sessions(&self) {
-        self.completed_sessions.write().await.clear();
+    /// Sessions completed since the last call. Taken in one step, so a
+    /// session finishing while the previous batch is stored is kept.
+    pub async fn take_completed_sessions(&self) -> Vec<LlmSession> {
+        std::mem::take(&mut *self.completed_sessions.write().await)
+    }
+
+    pub async fn record_request(&self, request: ProxyRequest) {
+        debug!(
+            "Proxied {} {} -> {} in {} ms",
+            request.method, request.endpoint, request.status, request.latency_ms
+        );
+        let mut requests = self.proxy_requests.write().await;
+        if requests.len() >= MAX_PENDING_REQUESTS {
+            // Storage has fallen behind; keep the newest calls
+            let dropped = MAX_PENDING_REQUESTS / 10;
+            warn!("{} proxied calls not stored yet, dropping the oldest {}", requests.len(), dropped);
+            requests.drain(..dropped);
+        }
+        requests.push(request);
+    }
+
+    /// Proxied calls recorded since the last call.
+    pub async fn take_requests(&self) -> Vec<ProxyRequest> {
+        std::mem::take(&mut *self.proxy_requests.write().await)
     }
 
     pub async fn check_and_track_logs(&self) -> Result<()> {
@@ -289,5 +635,101 @@ mod tests {
         assert_eq!(completed.len(), 1);
         assert_eq!(completed[0].model, "llama2");
         assert_eq!(completed[0].completion_tokens, 3);
+        assert!(!completed[0].abandoned);
+        assert!(monitor.active_sessions.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_idle_sessions_reaped() {
+        let monitor = OllamaMonitor::new("http://localhost:11434".to_string());
+        let chunk = |response: &str| OllamaApiResponse {
+            model: "llama2".to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            response: Some(response.to_string()),
+            done: false,
+            eval_count: None,
+            eval_duration: None,
+            prompt_eval_count: None,
+            prompt_eval_duration: None,
+        };
+
+        // The final object never arrives
+        for response in ["Once", " upon", " a"] {
+            monitor.track_generation("cut".to_string(), "llama2".to_string(), &chunk(response)).await;
+        }
+
+        assert_eq!(monitor.reap_idle_sessions(chrono::Duration::minutes(5)).await, 0);
+        assert_eq!(monitor.reap_idle_sessions(chrono::Duration::zero()).await, 1);
+        assert!(monitor.active_sessions.read().await.is_empty());
+
+        let completed = monitor.take_completed_sessions().await;
+        assert_eq!(completed.len(), 1);
+        assert!(completed[0].abandoned);
+        assert_eq!(completed[0].completion_tokens, 3);
+        assert!(completed[0].end_time.unwrap() <= chrono::Utc::now());
+        assert!(monitor.take_completed_sessions().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_openai_session_tracking() {
+        let monitor = OllamaMonitor::new("http://localhost:11434".to_string());
+        let started = chrono::Utc::now() - chrono::Duration::milliseconds(500);
+        let chunk = |content: &str| OpenAiResponse {
+            choices: vec![OpenAiChoice {
+                delta: Some(OpenAiMessage { content: Some(content.to_string()) }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        // A stream without `usage` counts its text chunks
+        for event in [
+            OpenAiEvent::Chunk(chunk("Hello")),
+            OpenAiEvent::Chunk(chunk(" world")),
+            OpenAiEvent::Chunk(chunk("")),
+            OpenAiEvent::Done,
+        ] {
+            monitor.track_openai("stream".to_string(), "llama3".to_string(), started, &event).await;
+        }
+
+        let response: OpenAiResponse = serde_json::from_str(
+            r#"{"id":"chatcmpl-7","object":"chat.completion","model":"llama3",
+                "choices":[{"index":0,"message":{"role":"assistant","content":"Hi there"},"finish_reason":"stop"}],
+                "usage":{"prompt_tokens":26,"completion_tokens":12,"total_tokens":38}}"#,
+        )
+        .unwrap();
+        monitor
+            .track_openai("whole".to_string(), "llama3".to_string(), started, &OpenAiEvent::Response(response))
+            .await;
+
+        let completed = monitor.get_completed_sessions().await;
+        assert_eq!(completed.len(), 2);
+        assert!(completed.iter().all(|s| s.api_flavor == ApiFlavor::OpenAi));
+
+        assert_eq!(completed[0].completion_tokens, 2);
+        assert!(completed[0].time_to_first_token_ms.unwrap() >= 500);
+
+        assert_eq!(completed[1].prompt_tokens, 26);
+        assert_eq!(completed[1].completion_tokens, 12);
+        assert_eq!(completed[1].time_to_first_token_ms, None);
+        assert!(completed[1].tokens_per_second > 0.0);
+        assert!(monitor.active_sessions.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pending_requests_capped() {
+        let monitor = OllamaMonitor::new("http://localhost:11434".to_string());
+        for i in 0..=MAX_PENDING_REQUESTS {
+            let request = ProxyRequest {
+                id: i.to_string(),
+                ..Default::default()
+            };
+            monitor.record_request(request).await;
+        }
+
+        let pending = monitor.take_requests().await;
+        assert!(pending.len() <= MAX_PENDING_REQUESTS);
+        assert_eq!(pending.last().unwrap().id, MAX_PENDING_REQUESTS.to_string());
+        assert!(monitor.take_requests().await.is_empty());
     }
 }
@@ -1,15 +1,18 @@
-use crate::ollama::{OllamaApiResponse, OllamaMonitor};
+use crate::ollama::{
+    ApiFlavor, OllamaApiResponse, OllamaMonitor, OpenAiEvent, OpenAiResponse, OpenAiUsage, ProxyRequest,
+};
 use axum::{
     body::Body,
-    extract::State,
+    extract::{ConnectInfo, State},
     http::{Request, Response, StatusCode},
     routing::any,
     Router,
 };
 use bytes::Bytes;
 use futures_util::StreamExt;
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, mpsc};
 use tracing::{debug, error, info, warn};
 
 #[derive(Clone)]
@@ -58,7 +61,7 @@ impl OllamaProxy {
         info!("Ollama proxy listening on http://0.0.0.0:{}", self.listen_port);
         info!("Forwarding to backend: {}", self.backend_url);
 
-        axum::serve(listener, app)
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
             .with_graceful_shutdown(async move {
                 let _ = shutdown_rx.recv().await;
                 info!("Ollama proxy shutting down");
@@ -72,8 +75,10 @@ impl OllamaProxy {
 
 async fn proxy_handler(
     State(state): State<ProxyState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
     req: Request<Body>,
 ) -> Response<Body> {
+    let started = chrono::Utc::now();
     let method = req.method().clone();
     let uri = req.uri().clone();
     let path = uri.path();
@@ -84,6 +89,8 @@ async fn proxy_handler(
     debug!("Proxying {} {} -> {}", method, path, backend_url);
 
     let is_streaming_endpoint = path == "/api/generate" || path == "/api/chat";
+    let is_openai_endpoint = path == "/v1/chat/completions" || path == "/v1/completions";
+    let is_embedding_endpoint = matches!(path, "/api/embed" | "/api/embeddings" | "/v1/embeddings");
 
     let headers = req.headers().clone();
     let body_bytes = match axum::body::to_bytes(req.into_body(), 10 * 1024 * 1024).await {
@@ -97,6 +104,17 @@ async fn proxy_handler(
         }
     };
 
+    let mut request = ProxyRequest {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp: started,
+        method: method.to_string(),
+        endpoint: path.to_string(),
+        model: request_model(&body_bytes),
+        request_bytes: body_bytes.len() as u64,
+        client_addr: Some(client.ip().to_string()),
+        ..Default::default()
+    };
+
     let mut request_builder = state.client.request(method.clone(), &backend_url);
 
     for (name, value) in headers.iter() {
@@ -115,6 +133,8 @@ async fn proxy_handler(
         Ok(resp) => resp,
         Err(e) => {
             error!("Failed to forward request to Ollama: {}", e);
+            request.finish(StatusCode::BAD_GATEWAY.as_u16(), 0);
+            state.ollama_monitor.record_request(request).await;
             return Response::builder()
                 .status(StatusCode::BAD_GATEWAY)
                 .body(Body::from(format!("Failed to connect to Ollama backend: {}", e)))
@@ -125,26 +145,54 @@ async fn proxy_handler(
     let status = response.status();
     let resp_headers = response.headers().clone();
 
-    if is_streaming_endpoint && status.is_success() {
+    let content_type = resp_headers
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+
+    let stream_kind = if !status.is_success() {
+        None
+    } else if is_streaming_endpoint {
+        Some(StreamKind::Generation(ApiFlavor::Ollama))
+    } else if is_openai_endpoint && content_type.starts_with("text/event-stream") {
+        Some(StreamKind::Generation(ApiFlavor::OpenAi))
+    } else if content_type.starts_with("application/x-ndjson") {
+        // Progress of pulls, pushes and creates, which can run for minutes
+        Some(if path == "/api/pull" { StreamKind::Pull } else { StreamKind::Passthrough })
+    } else {
+        None
+    };
+
+    if let Some(kind) = stream_kind {
         let session_id = uuid::Uuid::new_v4().to_string();
-        let model = extract_model_from_request(&body_bytes);
+        if matches!(kind, StreamKind::Generation(_)) {
+            request.session_id = Some(session_id.clone());
+        }
+        request.status = status.as_u16();
+
+        let session = StreamSession {
+            monitor: Arc::clone(&state.ollama_monitor),
+            kind,
+            session_id,
+            model: extract_model_from_request(&body_bytes),
+            started,
+            request,
+            pull: PullProgress::default(),
+        };
 
-        debug!("Starting LLM session tracking: {} (model: {})", session_id, model);
+        debug!(
+            "Tracking {} stream: {} (model: {})",
+            session.request.endpoint, session.session_id, session.model
+        );
 
-        let ollama_monitor = Arc::clone(&state.ollama_monitor);
-        let stream = response.bytes_stream();
+        // Chunks are decoded in order by one task, off the response path
+        let (chunks_tx, chunks_rx) = mpsc::unbounded_channel();
+        tokio::spawn(session.run(chunks_rx));
 
-        let tracked_stream = stream.map(move |chunk_result| {
+        let tracked_stream = response.bytes_stream().map(move |chunk_result| {
             match &chunk_result {
                 Ok(bytes) => {
-                    if let Some(api_response) = parse_streaming_chunk(bytes) {
-                        let monitor = ollama_monitor.clone();
-                        let sid = session_id.clone();
-                        let mdl = model.clone();
-                        tokio::spawn(async move {
-                            monitor.track_generation(sid, mdl, &api_response).await;
-                        });
-                    }
+                    let _ = chunks_tx.send(bytes.clone());
                 }
                 Err(e) => {
                     warn!("Stream chunk error: {}", e);
@@ -166,10 +214,14 @@ async fn proxy_handler(
         return response_builder.body(body).unwrap();
     }
 
+    let openai_model = (is_openai_endpoint && status.is_success()).then(|| extract_model_from_request(&body_bytes));
+
     let body_bytes = match response.bytes().await {
         Ok(bytes) => bytes,
         Err(e) => {
             error!("Failed to read response body: {}", e);
+            request.finish(StatusCode::BAD_GATEWAY.as_u16(), 0);
+            state.ollama_monitor.record_request(request).await;
             return Response::builder()
                 .status(StatusCode::BAD_GATEWAY)
                 .body(Body::from(format!("Failed to read response: {}", e)))
@@ -177,6 +229,38 @@ async fn proxy_handler(
         }
     };
 
+    if let Some(model) = openai_model {
+        if let Ok(completion) = serde_json::from_slice::<OpenAiResponse>(&body_bytes) {
+            let session_id = uuid::Uuid::new_v4().to_string();
+            request.session_id = Some(session_id.clone());
+            let ollama_monitor = Arc::clone(&state.ollama_monitor);
+            tokio::spawn(async move {
+                ollama_monitor
+                    .track_openai(session_id, model, started, &OpenAiEvent::Response(completion))
+                    .await;
+            });
+        }
+    }
+
+    if status.is_success() {
+        if is_embedding_endpoint {
+            if let Some((inputs, tokens)) = embedding_counts(&body_bytes) {
+                request.input_count = Some(inputs);
+                request.prompt_tokens = tokens;
+            }
+        } else if path == "/api/pull" {
+            // Not streamed: a single `{"status":"success"}`
+            let mut pull = PullProgress::default();
+            if let Ok(line) = std::str::from_utf8(&body_bytes) {
+                pull.observe(line);
+            }
+            pull.apply(&mut request);
+        }
+    }
+
+    request.finish(status.as_u16(), body_bytes.len() as u64);
+    state.ollama_monitor.record_request(request).await;
+
     let mut response_builder = Response::builder().status(status);
     for (name, value) in resp_headers.iter() {
         if name != "transfer-encoding" {
@@ -187,37 +271,221 @@ async fn proxy_handler(
     response_builder.body(Body::from(body_bytes.to_vec())).unwrap()
 }
 
-fn extract_model_from_request(body: &Bytes) -> String {
-    if body.is_empty() {
-        return "unknown".to_string();
-    }
-
+/// The `model` of a request body, or `name` as older clients send it for
+/// model management calls.
+fn request_model(body: &Bytes) -> Option<String> {
     #[derive(serde::Deserialize)]
     struct RequestBody {
         model: Option<String>,
+        name: Option<String>,
     }
 
     serde_json::from_slice::<RequestBody>(body)
         .ok()
-        .and_then(|r| r.model)
-        .unwrap_or_else(|| "unknown".to_string())
+        .and_then(|r| r.model.or(r.name))
+}
+
+fn extract_model_from_request(body: &Bytes) -> String {
+    request_model(body).unwrap_or_else(|| "unknown".to_string())
+}
+
+/// The longest line kept while waiting for its end; longer lines are
+/// dropped rather than buffered without bound.
+const MAX_LINE_BYTES: usize = 1024 * 1024;
+
+/// Splits a byte stream into lines, holding a partial line until the chunk
+/// that completes it arrives. Ollama's NDJSON and OpenAI-style SSE are both
+/// line based, but neither lines up with network reads.
+#[derive(Default)]
+struct LineDecoder {
+    buffer: Vec<u8>,
+}
+
+impl LineDecoder {
+    /// The non-empty lines that `chunk` completes, without line endings.
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+
+        let Some(end) = self.buffer.iter().rposition(|&b| b == b'\n') else {
+            if self.buffer.len() > MAX_LINE_BYTES {
+                warn!("Dropping {} bytes of an unterminated stream line", self.buffer.len());
+                self.buffer.clear();
+            }
+            return Vec::new();
+        };
+
+        let partial = self.buffer.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.buffer, partial);
+
+        String::from_utf8_lossy(&complete)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// The last line, if the stream ended without terminating it.
+    fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.buffer);
+        let line = String::from_utf8_lossy(&rest).trim().to_string();
+        (!line.is_empty()).then_some(line)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum StreamKind {
+    /// Becomes an `LlmSession`
+    Generation(ApiFlavor),
+    /// Download progress of `/api/pull`
+    Pull,
+    /// Only counted in the request log
+    Passthrough,
+}
+
+/// One streamed response, tracked as it passes the proxy and logged once
+/// the body is done.
+struct StreamSession {
+    monitor: Arc<OllamaMonitor>,
+    kind: StreamKind,
+    session_id: String,
+    model: String,
+    started: chrono::DateTime<chrono::Utc>,
+    request: ProxyRequest,
+    pull: PullProgress,
+}
+
+impl StreamSession {
+    /// Track the lines of `chunks` until the response body is dropped. A
+    /// generation that ends without its final object is left to the idle
+    /// session reaper.
+    async fn run(mut self, mut chunks: mpsc::UnboundedReceiver<Bytes>) {
+        let mut decoder = LineDecoder::default();
+        let mut response_bytes = 0;
+
+        while let Some(chunk) = chunks.recv().await {
+            response_bytes += chunk.len() as u64;
+            for line in decoder.push(&chunk) {
+                self.track_line(&line).await;
+            }
+        }
+
+        if let Some(line) = decoder.finish() {
+            self.track_line(&line).await;
+        }
+
+        let mut request = self.request;
+        if let StreamKind::Pull = self.kind {
+            self.pull.apply(&mut request);
+        }
+        let status = request.status;
+        request.finish(status, response_bytes);
+        self.monitor.record_request(request).await;
+    }
+
+    async fn track_line(&mut self, line: &str) {
+        match self.kind {
+            StreamKind::Generation(ApiFlavor::Ollama) => {
+                if let Some(response) = parse_ndjson_line(line) {
+                    self.monitor
+                        .track_generation(self.session_id.clone(), self.model.clone(), &response)
+                        .await;
+                }
+            }
+            StreamKind::Generation(ApiFlavor::OpenAi) => {
+                if let Some(event) = parse_sse_line(line) {
+                    self.monitor
+                        .track_openai(self.session_id.clone(), self.model.clone(), self.started, &event)
+                        .await;
+                }
+            }
+            StreamKind::Pull => self.pull.observe(line),
+            StreamKind::Passthrough => {}
+        }
+    }
 }
 
-fn parse_streaming_chunk(bytes: &Bytes) -> Option<OllamaApiResponse> {
-    let text = std::str::from_utf8(bytes).ok()?;
+/// Progress of a pull, from its status lines: `{"status":"pulling 6a07…",
+/// "digest":"sha256:…","total":4109853248,"completed":241970}`, then
+/// `{"status":"success"}` or `{"error":"…"}`.
+#[derive(Debug, Default)]
+struct PullProgress {
+    /// `(completed, total)` bytes by layer digest
+    layers: std::collections::HashMap<String, (u64, u64)>,
+    status: Option<String>,
+}
 
-    for line in text.lines() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
+impl PullProgress {
+    fn observe(&mut self, line: &str) {
+        #[derive(serde::Deserialize)]
+        struct Status {
+            status: Option<String>,
+            error: Option<String>,
+            digest: Option<String>,
+            total: Option<u64>,
+            completed: Option<u64>,
         }
 
-        if let Ok(response) = serde_json::from_str::<OllamaApiResponse>(trimmed) {
-            return Some(response);
+        let Ok(update) = serde_json::from_str::<Status>(line) else {
+            return;
+        };
+
+        if let (Some(digest), Some(total)) = (update.digest, update.total) {
+            let layer = self.layers.entry(digest).or_default();
+            layer.0 = layer.0.max(update.completed.unwrap_or(0));
+            layer.1 = total;
+        }
+
+        if let Some(status) = update.error.or(update.status) {
+            self.status = Some(status);
         }
     }
 
-    None
+    fn apply(&self, request: &mut ProxyRequest) {
+        request.pull_status.clone_from(&self.status);
+        if !self.layers.is_empty() {
+            request.pull_completed_bytes = Some(self.layers.values().map(|l| l.0).sum());
+            request.pull_total_bytes = Some(self.layers.values().map(|l| l.1).sum());
+        }
+    }
+}
+
+/// Embeddings returned and tokens embedded, from Ollama's `/api/embed` and
+/// `/api/embeddings` or the OpenAI-compatible `/v1/embeddings`.
+fn embedding_counts(body: &[u8]) -> Option<(u64, Option<u64>)> {
+    use serde::de::IgnoredAny;
+
+    #[derive(serde::Deserialize)]
+    struct EmbeddingResponse {
+        embeddings: Option<Vec<IgnoredAny>>,
+        embedding: Option<IgnoredAny>,
+        data: Option<Vec<IgnoredAny>>,
+        prompt_eval_count: Option<u64>,
+        usage: Option<OpenAiUsage>,
+    }
+
+    let response = serde_json::from_slice::<EmbeddingResponse>(body).ok()?;
+    let inputs = response
+        .embeddings
+        .or(response.data)
+        .map(|e| e.len() as u64)
+        .or(response.embedding.map(|_| 1))?;
+
+    Some((inputs, response.prompt_eval_count.or(response.usage.map(|u| u.prompt_tokens))))
+}
+
+fn parse_ndjson_line(line: &str) -> Option<OllamaApiResponse> {
+    serde_json::from_str(line).ok()
+}
+
+/// An OpenAI-style server-sent event: a `data: {...}` chunk or the closing
+/// `data: [DONE]`. Other SSE fields are ignored.
+fn parse_sse_line(line: &str) -> Option<OpenAiEvent> {
+    let data = line.strip_prefix("data:")?.trim();
+    if data == "[DONE]" {
+        return Some(OpenAiEvent::Done);
+    }
+    serde_json::from_str(data).ok().map(OpenAiEvent::Chunk)
 }
 
 #[cfg(test)]
@@ -234,10 +502,87 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_streaming_chunk() {
-        let chunk = Bytes::from(r#"{"model":"llama2","created_at":"2024-01-01T00:00:00Z","response":"Hi","done":false}"#);
-        let parsed = parse_streaming_chunk(&chunk);
-        assert!(parsed.is_some());
-        assert_eq!(parsed.unwrap().model, "llama2");
+    fn test_line_decoder_reframes_ndjson() {
+        let mut decoder = LineDecoder::default();
+
+        // Two objects in one read, the final one split across three
+        let lines = decoder.push(concat!(
+            r#"{"model":"llama2","created_at":"2024-01-01T00:00:00Z","response":"Hi","done":false}"#, "\n",
+            r#"{"model":"llama2","created_at":"2024-01-01T00:00:00Z","response":"!","done":false}"#, "\n",
+            r#"{"model":"llama2","created_at":"2024-01-01T00:00:00Z","response":"","#,
+        ).as_bytes());
+        assert_eq!(lines.len(), 2);
+        assert_eq!(parse_ndjson_line(&lines[1]).unwrap().response.as_deref(), Some("!"));
+
+        assert!(decoder.push(br#""done":true,"eval_count":2,"#).is_empty());
+        let lines = decoder.push(b"\"eval_duration\":40000000}\n");
+        assert_eq!(lines.len(), 1);
+        let done = parse_ndjson_line(&lines[0]).unwrap();
+        assert!(done.done);
+        assert_eq!(done.eval_count, Some(2));
+        assert_eq!(decoder.finish(), None);
+
+        // A stream that ends without a newline still yields its last line
+        assert!(decoder.push(br#"{"model":"llama2","created_at":"","done":true}"#).is_empty());
+        assert!(parse_ndjson_line(&decoder.finish().unwrap()).unwrap().done);
+    }
+
+    #[test]
+    fn test_parse_sse_lines() {
+        let mut decoder = LineDecoder::default();
+        let mut lines = decoder.push(concat!(
+            "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"model\":\"llama3\",",
+            "\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"\"},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"model\":\"llama3\",\"choices\":[],",
+        ).as_bytes());
+        lines.extend(decoder.push(b"\"usage\":{\"prompt_tokens\":26,\"completion_tokens\":12,\"total_tokens\":38}}\n\ndata: [DONE]\n\n"));
+
+        let events: Vec<_> = lines.iter().filter_map(|l| parse_sse_line(l)).collect();
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], OpenAiEvent::Chunk(c) if c.choices[0].finish_reason.as_deref() == Some("stop")));
+        assert!(matches!(&events[1], OpenAiEvent::Chunk(c) if c.usage.is_some_and(|u| u.completion_tokens == 12)));
+        assert!(matches!(events[2], OpenAiEvent::Done));
+
+        // Ollama's native stream is not SSE
+        assert!(parse_sse_line(r#"{"model":"llama2","created_at":"2024-01-01T00:00:00Z","done":false}"#).is_none());
+    }
+
+    #[test]
+    fn test_pull_progress() {
+        let mut pull = PullProgress::default();
+        for line in [
+            r#"{"status":"pulling manifest"}"#,
+            r#"{"status":"pulling dde5aa3fc5ff","digest":"sha256:dde5aa3fc5ff","total":2019377376,"completed":241970}"#,
+            r#"{"status":"pulling 966de95ca8a6","digest":"sha256:966de95ca8a6","total":1429}"#,
+            r#"{"status":"pulling dde5aa3fc5ff","digest":"sha256:dde5aa3fc5ff","total":2019377376,"completed":2019377376}"#,
+            r#"{"status":"verifying sha256 digest"}"#,
+        ] {
+            pull.observe(line);
+        }
+
+        let mut request = ProxyRequest::default();
+        pull.apply(&mut request);
+        assert_eq!(request.pull_status.as_deref(), Some("verifying sha256 digest"));
+        assert_eq!(request.pull_completed_bytes, Some(2019377376));
+        assert_eq!(request.pull_total_bytes, Some(2019378805));
+
+        pull.observe(r#"{"error":"pull model manifest: file does not exist"}"#);
+        pull.apply(&mut request);
+        assert_eq!(request.pull_status.as_deref(), Some("pull model manifest: file does not exist"));
+    }
+
+    #[test]
+    fn test_embedding_counts() {
+        let embed = br#"{"model":"all-minilm","embeddings":[[0.01,-0.02],[0.03,0.04]],"prompt_eval_count":8}"#;
+        assert_eq!(embedding_counts(embed), Some((2, Some(8))));
+
+        let legacy = br#"{"embedding":[0.56,0.32,-0.41]}"#;
+        assert_eq!(embedding_counts(legacy), Some((1, None)));
+
+        let openai = br#"{"object":"list","data":[{"object":"embedding","embedding":[0.1],"index":0}],
+            "model":"all-minilm","usage":{"prompt_tokens":5,"total_tokens":5}}"#;
+        assert_eq!(embedding_counts(openai), Some((1, Some(5))));
+
+        assert_eq!(embedding_counts(br#"{"error":"model not found"}"#), None);
     }
 }
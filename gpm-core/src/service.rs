@@ -1,20 +1,34 @@
+use crate::accounting::EnergyMeter;
+use crate::alerts::AlertManager;
 use crate::classifier::ProcessClassifier;
-use crate::config::GpmConfig;
+use crate::config::{GpmConfig, OllamaConfig};
 use crate::error::Result;
-use crate::gpu::GpuMonitorBackend;
-use crate::ollama::OllamaMonitor;
+use crate::gpu::{DeviceTracker, GpuEvent, GpuEventWatcher, GpuMonitorBackend, GpuSelector};
+use crate::ollama::{LlmSession, OllamaMonitor, SessionHardware};
 use crate::proxy::OllamaProxy;
 use crate::storage::StorageManager;
 use crate::telemetry::TelemetryManager;
+use crate::tracker::{ProcessEventKind, MAX_SAMPLE_GAP_SECS};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Notify, RwLock};
 use tokio::time::{interval, Duration};
 use tracing::{debug, error, info, warn};
 
+/// State the collector carries from one poll to the next.
+#[derive(Default)]
+struct CollectorState {
+    energy_meter: EnergyMeter,
+    device_tracker: DeviceTracker,
+    /// Told when GPUs are added or removed, so the event watcher registers
+    /// the current set. A driver reload shows up as both.
+    devices_changed: Arc<Notify>,
+}
+
 pub struct GpmService {
     config: GpmConfig,
     gpu_monitor: Arc<RwLock<GpuMonitorBackend>>,
     process_classifier: Arc<RwLock<ProcessClassifier>>,
+    alert_manager: Arc<AlertManager>,
     ollama_monitor: Arc<OllamaMonitor>,
     storage: Arc<StorageManager>,
     telemetry: Arc<TelemetryManager>,
@@ -29,12 +43,24 @@ impl GpmService {
             GpuMonitorBackend::initialize(&config)?
         ));
 
-        let process_classifier = Arc::new(RwLock::new(ProcessClassifier::new()));
+        let process_classifier = Arc::new(RwLock::new(ProcessClassifier::from_config(&config.classifier)?));
 
         let ollama_monitor = Arc::new(OllamaMonitor::new(config.ollama.api_url.clone()));
 
         let storage = Arc::new(StorageManager::new(&config).await?);
 
+        let alert_manager = Arc::new(AlertManager::from_config(&config.alerts)?);
+
+        let stale_alerts = storage.database.resolve_stale_alerts().await?;
+        if stale_alerts > 0 {
+            info!("Resolved {} alert(s) left firing by a previous run", stale_alerts);
+        }
+
+        let stale_sessions = storage.database.close_stale_process_sessions().await?;
+        if stale_sessions > 0 {
+            info!("Closed {} process session(s) left open by a previous run", stale_sessions);
+        }
+
         let telemetry = Arc::new(TelemetryManager::new(&config)?);
 
         if telemetry.prometheus.is_some() {
@@ -49,6 +75,7 @@ impl GpmService {
             config,
             gpu_monitor,
             process_classifier,
+            alert_manager,
             ollama_monitor,
             storage,
             telemetry,
@@ -75,26 +102,43 @@ impl GpmService {
         let telemetry2 = Arc::clone(&self.telemetry);
         let gpu_monitor = Arc::clone(&self.gpu_monitor);
         let classifier = Arc::clone(&self.process_classifier);
+        let alert_manager = Arc::clone(&self.alert_manager);
         let ollama_monitor = Arc::clone(&self.ollama_monitor);
+        let collector_ollama = Arc::clone(&self.ollama_monitor);
         let config1 = self.config.clone();
         let config2 = self.config.clone();
         let config3 = self.config.clone();
         let shutdown_tx1 = self.shutdown_tx.clone();
         let shutdown_tx2 = self.shutdown_tx.clone();
         let shutdown_tx3 = self.shutdown_tx.clone();
+        let devices_changed = Arc::new(Notify::new());
+        let collector_devices_changed = Arc::clone(&devices_changed);
 
         let metrics_task = tokio::spawn(async move {
-            Self::metrics_collector_loop(gpu_monitor, classifier, storage1, telemetry1, config1.service.poll_interval_secs, shutdown_tx1).await
+            Self::metrics_collector_loop(gpu_monitor, classifier, alert_manager, collector_ollama, storage1, telemetry1, config1.service.poll_interval_secs, collector_devices_changed, shutdown_tx1).await
         });
 
         let ollama_task = tokio::spawn(async move {
-            Self::ollama_monitor_loop(ollama_monitor.clone(), storage2, telemetry2, config2.ollama.enabled, shutdown_tx2).await
+            Self::ollama_monitor_loop(ollama_monitor.clone(), storage2, telemetry2, config2.ollama, shutdown_tx2).await
         });
 
         let maintenance_task = tokio::spawn(async move {
             Self::maintenance_worker_loop(storage3, config3.clone(), shutdown_tx3).await
         });
 
+        let event_task = self.config.gpu.watch_events.then(|| {
+            let config = self.config.clone();
+            let gpu_monitor = Arc::clone(&self.gpu_monitor);
+            let storage = Arc::clone(&self.storage);
+            let telemetry = Arc::clone(&self.telemetry);
+            let alert_manager = Arc::clone(&self.alert_manager);
+            let shutdown_tx = self.shutdown_tx.clone();
+            tokio::spawn(async move {
+                Self::gpu_event_loop(config, gpu_monitor, storage, telemetry, alert_manager, devices_changed, shutdown_tx)
+                    .await
+            })
+        });
+
         // Spawn proxy task if enabled
         let proxy_task = if self.config.ollama.enable_proxy {
             let proxy = OllamaProxy::new(
@@ -124,6 +168,9 @@ impl GpmService {
         let _ = self.shutdown_tx.send(());
 
         let _ = tokio::join!(metrics_task, ollama_task, maintenance_task);
+        if let Some(task) = event_task {
+            let _ = task.await;
+        }
         if let Some(task) = proxy_task {
             let _ = task.await;
         }
@@ -132,16 +179,24 @@ impl GpmService {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn metrics_collector_loop(
         gpu_monitor: Arc<RwLock<GpuMonitorBackend>>,
         classifier: Arc<RwLock<ProcessClassifier>>,
+        alert_manager: Arc<AlertManager>,
+        ollama_monitor: Arc<OllamaMonitor>,
         storage: Arc<StorageManager>,
         telemetry: Arc<TelemetryManager>,
         poll_interval_secs: u64,
+        devices_changed: Arc<Notify>,
         shutdown_tx: tokio::sync::broadcast::Sender<()>,
     ) -> Result<()> {
         let mut interval = interval(Duration::from_secs(poll_interval_secs));
         let mut shutdown_rx = shutdown_tx.subscribe();
+        let mut state = CollectorState {
+            devices_changed,
+            ..Default::default()
+        };
 
         loop {
             tokio::select! {
@@ -149,8 +204,11 @@ impl GpmService {
                     if let Err(e) = Self::collect_and_store_metrics_static(
                         &gpu_monitor,
                         &classifier,
+                        &alert_manager,
+                        &ollama_monitor,
                         &storage,
-                        &telemetry
+                        &telemetry,
+                        &mut state,
                     ).await {
                         error!("Failed to collect metrics: {}", e);
                     }
@@ -165,43 +223,95 @@ impl GpmService {
         Ok(())
     }
 
-    async fn collect_and_store_metrics(
-        &self,
+    async fn collect_and_store_metrics_static(
         gpu_monitor: &Arc<RwLock<GpuMonitorBackend>>,
         classifier: &Arc<RwLock<ProcessClassifier>>,
+        alert_manager: &Arc<AlertManager>,
+        ollama_monitor: &Arc<OllamaMonitor>,
         storage: &Arc<StorageManager>,
+        telemetry: &Arc<TelemetryManager>,
+        state: &mut CollectorState,
     ) -> Result<()> {
-        let gpu_metrics = {
-            let monitor = gpu_monitor.read().await;
-            monitor.collect_metrics()?
+        let collected = gpu_monitor.read().await.collect_metrics();
+
+        // A failed poll still counts: it is how lost GPUs are noticed
+        let device_events = match &collected {
+            Ok(metrics) => state.device_tracker.observe(metrics, None, chrono::Utc::now()),
+            Err(e) => state.device_tracker.observe(&[], Some(&e.to_string()), chrono::Utc::now()),
         };
 
+        if !device_events.is_empty() {
+            state.devices_changed.notify_one();
+        }
+
+        for event in &device_events {
+            storage.database.insert_device_event(event).await?;
+
+            if let Some(prom) = &telemetry.prometheus {
+                prom.record_device_event(event);
+            }
+
+            for alert in alert_manager.process_device_event(event).await {
+                storage.database.upsert_alert(&alert).await?;
+            }
+        }
+
+        let mut gpu_metrics = collected?;
+
+        for metrics in &mut gpu_metrics {
+            metrics.energy_wh = state.energy_meter.observe(metrics);
+        }
+        ollama_monitor.observe_gpus(&gpu_metrics).await;
+
         for metrics in &gpu_metrics {
             storage.database.insert_gpu_metrics(metrics).await?;
 
-            if let Some(otel_metrics) = &self.telemetry.metrics {
+            if let Some(otel_metrics) = &telemetry.metrics {
                 otel_metrics.record_gpu_metrics(metrics);
             }
 
-            if let Some(prom) = &self.telemetry.prometheus {
+            if let Some(prom) = &telemetry.prometheus {
                 prom.update_gpu_metrics(metrics);
             }
+
+            let changed_alerts = alert_manager.process(metrics).await;
+            for alert in &changed_alerts {
+                storage.database.upsert_alert(alert).await?;
+            }
         }
 
-        let classified_processes = {
+        // Overrides are managed through the API, possibly by another process
+        let overrides = storage.database.get_classification_overrides().await?;
+
+        let (classified_processes, process_events) = {
             let mut clf = classifier.write().await;
-            clf.classify_gpu_processes(&gpu_metrics)
+            clf.set_overrides(overrides);
+            let classified = clf.classify_gpu_processes(&gpu_metrics);
+            let events = clf.track_processes(&classified, chrono::Utc::now());
+            (classified, events)
         };
 
         for process in &classified_processes {
-            storage.database.insert_process_event(process).await?;
+            let elapsed_secs = process_events
+                .iter()
+                .find(|e| e.kind != ProcessEventKind::End && e.session.pid == process.pid)
+                .map_or(0, |e| e.elapsed_secs);
+            storage.database.insert_process_event(process, elapsed_secs).await?;
         }
 
-        if let Some(prom) = &self.telemetry.prometheus {
+        for event in &process_events {
+            storage.database.upsert_process_session(&event.session).await?;
+
+            if let Some(otel_metrics) = &telemetry.metrics {
+                otel_metrics.record_process_gpu_time(event);
+            }
+        }
+
+        if let Some(prom) = &telemetry.prometheus {
             prom.update_process_metrics(&classified_processes);
         }
 
-        if let Some(otel_metrics) = &self.telemetry.metrics {
+        if let Some(otel_metrics) = &telemetry.metrics {
             otel_metrics.record_process_metrics(&classified_processes);
         }
 
@@ -214,83 +324,143 @@ impl GpmService {
         Ok(())
     }
 
-    async fn collect_and_store_metrics_static(
-        gpu_monitor: &Arc<RwLock<GpuMonitorBackend>>,
-        classifier: &Arc<RwLock<ProcessClassifier>>,
-        storage: &Arc<StorageManager>,
-        telemetry: &Arc<TelemetryManager>,
+    /// Record driver events, starting a new watcher whenever the GPU list
+    /// changes: an NVML event set only covers the devices it was built with.
+    async fn gpu_event_loop(
+        config: GpmConfig,
+        gpu_monitor: Arc<RwLock<GpuMonitorBackend>>,
+        storage: Arc<StorageManager>,
+        telemetry: Arc<TelemetryManager>,
+        alert_manager: Arc<AlertManager>,
+        devices_changed: Arc<Notify>,
+        shutdown_tx: tokio::sync::broadcast::Sender<()>,
     ) -> Result<()> {
-        let gpu_metrics = {
-            let monitor = gpu_monitor.read().await;
-            monitor.collect_metrics()?
-        };
+        let mut shutdown_rx = shutdown_tx.subscribe();
 
-        for metrics in &gpu_metrics {
-            storage.database.insert_gpu_metrics(metrics).await?;
+        loop {
+            let watcher = Self::gpu_event_watcher(&config, &*gpu_monitor.read().await);
+            let mut events = watcher.as_ref().map(GpuEventWatcher::spawn);
+
+            let restart = loop {
+                let next_event = async {
+                    match &mut events {
+                        Some(events) => events.recv().await,
+                        None => std::future::pending().await,
+                    }
+                };
 
-            if let Some(otel_metrics) = &telemetry.metrics {
-                otel_metrics.record_gpu_metrics(metrics);
+                tokio::select! {
+                    Some(event) = next_event => {
+                        if let Err(e) = Self::record_gpu_event(event, &storage, &telemetry, &alert_manager).await {
+                            error!("Failed to record GPU event: {}", e);
+                        }
+                    }
+                    _ = devices_changed.notified() => break true,
+                    _ = shutdown_rx.recv() => break false,
+                }
+            };
+
+            if let Some(watcher) = &watcher {
+                watcher.stop();
             }
 
-            if let Some(prom) = &telemetry.prometheus {
-                prom.update_gpu_metrics(metrics);
+            if !restart {
+                info!("GPU event monitor shutting down");
+                break;
             }
+            info!("GPU list changed, restarting the GPU event watcher");
         }
 
-        let classified_processes = {
-            let mut clf = classifier.write().await;
-            clf.classify_gpu_processes(&gpu_metrics)
-        };
+        Ok(())
+    }
 
-        for process in &classified_processes {
-            storage.database.insert_process_event(process).await?;
-        }
+    async fn record_gpu_event(
+        mut event: GpuEvent,
+        storage: &StorageManager,
+        telemetry: &TelemetryManager,
+        alert_manager: &AlertManager,
+    ) -> Result<()> {
+        // Kernel log lines only carry the PCI address
+        let gpus = storage.database.get_gpus().await?;
+        let known = gpus.iter().find(|gpu| match (&event.uuid, &event.pci_bus_id) {
+            (Some(uuid), _) => &gpu.uuid == uuid,
+            (None, Some(pci)) => gpu.pci_bus_id.as_deref().is_some_and(|id| crate::gpu::events::same_pci_device(pci, id)),
+            (None, None) => false,
+        });
 
-        if let Some(prom) = &telemetry.prometheus {
-            prom.update_process_metrics(&classified_processes);
+        if let Some(gpu) = known {
+            event.gpu_id.get_or_insert(gpu.gpu_index);
+            event.uuid.get_or_insert_with(|| gpu.uuid.clone());
         }
+        let gpu_name = known.map_or("unknown GPU", |gpu| gpu.name.as_str());
+
+        storage.database.insert_gpu_event(&event).await?;
 
         if let Some(otel_metrics) = &telemetry.metrics {
-            otel_metrics.record_process_metrics(&classified_processes);
+            otel_metrics.record_gpu_event(&event);
         }
 
-        debug!(
-            "Collected metrics from {} GPU(s), classified {} processes",
-            gpu_metrics.len(),
-            classified_processes.len()
-        );
+        if let Some(prom) = &telemetry.prometheus {
+            prom.record_gpu_event(&event);
+        }
+
+        if let Some(alert) = alert_manager.process_gpu_event(&event, gpu_name) {
+            storage.database.upsert_alert(&alert).await?;
+        }
 
         Ok(())
     }
 
+    /// Xid errors are NVIDIA-specific, so other backends get no watcher.
+    fn gpu_event_watcher(config: &GpmConfig, gpu_monitor: &GpuMonitorBackend) -> Option<GpuEventWatcher> {
+        (config.gpu.watch_events && gpu_monitor.is_nvidia()).then(|| GpuEventWatcher::new(&config.gpu.root_prefix))
+    }
+
     async fn ollama_monitor_loop(
         ollama_monitor: Arc<OllamaMonitor>,
         storage: Arc<StorageManager>,
         telemetry: Arc<TelemetryManager>,
-        enabled: bool,
+        config: OllamaConfig,
         shutdown_tx: tokio::sync::broadcast::Sender<()>,
     ) -> Result<()> {
-        if !enabled {
+        // The proxy records sessions and requests even with monitoring off,
+        // so they are stored whenever it runs
+        if !config.enabled && !config.enable_proxy {
             info!("Ollama monitoring disabled");
             return Ok(());
         }
 
         let mut interval = interval(Duration::from_secs(5));
         let mut shutdown_rx = shutdown_tx.subscribe();
+        let session_timeout = chrono::Duration::seconds(config.session_timeout_secs as i64);
 
         loop {
             tokio::select! {
                 _ = interval.tick() => {
-                    if let Err(e) = ollama_monitor.check_and_track_logs().await {
-                        warn!("Failed to check Ollama logs: {}", e);
+                    if config.enabled {
+                        if let Err(e) = ollama_monitor.check_and_track_logs().await {
+                            warn!("Failed to check Ollama logs: {}", e);
+                        }
                     }
 
-                    let sessions = ollama_monitor.get_completed_sessions().await;
-                    for session in sessions {
+                    ollama_monitor.reap_idle_sessions(session_timeout).await;
+
+                    let sessions = ollama_monitor.take_completed_sessions().await;
+                    for mut session in sessions {
+                        match Self::session_hardware(&ollama_monitor, &storage, config.gpu.as_deref(), &session).await {
+                            Ok(hardware) => session.hardware = hardware,
+                            Err(e) => warn!("Failed to attribute GPU usage to LLM session: {}", e),
+                        }
+
                         if let Err(e) = storage.database.insert_llm_session(&session).await {
                             error!("Failed to store LLM session: {}", e);
                         }
 
+                        // Partial counts would skew the throughput histograms
+                        if session.abandoned {
+                            continue;
+                        }
+
                         if let Some(otel_metrics) = &telemetry.metrics {
                             otel_metrics.record_llm_session(&session);
                         }
@@ -299,7 +469,12 @@ impl GpmService {
                             prom.record_llm_session(&session);
                         }
                     }
-                    ollama_monitor.clear_completed_sessions().await;
+
+                    for request in ollama_monitor.take_requests().await {
+                        if let Err(e) = storage.database.insert_proxy_request(&request).await {
+                            error!("Failed to store proxy request: {}", e);
+                        }
+                    }
                 }
                 _ = shutdown_rx.recv() => {
                     info!("Ollama monitor shutting down");
@@ -311,6 +486,34 @@ impl GpmService {
         Ok(())
     }
 
+    /// GPU cost of `session` from the stored samples of `gpu` if given, else
+    /// of the GPUs Ollama was last seen on, else of every GPU.
+    async fn session_hardware(
+        ollama_monitor: &OllamaMonitor,
+        storage: &StorageManager,
+        gpu: Option<&str>,
+        session: &LlmSession,
+    ) -> Result<Option<SessionHardware>> {
+        let Some(end) = session.end_time else {
+            return Ok(None);
+        };
+
+        // Sessions shorter than the poll interval need the next sample
+        let mut samples = storage
+            .database
+            .get_gpu_metrics_between(session.start_time, end + chrono::Duration::seconds(MAX_SAMPLE_GAP_SECS))
+            .await?;
+
+        let hosts = ollama_monitor.host_gpus().await;
+        match gpu.map(GpuSelector::parse) {
+            Some(selector) => samples.retain(|m| selector.matches(m)),
+            None if !hosts.is_empty() => samples.retain(|m| hosts.contains(&m.stable_id())),
+            None => {}
+        }
+
+        Ok(crate::accounting::session_hardware(session, &samples))
+    }
+
     async fn maintenance_worker_loop(
         storage: Arc<StorageManager>,
         config: GpmConfig,
@@ -357,4 +560,71 @@ mod tests {
 
         assert!(result.is_ok() || result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_simulated_pipeline_end_to_end() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut config = GpmConfig::default();
+        config.service.data_dir = dir.path().to_path_buf();
+        config.storage.archive_dir = dir.path().join("archive");
+        config.gpu.backend = crate::config::GpuBackendKind::Simulated;
+        config.gpu.simulated.gpu_count = 2;
+        config.telemetry.enable_opentelemetry = false;
+        config.alerts.temp_threshold_celsius = 0.0;
+        config.alerts.duration_secs = 0;
+
+        let gpu_monitor = Arc::new(RwLock::new(GpuMonitorBackend::initialize(&config).unwrap()));
+        let classifier = Arc::new(RwLock::new(ProcessClassifier::new()));
+        let alert_manager = Arc::new(AlertManager::from_config(&config.alerts).unwrap());
+        let storage = Arc::new(StorageManager::new(&config).await.unwrap());
+        let telemetry = Arc::new(TelemetryManager::new(&config).unwrap());
+        let ollama_monitor = Arc::new(OllamaMonitor::new(config.ollama.api_url.clone()));
+        let mut state = CollectorState::default();
+
+        for _ in 0..3 {
+            GpmService::collect_and_store_metrics_static(
+                &gpu_monitor,
+                &classifier,
+                &alert_manager,
+                &ollama_monitor,
+                &storage,
+                &telemetry,
+                &mut state,
+            )
+                .await
+                .unwrap();
+        }
+
+        let stored = storage.database.get_recent_gpu_metrics(1).await.unwrap();
+        assert_eq!(stored.len(), 6);
+        assert!(stored.iter().all(|m| m.name == "Simulated GPU"));
+        assert_eq!(stored.iter().filter(|m| m.energy_wh.is_some()).count(), 4);
+
+        let active_alerts = storage.database.get_active_alerts().await.unwrap();
+        assert_eq!(active_alerts.len(), 2);
+        assert!(active_alerts.iter().all(|a| a.rule == "gpu_temperature_high"));
+
+        let rendered = telemetry.prometheus.as_ref().unwrap().render_metrics();
+        assert!(rendered.contains("gpm_gpu_utilization_percent{gpu_id=\"1\",gpu_name=\"Simulated GPU\"}"));
+
+        let gpus = storage.database.get_gpus().await.unwrap();
+        assert_eq!(gpus.len(), 2);
+        assert!(gpus.iter().all(|g| g.uuid.starts_with("GPU-")));
+    }
+
+    #[test]
+    fn test_event_watcher_follows_backend_vendor() {
+        use crate::gpu::{NvmlFallbackMonitor, SimulatedBackend};
+
+        let mut config = GpmConfig::default();
+        let nvidia_smi = GpuMonitorBackend::new(Box::new(NvmlFallbackMonitor));
+        let simulated = GpuMonitorBackend::new(Box::new(SimulatedBackend::new(config.gpu.simulated.clone())));
+
+        assert!(GpmService::gpu_event_watcher(&config, &nvidia_smi).is_some());
+        assert!(GpmService::gpu_event_watcher(&config, &simulated).is_none());
+
+        config.gpu.watch_events = false;
+        assert!(GpmService::gpu_event_watcher(&config, &nvidia_smi).is_none());
+    }
 }
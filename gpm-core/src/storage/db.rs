@@ -1,13 +1,77 @@
-use crate::classifier::{ClassifiedProcess, WorkloadCategory};
+use crate::accounting::{EnergySample, UsagePeriod, UserUsage};
+use crate::alerts::{Alert, AlertMetric, AlertSeverity, AlertState};
+use crate::classifier::cgroup::CgroupInfo;
+use crate::classifier::overrides::{ClassificationOverride, OverrideKind, OverrideSet};
+use crate::classifier::{ClassifiedProcess, ProcessEventRecord, WorkloadCategory};
 use crate::error::Result;
-use crate::gpu::GpuMetrics;
-use crate::ollama::LlmSession;
+use crate::gpu::{
+    DeviceEvent, DeviceEventKind, GpuEvent, GpuEventKind, GpuEventSource, GpuIdentity, GpuMetrics, GpuTelemetry,
+    KnownGpu, MigDevice, ProcessUtilization,
+};
+use crate::ollama::{ApiFlavor, LlmSession, ProxyRequest, SessionHardware};
+use crate::tracker::ProcessSession;
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
-use sqlx::{Pool, Sqlite};
+use sqlx::{Pool, Row, Sqlite};
+use std::collections::HashMap;
 use std::path::Path;
 use std::str::FromStr;
 use tracing::info;
 
+/// Tables that are never archived to Parquet, with the column their age is
+/// judged by. A NULL `resolved_at` never compares older than the cutoff.
+const UNARCHIVED_TABLES: &[(&str, &str)] = &[
+    ("gpu_process_sessions", "last_seen"),
+    ("alerts", "resolved_at"),
+    ("gpu_events", "timestamp"),
+    ("gpu_device_events", "timestamp"),
+    ("proxy_requests", "timestamp"),
+];
+
+/// Columns added after the first release. `schema.sql` only creates missing
+/// tables, so databases created by older versions get these via ALTER TABLE.
+const ADDED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("gpu_metrics", "clock_graphics_mhz", "INTEGER"),
+    ("gpu_metrics", "clock_sm_mhz", "INTEGER"),
+    ("gpu_metrics", "clock_memory_mhz", "INTEGER"),
+    ("gpu_metrics", "fan_speed_percent", "INTEGER"),
+    ("gpu_metrics", "pcie_tx_kbps", "INTEGER"),
+    ("gpu_metrics", "pcie_rx_kbps", "INTEGER"),
+    ("gpu_metrics", "pcie_link_gen", "INTEGER"),
+    ("gpu_metrics", "pcie_link_width", "INTEGER"),
+    ("gpu_metrics", "throttle_reasons", "TEXT"),
+    ("gpu_metrics", "performance_state", "INTEGER"),
+    ("gpu_metrics", "power_limit_watts", "INTEGER"),
+    ("gpu_metrics", "ecc_corrected_errors", "BIGINT"),
+    ("gpu_metrics", "ecc_uncorrected_errors", "BIGINT"),
+    ("gpu_metrics", "gpu_uuid", "TEXT"),
+    ("gpu_metrics", "pci_bus_id", "TEXT"),
+    ("gpu_metrics", "total_energy_mj", "BIGINT"),
+    ("gpu_metrics", "energy_wh", "REAL"),
+    ("llm_sessions", "energy_joules", "REAL"),
+    ("llm_sessions", "joules_per_token", "REAL"),
+    ("llm_sessions", "avg_power_watts", "REAL"),
+    ("llm_sessions", "peak_gpu_memory_mb", "BIGINT"),
+    ("llm_sessions", "avg_gpu_utilization", "REAL"),
+    ("llm_sessions", "api_flavor", "TEXT"),
+    ("llm_sessions", "abandoned", "INTEGER NOT NULL DEFAULT 0"),
+    ("process_events", "rule", "TEXT"),
+    ("process_events", "confidence", "REAL"),
+    ("process_events", "evidence", "TEXT"),
+    ("process_events", "container_id", "TEXT"),
+    ("process_events", "container_name", "TEXT"),
+    ("process_events", "container_image", "TEXT"),
+    ("process_events", "container_runtime", "TEXT"),
+    ("process_events", "systemd_unit", "TEXT"),
+    ("process_events", "systemd_slice", "TEXT"),
+    ("process_events", "uid", "INTEGER"),
+    ("process_events", "user", "TEXT"),
+    ("process_events", "power_watts", "REAL"),
+    ("process_events", "gpu_memory_utilization", "INTEGER"),
+    ("process_events", "encoder_utilization", "INTEGER"),
+    ("process_events", "decoder_utilization", "INTEGER"),
+    ("process_events", "mig_device", "TEXT"),
+];
+
 pub struct Database {
     pool: Pool<Sqlite>,
 }
@@ -37,6 +101,10 @@ impl Database {
         Ok(db)
     }
 
+    pub(crate) fn pool(&self) -> &Pool<Sqlite> {
+        &self.pool
+    }
+
     async fn initialize_schema(&self) -> Result<()> {
         let schema = include_str!("schema.sql");
 
@@ -44,17 +112,42 @@ impl Database {
             .execute(&self.pool)
             .await?;
 
+        for (table, column, definition) in ADDED_COLUMNS {
+            self.add_column_if_missing(table, column, definition).await?;
+        }
+
         info!("Database schema initialized");
         Ok(())
     }
 
+    async fn add_column_if_missing(&self, table: &str, column: &str, definition: &str) -> Result<()> {
+        let columns: Vec<(String,)> = sqlx::query_as(&format!("SELECT name FROM pragma_table_info('{}')", table))
+            .fetch_all(&self.pool)
+            .await?;
+
+        if !columns.iter().any(|(name,)| name == column) {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                .execute(&self.pool)
+                .await?;
+            info!("Added column {}.{}", table, column);
+        }
+
+        Ok(())
+    }
+
     pub async fn insert_gpu_metrics(&self, metrics: &GpuMetrics) -> Result<()> {
-        sqlx::query(
+        let t = &metrics.telemetry;
+
+        let result = sqlx::query(
             r#"
             INSERT INTO gpu_metrics (
                 timestamp, gpu_id, name, utilization_gpu, utilization_memory,
-                memory_used, memory_total, temperature, power_usage
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                memory_used, memory_total, temperature, power_usage,
+                clock_graphics_mhz, clock_sm_mhz, clock_memory_mhz, fan_speed_percent,
+                pcie_tx_kbps, pcie_rx_kbps, pcie_link_gen, pcie_link_width, throttle_reasons,
+                performance_state, power_limit_watts, ecc_corrected_errors, ecc_uncorrected_errors,
+                gpu_uuid, pci_bus_id, total_energy_mj, energy_wh
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(metrics.timestamp)
@@ -66,26 +159,302 @@ impl Database {
         .bind(metrics.memory_total as i64)
         .bind(metrics.temperature)
         .bind(metrics.power_usage)
+        .bind(t.clock_graphics_mhz)
+        .bind(t.clock_sm_mhz)
+        .bind(t.clock_memory_mhz)
+        .bind(t.fan_speed_percent)
+        .bind(t.pcie_tx_kbps)
+        .bind(t.pcie_rx_kbps)
+        .bind(t.pcie_link_gen)
+        .bind(t.pcie_link_width)
+        .bind(serde_json::to_string(&t.throttle_reasons)?)
+        .bind(t.performance_state)
+        .bind(t.power_limit_watts)
+        .bind(t.ecc_corrected_errors.map(|e| e as i64))
+        .bind(t.ecc_uncorrected_errors.map(|e| e as i64))
+        .bind(&metrics.identity.uuid)
+        .bind(&metrics.identity.pci_bus_id)
+        .bind(t.total_energy_mj.map(|e| e as i64))
+        .bind(metrics.energy_wh)
         .execute(&self.pool)
         .await?;
 
+        let metrics_id = result.last_insert_rowid();
+        for mig in &metrics.mig_devices {
+            sqlx::query(
+                r#"
+                INSERT INTO gpu_mig_metrics (
+                    metrics_id, timestamp, gpu_id, mig_uuid, mig_index, gpu_instance_id, compute_instance_id,
+                    profile, multiprocessor_count, memory_used, memory_total, utilization_gpu
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(metrics_id)
+            .bind(metrics.timestamp)
+            .bind(metrics.gpu_id)
+            .bind(&mig.uuid)
+            .bind(mig.index)
+            .bind(mig.gpu_instance_id)
+            .bind(mig.compute_instance_id)
+            .bind(&mig.profile)
+            .bind(mig.multiprocessor_count)
+            .bind(mig.memory_used as i64)
+            .bind(mig.memory_total as i64)
+            .bind(mig.utilization_gpu)
+            .execute(&self.pool)
+            .await?;
+        }
+
+        if metrics.identity.uuid.is_some() {
+            self.upsert_gpu(metrics).await?;
+        }
+
         Ok(())
     }
 
+    /// Record the GPU behind `metrics` in the inventory. No-op if the backend
+    /// did not report a UUID.
+    pub async fn upsert_gpu(&self, metrics: &GpuMetrics) -> Result<()> {
+        let Some(uuid) = &metrics.identity.uuid else {
+            return Ok(());
+        };
+        let identity = &metrics.identity;
+
+        sqlx::query(
+            r#"
+            INSERT INTO gpus (
+                uuid, gpu_index, name, serial, pci_bus_id, vbios_version, driver_version,
+                memory_total, first_seen, last_seen
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(uuid) DO UPDATE SET
+                gpu_index = excluded.gpu_index,
+                name = excluded.name,
+                serial = COALESCE(excluded.serial, gpus.serial),
+                pci_bus_id = COALESCE(excluded.pci_bus_id, gpus.pci_bus_id),
+                vbios_version = COALESCE(excluded.vbios_version, gpus.vbios_version),
+                driver_version = COALESCE(excluded.driver_version, gpus.driver_version),
+                memory_total = excluded.memory_total,
+                last_seen = MAX(gpus.last_seen, excluded.last_seen)
+            "#,
+        )
+        .bind(uuid)
+        .bind(metrics.gpu_id)
+        .bind(&metrics.name)
+        .bind(&identity.serial)
+        .bind(&identity.pci_bus_id)
+        .bind(&identity.vbios_version)
+        .bind(&identity.driver_version)
+        .bind(metrics.memory_total as i64)
+        .bind(metrics.timestamp)
+        .bind(metrics.timestamp)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The GPU inventory, most recently seen first.
+    pub async fn get_gpus(&self) -> Result<Vec<KnownGpu>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT uuid, gpu_index, name, serial, pci_bus_id, vbios_version, driver_version,
+                   memory_total, first_seen, last_seen
+            FROM gpus
+            ORDER BY last_seen DESC, gpu_index ASC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let parse = |s: String| {
+            chrono::DateTime::parse_from_rfc3339(&s)
+                .ok()
+                .map(|t| t.with_timezone(&chrono::Utc))
+        };
+
+        let gpus = rows
+            .iter()
+            .map(|row| -> Result<Option<KnownGpu>> {
+                let (Some(first_seen), Some(last_seen)) =
+                    (parse(row.try_get("first_seen")?), parse(row.try_get("last_seen")?))
+                else {
+                    return Ok(None);
+                };
+
+                Ok(Some(KnownGpu {
+                    uuid: row.try_get("uuid")?,
+                    gpu_index: row.try_get::<i64, _>("gpu_index")? as u32,
+                    name: row.try_get("name")?,
+                    serial: row.try_get("serial")?,
+                    pci_bus_id: row.try_get("pci_bus_id")?,
+                    vbios_version: row.try_get("vbios_version")?,
+                    driver_version: row.try_get("driver_version")?,
+                    memory_total: row.try_get::<i64, _>("memory_total")? as u64,
+                    first_seen,
+                    last_seen,
+                }))
+            })
+            .filter_map(Result::transpose)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(gpus)
+    }
+
+    pub async fn insert_device_event(&self, event: &DeviceEvent) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO gpu_device_events (id, timestamp, kind, gpu_index, uuid, name, pci_bus_id, detail)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&event.id)
+        .bind(event.timestamp)
+        .bind(event.kind.as_str())
+        .bind(event.gpu_id)
+        .bind(&event.uuid)
+        .bind(&event.name)
+        .bind(&event.pci_bus_id)
+        .bind(&event.detail)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// GPUs added or removed in the range, newest first.
+    pub async fn get_device_events(
+        &self,
+        start_date: chrono::DateTime<chrono::Utc>,
+        end_date: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<DeviceEvent>> {
+        let rows = sqlx::query_as::<_, (String, String, String, i64, Option<String>, String, Option<String>, Option<String>)>(
+            r#"
+            SELECT id, timestamp, kind, gpu_index, uuid, name, pci_bus_id, detail
+            FROM gpu_device_events
+            WHERE timestamp >= ? AND timestamp <= ?
+            ORDER BY timestamp DESC
+            "#,
+        )
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let events = rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(DeviceEvent {
+                    id: row.0,
+                    timestamp: chrono::DateTime::parse_from_rfc3339(&row.1)
+                        .ok()?
+                        .with_timezone(&chrono::Utc),
+                    kind: DeviceEventKind::parse(&row.2)?,
+                    gpu_id: row.3 as u32,
+                    uuid: row.4,
+                    name: row.5,
+                    pci_bus_id: row.6,
+                    detail: row.7,
+                })
+            })
+            .collect();
+
+        Ok(events)
+    }
+
+    pub async fn insert_gpu_event(&self, event: &GpuEvent) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO gpu_events (id, timestamp, kind, gpu_index, uuid, pci_bus_id, xid, pids, source, message)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&event.id)
+        .bind(event.timestamp)
+        .bind(event.kind.as_str())
+        .bind(event.gpu_id)
+        .bind(&event.uuid)
+        .bind(&event.pci_bus_id)
+        .bind(event.xid.map(|x| x as i64))
+        .bind(serde_json::to_string(&event.pids)?)
+        .bind(event.source.as_str())
+        .bind(&event.message)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Driver events in the range, newest first.
+    pub async fn get_gpu_events(
+        &self,
+        start_date: chrono::DateTime<chrono::Utc>,
+        end_date: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<GpuEvent>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, timestamp, kind, gpu_index, uuid, pci_bus_id, xid, pids, source, message
+            FROM gpu_events
+            WHERE timestamp >= ? AND timestamp <= ?
+            ORDER BY timestamp DESC
+            "#,
+        )
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let events = rows
+            .iter()
+            .map(|row| -> Result<Option<GpuEvent>> {
+                let timestamp = chrono::DateTime::parse_from_rfc3339(&row.try_get::<String, _>("timestamp")?)
+                    .ok()
+                    .map(|t| t.with_timezone(&chrono::Utc));
+                let kind = GpuEventKind::parse(&row.try_get::<String, _>("kind")?);
+                let source = GpuEventSource::parse(&row.try_get::<String, _>("source")?);
+                let (Some(timestamp), Some(kind), Some(source)) = (timestamp, kind, source) else {
+                    return Ok(None);
+                };
+
+                Ok(Some(GpuEvent {
+                    id: row.try_get("id")?,
+                    timestamp,
+                    kind,
+                    gpu_id: row.try_get::<Option<i64>, _>("gpu_index")?.map(|i| i as u32),
+                    uuid: row.try_get("uuid")?,
+                    pci_bus_id: row.try_get("pci_bus_id")?,
+                    xid: row.try_get::<Option<i64>, _>("xid")?.map(|x| x as u64),
+                    pids: serde_json::from_str(&row.try_get::<String, _>("pids")?).unwrap_or_default(),
+                    source,
+                    message: row.try_get("message")?,
+                }))
+            })
+            .filter_map(Result::transpose)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(events)
+    }
+
     pub async fn insert_llm_session(&self, session: &LlmSession) -> Result<()> {
         sqlx::query(
             r#"
             INSERT INTO llm_sessions (
                 id, start_time, end_time, model, prompt_tokens, completion_tokens,
-                total_tokens, tokens_per_second, time_to_first_token_ms, time_per_output_token_ms
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                total_tokens, tokens_per_second, time_to_first_token_ms, time_per_output_token_ms,
+                energy_joules, joules_per_token, avg_power_watts, peak_gpu_memory_mb, avg_gpu_utilization,
+                api_flavor, abandoned
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             ON CONFLICT(id) DO UPDATE SET
                 end_time = excluded.end_time,
                 completion_tokens = excluded.completion_tokens,
                 total_tokens = excluded.total_tokens,
                 tokens_per_second = excluded.tokens_per_second,
                 time_to_first_token_ms = excluded.time_to_first_token_ms,
-                time_per_output_token_ms = excluded.time_per_output_token_ms
+                time_per_output_token_ms = excluded.time_per_output_token_ms,
+                energy_joules = excluded.energy_joules,
+                joules_per_token = excluded.joules_per_token,
+                avg_power_watts = excluded.avg_power_watts,
+                peak_gpu_memory_mb = excluded.peak_gpu_memory_mb,
+                avg_gpu_utilization = excluded.avg_gpu_utilization
             "#,
         )
         .bind(&session.id)
@@ -98,19 +467,63 @@ impl Database {
         .bind(session.tokens_per_second)
         .bind(session.time_to_first_token_ms.map(|t| t as i64))
         .bind(session.time_per_output_token_ms)
+        .bind(session.hardware.as_ref().map(|h| h.energy_joules))
+        .bind(session.hardware.as_ref().and_then(|h| h.joules_per_token))
+        .bind(session.hardware.as_ref().map(|h| h.avg_power_watts))
+        .bind(session.hardware.as_ref().map(|h| h.peak_gpu_memory_mb as i64))
+        .bind(session.hardware.as_ref().map(|h| h.avg_gpu_utilization))
+        .bind(session.api_flavor.as_str())
+        .bind(session.abandoned)
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
-    pub async fn insert_process_event(&self, process: &ClassifiedProcess) -> Result<()> {
+    pub async fn insert_proxy_request(&self, request: &ProxyRequest) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO proxy_requests (
+                id, timestamp, method, endpoint, model, status, latency_ms, request_bytes, response_bytes,
+                client_addr, session_id, input_count, prompt_tokens, pull_status, pull_completed_bytes,
+                pull_total_bytes
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&request.id)
+        .bind(request.timestamp)
+        .bind(&request.method)
+        .bind(&request.endpoint)
+        .bind(&request.model)
+        .bind(request.status as i64)
+        .bind(request.latency_ms as i64)
+        .bind(request.request_bytes as i64)
+        .bind(request.response_bytes as i64)
+        .bind(&request.client_addr)
+        .bind(&request.session_id)
+        .bind(request.input_count.map(|n| n as i64))
+        .bind(request.prompt_tokens.map(|n| n as i64))
+        .bind(&request.pull_status)
+        .bind(request.pull_completed_bytes.map(|n| n as i64))
+        .bind(request.pull_total_bytes.map(|n| n as i64))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// `duration_secs` is the GPU time this sample accounts for, i.e. the
+    /// time since the process was last observed.
+    pub async fn insert_process_event(&self, process: &ClassifiedProcess, duration_secs: u64) -> Result<()> {
         sqlx::query(
             r#"
             INSERT INTO process_events (
                 timestamp, pid, name, category, gpu_memory_mb, gpu_utilization,
-                command_line, exe_path
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                command_line, exe_path, duration_secs, rule, confidence, evidence,
+                container_id, container_name, container_image, container_runtime,
+                systemd_unit, systemd_slice, uid, user, power_watts,
+                gpu_memory_utilization, encoder_utilization, decoder_utilization, mig_device
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(chrono::Utc::now())
@@ -121,71 +534,593 @@ impl Database {
         .bind(process.gpu_utilization)
         .bind(&process.command_line)
         .bind(process.exe_path.as_ref().map(|p| p.to_string_lossy().to_string()))
+        .bind(duration_secs as i64)
+        .bind(&process.rule)
+        .bind(process.confidence)
+        .bind(serde_json::to_string(&process.evidence)?)
+        .bind(&process.cgroup.container_id)
+        .bind(&process.cgroup.container_name)
+        .bind(&process.cgroup.container_image)
+        .bind(&process.cgroup.container_runtime)
+        .bind(&process.cgroup.systemd_unit)
+        .bind(&process.cgroup.systemd_slice)
+        .bind(process.uid.map(|u| u as i64))
+        .bind(&process.user)
+        .bind(process.power_watts)
+        .bind(process.engine_utilization.map(|u| u.memory))
+        .bind(process.engine_utilization.map(|u| u.encoder))
+        .bind(process.engine_utilization.map(|u| u.decoder))
+        .bind(&process.mig_device)
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
-    pub async fn get_recent_gpu_metrics(&self, hours: i64) -> Result<Vec<GpuMetrics>> {
-        let cutoff = chrono::Utc::now() - chrono::Duration::hours(hours);
+    /// Stored process samples in `[start, end]`, newest first, optionally
+    /// limited to one PID.
+    pub async fn get_process_events(
+        &self,
+        start_date: chrono::DateTime<chrono::Utc>,
+        end_date: chrono::DateTime<chrono::Utc>,
+        pid: Option<u32>,
+    ) -> Result<Vec<ProcessEventRecord>> {
+        // Too many columns for a tuple row, so read them by name
+        let rows = sqlx::query(
+            r#"
+            SELECT id, timestamp, pid, name, category, gpu_memory_mb, gpu_utilization,
+                   command_line, exe_path, duration_secs, rule, confidence, evidence,
+                   container_id, container_name, container_image, container_runtime,
+                   systemd_unit, systemd_slice, uid, user, power_watts,
+                   gpu_memory_utilization, encoder_utilization, decoder_utilization, mig_device
+            FROM process_events
+            WHERE timestamp >= ? AND timestamp <= ? AND (? IS NULL OR pid = ?)
+            ORDER BY timestamp DESC
+            "#,
+        )
+        .bind(start_date)
+        .bind(end_date)
+        .bind(pid.map(|p| p as i64))
+        .bind(pid.map(|p| p as i64))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let events = rows
+            .iter()
+            .map(|row| -> Result<Option<ProcessEventRecord>> {
+                let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(&row.try_get::<String, _>("timestamp")?) else {
+                    return Ok(None);
+                };
+                let gpu_utilization = row.try_get::<i64, _>("gpu_utilization")? as u32;
+
+                // Engine columns are only written when the driver reported them
+                let engine = |name: &str| -> Result<Option<u32>> {
+                    Ok(row.try_get::<Option<i64>, _>(name)?.map(|v| v as u32))
+                };
+                let engine_utilization = match (
+                    engine("gpu_memory_utilization")?,
+                    engine("encoder_utilization")?,
+                    engine("decoder_utilization")?,
+                ) {
+                    (Some(memory), Some(encoder), Some(decoder)) => Some(ProcessUtilization {
+                        sm: gpu_utilization,
+                        memory,
+                        encoder,
+                        decoder,
+                    }),
+                    _ => None,
+                };
+
+                Ok(Some(ProcessEventRecord {
+                    id: row.try_get("id")?,
+                    timestamp: timestamp.with_timezone(&chrono::Utc),
+                    duration_secs: row.try_get::<Option<i64>, _>("duration_secs")?.unwrap_or(0) as u64,
+                    process: ClassifiedProcess {
+                        pid: row.try_get::<i64, _>("pid")? as u32,
+                        name: row.try_get("name")?,
+                        category: WorkloadCategory::parse(row.try_get("category")?),
+                        gpu_memory_mb: row.try_get::<i64, _>("gpu_memory_mb")? as u64,
+                        gpu_utilization,
+                        engine_utilization,
+                        command_line: row.try_get::<Option<String>, _>("command_line")?.unwrap_or_default(),
+                        exe_path: row.try_get::<Option<String>, _>("exe_path")?.map(Into::into),
+                        rule: row
+                            .try_get::<Option<String>, _>("rule")?
+                            .unwrap_or_else(|| "unknown".to_string()),
+                        confidence: row.try_get::<Option<f64>, _>("confidence")?.unwrap_or(0.0),
+                        evidence: row
+                            .try_get::<Option<String>, _>("evidence")?
+                            .and_then(|e| serde_json::from_str(&e).ok())
+                            .unwrap_or_default(),
+                        cgroup: CgroupInfo {
+                            container_id: row.try_get("container_id")?,
+                            container_name: row.try_get("container_name")?,
+                            container_image: row.try_get("container_image")?,
+                            container_runtime: row.try_get("container_runtime")?,
+                            systemd_unit: row.try_get("systemd_unit")?,
+                            systemd_slice: row.try_get("systemd_slice")?,
+                        },
+                        uid: row.try_get::<Option<i64>, _>("uid")?.map(|u| u as u32),
+                        user: row.try_get("user")?,
+                        power_watts: row.try_get::<Option<f64>, _>("power_watts")?.unwrap_or(0.0),
+                        mig_device: row.try_get("mig_device")?,
+                    },
+                }))
+            })
+            .filter_map(Result::transpose)
+            .collect::<Result<Vec<_>>>()?;
 
-        let rows = sqlx::query_as::<_, (String, i64, String, i64, i64, i64, i64, i64, i64)>(
+        Ok(events)
+    }
+
+    /// GPU-hours, peak VRAM and energy per user and day/week for process
+    /// samples in `[start, end]`, newest period first. Samples without an
+    /// owner are reported as `unknown`.
+    pub async fn get_user_usage(
+        &self,
+        start_date: chrono::DateTime<chrono::Utc>,
+        end_date: chrono::DateTime<chrono::Utc>,
+        period: UsagePeriod,
+        user: Option<&str>,
+    ) -> Result<Vec<UserUsage>> {
+        let rows = sqlx::query_as::<_, (String, Option<i64>, String, i64, i64, f64, i64)>(&format!(
             r#"
-            SELECT timestamp, gpu_id, name, utilization_gpu, utilization_memory,
-                   memory_used, memory_total, temperature, power_usage
-            FROM gpu_metrics
-            WHERE timestamp >= ?
+            SELECT
+                COALESCE(user, CAST(uid AS TEXT), 'unknown') AS owner,
+                MAX(uid),
+                {bucket} AS period_start,
+                COALESCE(SUM(duration_secs), 0),
+                MAX(gpu_memory_mb),
+                COALESCE(SUM(COALESCE(power_watts, 0) * duration_secs), 0) / 3600.0,
+                COUNT(*)
+            FROM process_events
+            WHERE timestamp >= ? AND timestamp <= ?
+            GROUP BY owner, period_start
+            HAVING ? IS NULL OR owner = ?
+            ORDER BY period_start DESC, SUM(duration_secs) DESC
+            "#,
+            bucket = period.bucket_sql("timestamp"),
+        ))
+        .bind(start_date)
+        .bind(end_date)
+        .bind(user)
+        .bind(user)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let usage = rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(UserUsage {
+                    user: row.0,
+                    uid: row.1.map(|u| u as u32),
+                    period_start: chrono::NaiveDate::parse_from_str(&row.2, "%Y-%m-%d").ok()?,
+                    gpu_hours: row.3 as f64 / 3600.0,
+                    peak_gpu_memory_mb: row.4 as u64,
+                    energy_wh: row.5,
+                    sample_count: row.6 as u64,
+                })
+            })
+            .collect();
+
+        Ok(usage)
+    }
+
+    /// Energy of each process sample in `[start, end]` (its share of board
+    /// power over the time it covers), keyed by category.
+    pub async fn get_category_energy(
+        &self,
+        start_date: chrono::DateTime<chrono::Utc>,
+        end_date: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<EnergySample>> {
+        let rows = sqlx::query_as::<_, (String, String, f64)>(
+            r#"
+            SELECT timestamp, category, power_watts * duration_secs / 3600.0
+            FROM process_events
+            WHERE timestamp >= ? AND timestamp <= ?
+              AND power_watts IS NOT NULL AND duration_secs > 0
             ORDER BY timestamp ASC
             "#,
         )
-        .bind(cutoff)
+        .bind(start_date)
+        .bind(end_date)
         .fetch_all(&self.pool)
         .await?;
 
-        let metrics = rows
+        let samples = rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(EnergySample {
+                    timestamp: chrono::DateTime::parse_from_rfc3339(&row.0).ok()?.with_timezone(&chrono::Utc),
+                    key: row.1,
+                    energy_wh: row.2,
+                })
+            })
+            .collect();
+
+        Ok(samples)
+    }
+
+    pub async fn insert_classification_override(&self, o: &ClassificationOverride) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO classification_overrides (id, kind, pattern, category, note, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&o.id)
+        .bind(o.kind.as_str())
+        .bind(&o.pattern)
+        .bind(o.category.as_str())
+        .bind(&o.note)
+        .bind(o.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// All overrides, oldest first (the order they are matched in).
+    pub async fn get_classification_overrides(&self) -> Result<Vec<ClassificationOverride>> {
+        let rows = sqlx::query_as::<_, (String, String, String, String, Option<String>, String)>(
+            r#"
+            SELECT id, kind, pattern, category, note, created_at
+            FROM classification_overrides
+            ORDER BY created_at ASC, id ASC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let overrides = rows
             .into_iter()
             .filter_map(|row| {
-                Some(GpuMetrics {
-                    timestamp: chrono::DateTime::parse_from_rfc3339(&row.0)
+                Some(ClassificationOverride {
+                    id: row.0,
+                    kind: OverrideKind::parse(&row.1)?,
+                    pattern: row.2,
+                    category: WorkloadCategory::parse(&row.3),
+                    note: row.4,
+                    created_at: chrono::DateTime::parse_from_rfc3339(&row.5)
                         .ok()?
                         .with_timezone(&chrono::Utc),
-                    gpu_id: row.1 as u32,
-                    name: row.2,
-                    utilization_gpu: row.3 as u32,
-                    utilization_memory: row.4 as u32,
-                    memory_used: row.5 as u64,
-                    memory_total: row.6 as u64,
-                    temperature: row.7 as u32,
-                    power_usage: row.8 as u32,
-                    processes: Vec::new(),
                 })
             })
             .collect();
 
-        Ok(metrics)
+        Ok(overrides)
     }
 
-    pub async fn get_llm_sessions(
+    /// Returns false if no override has `id`.
+    pub async fn delete_classification_override(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM classification_overrides WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Re-label stored process samples in `[start, end]` that match one of
+    /// `overrides`. Returns the number of rows changed.
+    /// Apply `overrides` to the process events stored between `start_date`
+    /// and `end_date`, along with the process sessions those events belong to
+    /// and the weekly summaries built from them.
+    pub async fn reclassify_process_events(
         &self,
+        overrides: &OverrideSet,
         start_date: chrono::DateTime<chrono::Utc>,
         end_date: chrono::DateTime<chrono::Utc>,
-    ) -> Result<Vec<LlmSession>> {
+    ) -> Result<u64> {
+        let rows = sqlx::query_as::<_, (i64, chrono::DateTime<chrono::Utc>, i64, Option<String>, Option<String>)>(
+            r#"
+            SELECT id, timestamp, pid, command_line, exe_path
+            FROM process_events
+            WHERE timestamp >= ? AND timestamp <= ?
+            "#,
+        )
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut tx = self.pool.begin().await?;
+        let mut updated = 0;
+        let mut weeks = std::collections::BTreeSet::new();
+
+        for (id, timestamp, pid, command_line, exe_path) in rows {
+            let exe_path = exe_path.map(std::path::PathBuf::from);
+            let Some(classification) = overrides.classify_at(
+                pid as u32,
+                command_line.as_deref().unwrap_or_default(),
+                exe_path.as_deref(),
+                timestamp,
+            ) else {
+                continue;
+            };
+
+            sqlx::query(
+                "UPDATE process_events SET category = ?, rule = ?, confidence = ?, evidence = ? WHERE id = ?",
+            )
+            .bind(classification.category.as_str())
+            .bind(&classification.rule)
+            .bind(classification.confidence)
+            .bind(serde_json::to_string(&classification.evidence)?)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query(
+                "UPDATE gpu_process_sessions SET category = ? WHERE pid = ? AND first_seen <= ? AND last_seen >= ?",
+            )
+            .bind(classification.category.as_str())
+            .bind(pid)
+            .bind(timestamp)
+            .bind(timestamp)
+            .execute(&mut *tx)
+            .await?;
+
+            weeks.insert(timestamp.date_naive().week(chrono::Weekday::Mon).first_day());
+            updated += 1;
+        }
+
+        for week_start in weeks {
+            Self::write_weekly_summary(&mut tx, week_start).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(updated)
+    }
+
+    pub async fn upsert_process_session(&self, session: &ProcessSession) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO gpu_process_sessions (
+                id, pid, name, category, command_line, first_seen, last_seen, end_time,
+                duration_secs, peak_gpu_memory_mb, avg_gpu_utilization, sample_count
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                category = excluded.category,
+                last_seen = excluded.last_seen,
+                end_time = excluded.end_time,
+                duration_secs = excluded.duration_secs,
+                peak_gpu_memory_mb = excluded.peak_gpu_memory_mb,
+                avg_gpu_utilization = excluded.avg_gpu_utilization,
+                sample_count = excluded.sample_count
+            "#,
+        )
+        .bind(&session.id)
+        .bind(session.pid as i64)
+        .bind(&session.name)
+        .bind(session.category.as_str())
+        .bind(&session.command_line)
+        .bind(session.first_seen)
+        .bind(session.last_seen)
+        .bind(session.end_time)
+        .bind(session.duration_secs as i64)
+        .bind(session.peak_gpu_memory_mb as i64)
+        .bind(session.avg_gpu_utilization)
+        .bind(session.sample_count as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// End sessions left open by a previous run at the last time they were
+    /// observed; the tracker starts empty and would never close them.
+    pub async fn close_stale_process_sessions(&self) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE gpu_process_sessions SET end_time = last_seen WHERE end_time IS NULL",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Process sessions that were on the GPU at any point in `[start, end]`,
+    /// newest first.
+    pub async fn get_process_sessions(
+        &self,
+        start_date: chrono::DateTime<chrono::Utc>,
+        end_date: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<ProcessSession>> {
         let rows = sqlx::query_as::<_, (
+            String,
+            i64,
             String,
             String,
             Option<String>,
             String,
-            i64,
+            String,
+            Option<String>,
             i64,
             i64,
             f64,
-            Option<i64>,
-            Option<f64>,
+            i64,
         )>(
+            r#"
+            SELECT id, pid, name, category, command_line, first_seen, last_seen, end_time,
+                   duration_secs, peak_gpu_memory_mb, avg_gpu_utilization, sample_count
+            FROM gpu_process_sessions
+            WHERE first_seen <= ? AND last_seen >= ?
+            ORDER BY first_seen DESC
+            "#,
+        )
+        .bind(end_date)
+        .bind(start_date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let sessions = rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(ProcessSession {
+                    id: row.0,
+                    pid: row.1 as u32,
+                    name: row.2,
+                    category: WorkloadCategory::parse(&row.3),
+                    command_line: row.4.unwrap_or_default(),
+                    first_seen: chrono::DateTime::parse_from_rfc3339(&row.5)
+                        .ok()?
+                        .with_timezone(&chrono::Utc),
+                    last_seen: chrono::DateTime::parse_from_rfc3339(&row.6)
+                        .ok()?
+                        .with_timezone(&chrono::Utc),
+                    end_time: row.7
+                        .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                        .map(|dt| dt.with_timezone(&chrono::Utc)),
+                    duration_secs: row.8 as u64,
+                    peak_gpu_memory_mb: row.9 as u64,
+                    avg_gpu_utilization: row.10,
+                    sample_count: row.11 as u64,
+                })
+            })
+            .collect();
+
+        Ok(sessions)
+    }
+
+    pub async fn get_recent_gpu_metrics(&self, hours: i64) -> Result<Vec<GpuMetrics>> {
+        let end = chrono::Utc::now();
+        let start = end - chrono::Duration::hours(hours);
+
+        self.get_gpu_metrics_between(start, end).await
+    }
+
+    pub async fn get_gpu_metrics_between(
+        &self,
+        start_date: chrono::DateTime<chrono::Utc>,
+        end_date: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<GpuMetrics>> {
+        // Too many columns for a tuple row, so read them by name
+        let rows = sqlx::query(
+            r#"
+            SELECT id, timestamp, gpu_id, name, utilization_gpu, utilization_memory,
+                   memory_used, memory_total, temperature, power_usage,
+                   clock_graphics_mhz, clock_sm_mhz, clock_memory_mhz, fan_speed_percent,
+                   pcie_tx_kbps, pcie_rx_kbps, pcie_link_gen, pcie_link_width, throttle_reasons,
+                   performance_state, power_limit_watts, ecc_corrected_errors, ecc_uncorrected_errors,
+                   gpu_uuid, pci_bus_id, total_energy_mj, energy_wh
+            FROM gpu_metrics
+            WHERE timestamp >= ? AND timestamp <= ?
+            ORDER BY timestamp ASC
+            "#,
+        )
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut mig_devices = self.get_mig_metrics_between(start_date, end_date).await?;
+
+        let metrics = rows
+            .iter()
+            .map(|row| -> Result<Option<GpuMetrics>> {
+                let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(&row.try_get::<String, _>("timestamp")?) else {
+                    return Ok(None);
+                };
+                let count = |name: &str| -> Result<Option<u32>> {
+                    Ok(row.try_get::<Option<i64>, _>(name)?.map(|v| v as u32))
+                };
+
+                Ok(Some(GpuMetrics {
+                    timestamp: timestamp.with_timezone(&chrono::Utc),
+                    gpu_id: row.try_get::<i64, _>("gpu_id")? as u32,
+                    name: row.try_get("name")?,
+                    utilization_gpu: row.try_get::<i64, _>("utilization_gpu")? as u32,
+                    utilization_memory: row.try_get::<i64, _>("utilization_memory")? as u32,
+                    memory_used: row.try_get::<i64, _>("memory_used")? as u64,
+                    memory_total: row.try_get::<i64, _>("memory_total")? as u64,
+                    temperature: row.try_get::<i64, _>("temperature")? as u32,
+                    power_usage: row.try_get::<i64, _>("power_usage")? as u32,
+                    processes: Vec::new(),
+                    telemetry: GpuTelemetry {
+                        clock_graphics_mhz: count("clock_graphics_mhz")?,
+                        clock_sm_mhz: count("clock_sm_mhz")?,
+                        clock_memory_mhz: count("clock_memory_mhz")?,
+                        fan_speed_percent: count("fan_speed_percent")?,
+                        pcie_tx_kbps: count("pcie_tx_kbps")?,
+                        pcie_rx_kbps: count("pcie_rx_kbps")?,
+                        pcie_link_gen: count("pcie_link_gen")?,
+                        pcie_link_width: count("pcie_link_width")?,
+                        throttle_reasons: row
+                            .try_get::<Option<String>, _>("throttle_reasons")?
+                            .and_then(|r| serde_json::from_str(&r).ok())
+                            .unwrap_or_default(),
+                        performance_state: count("performance_state")?,
+                        power_limit_watts: count("power_limit_watts")?,
+                        ecc_corrected_errors: row.try_get::<Option<i64>, _>("ecc_corrected_errors")?.map(|v| v as u64),
+                        ecc_uncorrected_errors: row.try_get::<Option<i64>, _>("ecc_uncorrected_errors")?.map(|v| v as u64),
+                        total_energy_mj: row.try_get::<Option<i64>, _>("total_energy_mj")?.map(|v| v as u64),
+                    },
+                    identity: GpuIdentity {
+                        uuid: row.try_get("gpu_uuid")?,
+                        pci_bus_id: row.try_get("pci_bus_id")?,
+                        ..Default::default()
+                    },
+                    energy_wh: row.try_get("energy_wh")?,
+                    mig_devices: mig_devices.remove(&row.try_get::<i64, _>("id")?).unwrap_or_default(),
+                }))
+            })
+            .filter_map(Result::transpose)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(metrics)
+    }
+
+    /// MIG devices of the samples in the range, by `gpu_metrics` row.
+    async fn get_mig_metrics_between(
+        &self,
+        start_date: chrono::DateTime<chrono::Utc>,
+        end_date: chrono::DateTime<chrono::Utc>,
+    ) -> Result<HashMap<i64, Vec<MigDevice>>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT metrics_id, mig_uuid, mig_index, gpu_instance_id, compute_instance_id,
+                   profile, multiprocessor_count, memory_used, memory_total, utilization_gpu
+            FROM gpu_mig_metrics
+            WHERE timestamp >= ? AND timestamp <= ?
+            ORDER BY metrics_id, mig_index
+            "#,
+        )
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut devices: HashMap<i64, Vec<MigDevice>> = HashMap::new();
+        for row in &rows {
+            devices.entry(row.try_get("metrics_id")?).or_default().push(MigDevice {
+                uuid: row.try_get("mig_uuid")?,
+                index: row.try_get::<i64, _>("mig_index")? as u32,
+                gpu_instance_id: row.try_get::<i64, _>("gpu_instance_id")? as u32,
+                compute_instance_id: row.try_get::<i64, _>("compute_instance_id")? as u32,
+                profile: row.try_get("profile")?,
+                multiprocessor_count: row.try_get::<i64, _>("multiprocessor_count")? as u32,
+                memory_used: row.try_get::<i64, _>("memory_used")? as u64,
+                memory_total: row.try_get::<i64, _>("memory_total")? as u64,
+                utilization_gpu: row.try_get::<Option<i64>, _>("utilization_gpu")?.map(|u| u as u32),
+            });
+        }
+
+        Ok(devices)
+    }
+
+    pub async fn get_llm_sessions(
+        &self,
+        start_date: chrono::DateTime<chrono::Utc>,
+        end_date: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<LlmSession>> {
+        // Too many columns for a tuple row, so read them by name
+        let rows = sqlx::query(
             r#"
             SELECT id, start_time, end_time, model, prompt_tokens, completion_tokens,
-                   total_tokens, tokens_per_second, time_to_first_token_ms, time_per_output_token_ms
+                   total_tokens, tokens_per_second, time_to_first_token_ms, time_per_output_token_ms,
+                   energy_joules, joules_per_token, avg_power_watts, peak_gpu_memory_mb, avg_gpu_utilization,
+                   api_flavor, abandoned
             FROM llm_sessions
             WHERE start_time >= ? AND start_time <= ?
             ORDER BY start_time DESC
@@ -197,43 +1132,296 @@ impl Database {
         .await?;
 
         let sessions = rows
+            .iter()
+            .map(|row| -> Result<Option<LlmSession>> {
+                let Ok(start_time) = chrono::DateTime::parse_from_rfc3339(&row.try_get::<String, _>("start_time")?) else {
+                    return Ok(None);
+                };
+
+                let hardware = match (
+                    row.try_get::<Option<f64>, _>("energy_joules")?,
+                    row.try_get::<Option<f64>, _>("avg_power_watts")?,
+                    row.try_get::<Option<i64>, _>("peak_gpu_memory_mb")?,
+                    row.try_get::<Option<f64>, _>("avg_gpu_utilization")?,
+                ) {
+                    (Some(energy_joules), Some(avg_power_watts), Some(peak), Some(avg_gpu_utilization)) => {
+                        Some(SessionHardware {
+                            energy_joules,
+                            joules_per_token: row.try_get("joules_per_token")?,
+                            avg_power_watts,
+                            peak_gpu_memory_mb: peak as u64,
+                            avg_gpu_utilization,
+                        })
+                    }
+                    _ => None,
+                };
+
+                Ok(Some(LlmSession {
+                    id: row.try_get("id")?,
+                    start_time: start_time.with_timezone(&chrono::Utc),
+                    end_time: row
+                        .try_get::<Option<String>, _>("end_time")?
+                        .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+                        .map(|dt| dt.with_timezone(&chrono::Utc)),
+                    model: row.try_get("model")?,
+                    prompt_tokens: row.try_get::<i64, _>("prompt_tokens")? as u64,
+                    completion_tokens: row.try_get::<i64, _>("completion_tokens")? as u64,
+                    total_tokens: row.try_get::<i64, _>("total_tokens")? as u64,
+                    tokens_per_second: row.try_get("tokens_per_second")?,
+                    time_to_first_token_ms: row.try_get::<Option<i64>, _>("time_to_first_token_ms")?.map(|t| t as u64),
+                    time_per_output_token_ms: row.try_get("time_per_output_token_ms")?,
+                    hardware,
+                    api_flavor: row
+                        .try_get::<Option<String>, _>("api_flavor")?
+                        .as_deref()
+                        .and_then(ApiFlavor::parse)
+                        .unwrap_or_default(),
+                    abandoned: row.try_get("abandoned")?,
+                }))
+            })
+            .filter_map(Result::transpose)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(sessions)
+    }
+
+    /// Proxied calls in the range, newest first, optionally for one endpoint.
+    pub async fn get_proxy_requests(
+        &self,
+        start_date: chrono::DateTime<chrono::Utc>,
+        end_date: chrono::DateTime<chrono::Utc>,
+        endpoint: Option<&str>,
+    ) -> Result<Vec<ProxyRequest>> {
+        // Too many columns for a tuple row, so read them by name
+        let rows = sqlx::query(
+            r#"
+            SELECT id, timestamp, method, endpoint, model, status, latency_ms, request_bytes, response_bytes,
+                   client_addr, session_id, input_count, prompt_tokens, pull_status, pull_completed_bytes,
+                   pull_total_bytes
+            FROM proxy_requests
+            WHERE timestamp >= ? AND timestamp <= ? AND (? IS NULL OR endpoint = ?)
+            ORDER BY timestamp DESC
+            "#,
+        )
+        .bind(start_date)
+        .bind(end_date)
+        .bind(endpoint)
+        .bind(endpoint)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let requests = rows
+            .iter()
+            .map(|row| -> Result<Option<ProxyRequest>> {
+                let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(&row.try_get::<String, _>("timestamp")?) else {
+                    return Ok(None);
+                };
+                let count = |column: &str| -> Result<Option<u64>> {
+                    Ok(row.try_get::<Option<i64>, _>(column)?.map(|n| n as u64))
+                };
+
+                Ok(Some(ProxyRequest {
+                    id: row.try_get("id")?,
+                    timestamp: timestamp.with_timezone(&chrono::Utc),
+                    method: row.try_get("method")?,
+                    endpoint: row.try_get("endpoint")?,
+                    model: row.try_get("model")?,
+                    status: row.try_get::<i64, _>("status")? as u16,
+                    latency_ms: row.try_get::<i64, _>("latency_ms")? as u64,
+                    request_bytes: row.try_get::<i64, _>("request_bytes")? as u64,
+                    response_bytes: row.try_get::<i64, _>("response_bytes")? as u64,
+                    client_addr: row.try_get("client_addr")?,
+                    session_id: row.try_get("session_id")?,
+                    input_count: count("input_count")?,
+                    prompt_tokens: count("prompt_tokens")?,
+                    pull_status: row.try_get("pull_status")?,
+                    pull_completed_bytes: count("pull_completed_bytes")?,
+                    pull_total_bytes: count("pull_total_bytes")?,
+                }))
+            })
+            .filter_map(Result::transpose)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(requests)
+    }
+
+    pub async fn upsert_alert(&self, alert: &Alert) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO alerts (
+                id, rule, metric, severity, state, gpu_id, gpu_name, threshold,
+                value, peak_value, message, started_at, resolved_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                state = excluded.state,
+                value = excluded.value,
+                peak_value = excluded.peak_value,
+                resolved_at = excluded.resolved_at
+            "#,
+        )
+        .bind(&alert.id)
+        .bind(&alert.rule)
+        .bind(alert.metric.as_str())
+        .bind(alert.severity.as_str())
+        .bind(alert.state.as_str())
+        .bind(alert.gpu_id)
+        .bind(&alert.gpu_name)
+        .bind(alert.threshold)
+        .bind(alert.value)
+        .bind(alert.peak_value)
+        .bind(&alert.message)
+        .bind(alert.started_at)
+        .bind(alert.resolved_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark alerts left firing by a previous run as resolved; the engine
+    /// starts with no state, so they would otherwise stay active forever.
+    pub async fn resolve_stale_alerts(&self) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE alerts SET state = ?, resolved_at = ? WHERE state = ?",
+        )
+        .bind(AlertState::Resolved.as_str())
+        .bind(chrono::Utc::now())
+        .bind(AlertState::Firing.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_active_alerts(&self) -> Result<Vec<Alert>> {
+        self.query_alerts("WHERE state = 'firing' ORDER BY started_at DESC", None)
+            .await
+    }
+
+    pub async fn get_alerts(
+        &self,
+        start_date: chrono::DateTime<chrono::Utc>,
+        end_date: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<Alert>> {
+        self.query_alerts(
+            "WHERE started_at >= ? AND started_at <= ? ORDER BY started_at DESC",
+            Some((start_date, end_date)),
+        )
+        .await
+    }
+
+    async fn query_alerts(
+        &self,
+        clause: &str,
+        range: Option<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)>,
+    ) -> Result<Vec<Alert>> {
+        let sql = format!(
+            r#"
+            SELECT id, rule, metric, severity, state, gpu_id, gpu_name, threshold,
+                   value, peak_value, message, started_at, resolved_at
+            FROM alerts
+            {}
+            "#,
+            clause
+        );
+
+        let mut query = sqlx::query_as::<_, (
+            String,
+            String,
+            String,
+            String,
+            String,
+            i64,
+            String,
+            f64,
+            f64,
+            f64,
+            String,
+            String,
+            Option<String>,
+        )>(&sql);
+
+        if let Some((start, end)) = range {
+            query = query.bind(start).bind(end);
+        }
+
+        let rows = query.fetch_all(&self.pool).await?;
+
+        let alerts = rows
             .into_iter()
             .filter_map(|row| {
-                Some(LlmSession {
+                Some(Alert {
                     id: row.0,
-                    start_time: chrono::DateTime::parse_from_rfc3339(&row.1)
+                    rule: row.1,
+                    metric: AlertMetric::parse(&row.2)?,
+                    severity: AlertSeverity::parse(&row.3)?,
+                    state: if row.4 == AlertState::Firing.as_str() {
+                        AlertState::Firing
+                    } else {
+                        AlertState::Resolved
+                    },
+                    gpu_id: row.5 as u32,
+                    gpu_name: row.6,
+                    threshold: row.7,
+                    value: row.8,
+                    peak_value: row.9,
+                    message: row.10,
+                    started_at: chrono::DateTime::parse_from_rfc3339(&row.11)
                         .ok()?
                         .with_timezone(&chrono::Utc),
-                    end_time: row
-                        .2
+                    resolved_at: row
+                        .12
                         .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
                         .map(|dt| dt.with_timezone(&chrono::Utc)),
-                    model: row.3,
-                    prompt_tokens: row.4 as u64,
-                    completion_tokens: row.5 as u64,
-                    total_tokens: row.6 as u64,
-                    tokens_per_second: row.7,
-                    time_to_first_token_ms: row.8.map(|t| t as u64),
-                    time_per_output_token_ms: row.9,
                 })
             })
             .collect();
 
-        Ok(sessions)
+        Ok(alerts)
     }
 
+    /// Delete everything older than `retention_days`, for when Parquet
+    /// archival is off.
     pub async fn cleanup_old_data(&self, retention_days: i64) -> Result<usize> {
         let cutoff = chrono::Utc::now() - chrono::Duration::days(retention_days);
 
-        let result = sqlx::query("DELETE FROM gpu_metrics WHERE timestamp < ?")
-            .bind(cutoff)
-            .execute(&self.pool)
-            .await?;
+        let mut deleted_count = 0;
+        for (table, column) in [
+            ("gpu_mig_metrics", "timestamp"),
+            ("gpu_metrics", "timestamp"),
+            ("process_events", "timestamp"),
+            ("llm_sessions", "start_time"),
+        ] {
+            let result = sqlx::query(&format!("DELETE FROM {} WHERE {} < ?", table, column))
+                .bind(cutoff)
+                .execute(&self.pool)
+                .await?;
+            deleted_count += result.rows_affected() as usize;
+        }
+
+        if deleted_count > 0 {
+            info!("Cleaned up {} old GPU metrics, process events and LLM sessions", deleted_count);
+        }
+
+        Ok(deleted_count + self.prune_unarchived(retention_days).await?)
+    }
+
+    /// Delete rows older than `retention_days` from the tables the Parquet
+    /// archiver does not cover. Alerts still firing are kept.
+    pub async fn prune_unarchived(&self, retention_days: i64) -> Result<usize> {
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(retention_days);
 
-        let deleted_count = result.rows_affected() as usize;
+        let mut deleted_count = 0;
+        for (table, column) in UNARCHIVED_TABLES {
+            let result = sqlx::query(&format!("DELETE FROM {} WHERE {} < ?", table, column))
+                .bind(cutoff)
+                .execute(&self.pool)
+                .await?;
+            deleted_count += result.rows_affected() as usize;
+        }
 
         if deleted_count > 0 {
-            info!("Cleaned up {} old GPU metrics records", deleted_count);
+            info!("Pruned {} old rows from unarchived tables", deleted_count);
         }
 
         Ok(deleted_count)
@@ -243,6 +1431,11 @@ impl Database {
         &self,
         week_start: chrono::NaiveDate,
     ) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        Self::write_weekly_summary(&mut conn, week_start).await
+    }
+
+    async fn write_weekly_summary(conn: &mut sqlx::SqliteConnection, week_start: chrono::NaiveDate) -> Result<()> {
         let week_end = week_start + chrono::Duration::days(7);
 
         for category in &[
@@ -270,7 +1463,7 @@ impl Database {
             .bind(category_str)
             .bind(week_start)
             .bind(week_end)
-            .fetch_one(&self.pool)
+            .fetch_one(&mut *conn)
             .await?;
 
             if row.0 > 0 {
@@ -296,11 +1489,365 @@ impl Database {
                 .bind(row.2)
                 .bind(row.3)
                 .bind(row.0)
-                .execute(&self.pool)
+                .execute(&mut *conn)
                 .await?;
+            } else {
+                // Reclassification can move every event out of a category
+                sqlx::query("DELETE FROM weekly_summaries WHERE week_start = ? AND category = ?")
+                    .bind(week_start)
+                    .bind(category_str)
+                    .execute(&mut *conn)
+                    .await?;
             }
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_old_process_events_table_is_migrated() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("gpm.db");
+
+        {
+            let db = Database::new(&path).await.unwrap();
+            sqlx::query("DROP TABLE process_events").execute(db.pool()).await.unwrap();
+            sqlx::query(
+                "CREATE TABLE process_events (id INTEGER PRIMARY KEY AUTOINCREMENT, timestamp DATETIME NOT NULL, \
+                 pid INTEGER NOT NULL, name TEXT NOT NULL, category TEXT NOT NULL, gpu_memory_mb BIGINT NOT NULL, \
+                 gpu_utilization INTEGER NOT NULL, command_line TEXT, exe_path TEXT, duration_secs INTEGER DEFAULT 0)",
+            )
+            .execute(db.pool())
+            .await
+            .unwrap();
+        }
+
+        let db = Database::new(&path).await.unwrap();
+        let process = ClassifiedProcess {
+            pid: 4242,
+            name: "python3".to_string(),
+            category: WorkloadCategory::LlmInference,
+            gpu_memory_mb: 2048,
+            gpu_utilization: 70,
+            engine_utilization: Some(ProcessUtilization { sm: 70, memory: 35, encoder: 0, decoder: 12 }),
+            command_line: "python3 loader.py --api-key x --model llama".to_string(),
+            exe_path: None,
+            rule: "python_ml_inference".to_string(),
+            confidence: 0.6,
+            evidence: vec!["cmdline matched \"api-key x --model llama\"".to_string()],
+            cgroup: CgroupInfo {
+                container_id: Some("4f2b8c1d9e7a".to_string()),
+                container_name: Some("vllm-server".to_string()),
+                systemd_slice: Some("system.slice".to_string()),
+                ..Default::default()
+            },
+            uid: Some(1000),
+            user: Some("alice".to_string()),
+            power_watts: 75.5,
+            mig_device: Some("MIG-5a1c3e0b-2f4d-5b6e-8c9a-0d1e2f3a4b5c".to_string()),
+        };
+        db.insert_process_event(&process, 2).await.unwrap();
+
+        let now = chrono::Utc::now();
+        let events = db
+            .get_process_events(now - chrono::Duration::hours(1), now, Some(4242))
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].duration_secs, 2);
+        assert_eq!(events[0].process.rule, "python_ml_inference");
+        assert_eq!(events[0].process.evidence, process.evidence);
+        assert_eq!(events[0].process.cgroup, process.cgroup);
+        assert_eq!(events[0].process.user.as_deref(), Some("alice"));
+        assert_eq!(events[0].process.power_watts, 75.5);
+        assert_eq!(events[0].process.engine_utilization, process.engine_utilization);
+        assert_eq!(events[0].process.mig_device, process.mig_device);
+
+        let other_pid = db
+            .get_process_events(now - chrono::Duration::hours(1), now, Some(1))
+            .await
+            .unwrap();
+        assert!(other_pid.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_overrides_persist_and_reclassify() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(dir.path().join("gpm.db")).await.unwrap();
+
+        let process = ClassifiedProcess {
+            pid: 900,
+            name: "trainer".to_string(),
+            category: WorkloadCategory::GeneralCompute,
+            gpu_memory_mb: 4096,
+            gpu_utilization: 95,
+            engine_utilization: None,
+            command_line: "/opt/team/bin/trainer --epochs 3".to_string(),
+            exe_path: Some("/opt/team/bin/trainer".into()),
+            rule: "default".to_string(),
+            confidence: 0.2,
+            evidence: Vec::new(),
+            cgroup: CgroupInfo::default(),
+            uid: None,
+            user: None,
+            power_watts: 0.0,
+            mig_device: None,
+        };
+        db.insert_process_event(&process, 2).await.unwrap();
+        db.insert_process_event(&ClassifiedProcess { pid: 901, exe_path: None, ..process.clone() }, 2)
+            .await
+            .unwrap();
+
+        let pin = ClassificationOverride::new(
+            OverrideKind::Exe,
+            "trainer".to_string(),
+            WorkloadCategory::MlTraining,
+            Some("team training binary".to_string()),
+        )
+        .unwrap();
+        db.insert_classification_override(&pin).await.unwrap();
+        assert_eq!(db.get_classification_overrides().await.unwrap(), vec![pin.clone()]);
+
+        // Pinned after both samples were taken, so it must not relabel 901's
+        let pid_pin =
+            ClassificationOverride::new(OverrideKind::Pid, "901".to_string(), WorkloadCategory::Gaming, None).unwrap();
+
+        let now = chrono::Utc::now();
+        let start = now - chrono::Duration::hours(1);
+        let session = ProcessSession {
+            id: "900-session".to_string(),
+            pid: 900,
+            name: "trainer".to_string(),
+            category: WorkloadCategory::GeneralCompute,
+            command_line: process.command_line.clone(),
+            first_seen: start,
+            last_seen: now,
+            end_time: None,
+            duration_secs: 3600,
+            peak_gpu_memory_mb: 4096,
+            avg_gpu_utilization: 95.0,
+            sample_count: 1,
+        };
+        db.upsert_process_session(&session).await.unwrap();
+        let week_start = now.date_naive().week(chrono::Weekday::Mon).first_day();
+        db.compute_weekly_summary(week_start).await.unwrap();
+
+        let mut specs = db.get_classification_overrides().await.unwrap();
+        specs.push(pid_pin);
+        let overrides = OverrideSet::new(specs);
+        assert_eq!(db.reclassify_process_events(&overrides, start, now).await.unwrap(), 1);
+
+        let events = db.get_process_events(start, now, Some(900)).await.unwrap();
+        assert_eq!(events[0].process.category, WorkloadCategory::MlTraining);
+        assert_eq!(events[0].process.rule, format!("override:{}", pin.id));
+        let events = db.get_process_events(start, now, Some(901)).await.unwrap();
+        assert_eq!(events[0].process.category, WorkloadCategory::GeneralCompute);
+
+        let sessions = db.get_process_sessions(start, now).await.unwrap();
+        assert_eq!(sessions[0].category, WorkloadCategory::MlTraining);
+
+        let summaries: Vec<(String, i64)> =
+            sqlx::query_as("SELECT category, event_count FROM weekly_summaries WHERE week_start = ? ORDER BY category")
+                .bind(week_start)
+                .fetch_all(db.pool())
+                .await
+                .unwrap();
+        assert_eq!(
+            summaries,
+            vec![("general_compute".to_string(), 1), ("ml_training".to_string(), 1)]
+        );
+
+        assert!(db.delete_classification_override(&pin.id).await.unwrap());
+        assert!(!db.delete_classification_override(&pin.id).await.unwrap());
+        assert!(db.get_classification_overrides().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_user_usage_report() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(dir.path().join("gpm.db")).await.unwrap();
+
+        let sample = |pid, user: Option<&str>, gpu_memory_mb, power_watts| ClassifiedProcess {
+            pid,
+            name: "python3".to_string(),
+            category: WorkloadCategory::MlTraining,
+            gpu_memory_mb,
+            gpu_utilization: 90,
+            engine_utilization: None,
+            command_line: "python3 train.py".to_string(),
+            exe_path: None,
+            rule: "python_ml_training".to_string(),
+            confidence: 0.7,
+            evidence: Vec::new(),
+            cgroup: CgroupInfo::default(),
+            uid: user.map(|_| 1000),
+            user: user.map(str::to_string),
+            power_watts,
+            mig_device: None,
+        };
+
+        // Three GPU-hours for alice (400 Wh in total) and one owner-less sample
+        db.insert_process_event(&sample(1, Some("alice"), 6000, 150.0), 3600).await.unwrap();
+        db.insert_process_event(&sample(2, Some("alice"), 2000, 50.0), 3600).await.unwrap();
+        db.insert_process_event(&sample(3, Some("alice"), 1000, 200.0), 3600).await.unwrap();
+        db.insert_process_event(&sample(4, None, 500, 10.0), 360).await.unwrap();
+
+        let now = chrono::Utc::now();
+        let start = now - chrono::Duration::hours(1);
+        let usage = db.get_user_usage(start, now, UsagePeriod::Week, None).await.unwrap();
+        assert_eq!(usage.len(), 2);
+
+        let alice = &usage[0];
+        assert_eq!(alice.user, "alice");
+        assert_eq!(alice.uid, Some(1000));
+        assert_eq!(alice.gpu_hours, 3.0);
+        assert_eq!(alice.peak_gpu_memory_mb, 6000);
+        assert_eq!(alice.energy_wh, 400.0);
+        assert_eq!(alice.sample_count, 3);
+        assert_eq!(chrono::Datelike::weekday(&alice.period_start), chrono::Weekday::Mon);
+        assert_eq!(usage[1].user, "unknown");
+
+        let only_alice = db.get_user_usage(start, now, UsagePeriod::Day, Some("alice")).await.unwrap();
+        assert_eq!(only_alice.len(), 1);
+        assert_eq!(only_alice[0].period_start, now.date_naive());
+    }
+
+    #[tokio::test]
+    async fn test_gpu_inventory_follows_uuid() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(dir.path().join("gpm.db")).await.unwrap();
+
+        let sample = |gpu_id, uuid: &str, minutes_ago| GpuMetrics {
+            timestamp: chrono::Utc::now() - chrono::Duration::minutes(minutes_ago),
+            gpu_id,
+            name: "Test GPU".to_string(),
+            utilization_gpu: 10,
+            utilization_memory: 5,
+            memory_used: 1 << 30,
+            memory_total: 8 << 30,
+            temperature: 50,
+            power_usage: 80,
+            processes: Vec::new(),
+            telemetry: Default::default(),
+            identity: GpuIdentity {
+                uuid: Some(uuid.to_string()),
+                driver_version: Some("550.54.15".to_string()),
+                ..Default::default()
+            },
+            energy_wh: None,
+            mig_devices: Vec::new(),
+        };
+
+        db.insert_gpu_metrics(&sample(0, "GPU-a", 10)).await.unwrap();
+        db.insert_gpu_metrics(&sample(1, "GPU-b", 10)).await.unwrap();
+        // A new card took index 0 and moved GPU-a to index 1
+        db.insert_gpu_metrics(&sample(1, "GPU-a", 5)).await.unwrap();
+        db.insert_gpu_metrics(&sample(0, "GPU-c", 5)).await.unwrap();
+
+        let gpus = db.get_gpus().await.unwrap();
+        assert_eq!(gpus.len(), 3);
+        let a = gpus.iter().find(|g| g.uuid == "GPU-a").unwrap();
+        assert_eq!(a.gpu_index, 1);
+        assert!(a.first_seen < a.last_seen);
+        assert_eq!(a.driver_version.as_deref(), Some("550.54.15"));
+
+        let stored = db.get_recent_gpu_metrics(1).await.unwrap();
+        let selector = crate::gpu::GpuSelector::parse("GPU-a");
+        assert_eq!(stored.iter().filter(|m| selector.matches(m)).count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_mig_devices_stored_with_parent() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(dir.path().join("gpm.db")).await.unwrap();
+
+        let mig = |index, gpu_instance_id, memory_used| MigDevice {
+            uuid: format!("MIG-{}", index),
+            index,
+            gpu_instance_id,
+            compute_instance_id: 0,
+            profile: "3g.20gb".to_string(),
+            multiprocessor_count: 42,
+            memory_used,
+            memory_total: 19968 << 20,
+            utilization_gpu: None,
+        };
+        let sample = |gpu_id, mig_devices| GpuMetrics {
+            timestamp: chrono::Utc::now() - chrono::Duration::minutes(1),
+            gpu_id,
+            name: "NVIDIA A100-SXM4-40GB".to_string(),
+            utilization_gpu: 0,
+            utilization_memory: 0,
+            memory_used: 12 << 30,
+            memory_total: 40 << 30,
+            temperature: 45,
+            power_usage: 120,
+            processes: Vec::new(),
+            telemetry: Default::default(),
+            identity: Default::default(),
+            energy_wh: None,
+            mig_devices,
+        };
+
+        let partitioned = vec![mig(0, 1, 8 << 30), mig(1, 2, 4 << 30)];
+        db.insert_gpu_metrics(&sample(0, partitioned.clone())).await.unwrap();
+        db.insert_gpu_metrics(&sample(1, Vec::new())).await.unwrap();
+
+        let stored = db.get_recent_gpu_metrics(1).await.unwrap();
+        assert_eq!(stored.len(), 2);
+        let a100 = stored.iter().find(|m| m.gpu_id == 0).unwrap();
+        assert_eq!(a100.mig_devices, partitioned);
+        assert!(stored.iter().find(|m| m.gpu_id == 1).unwrap().mig_devices.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_proxy_requests_filtered_by_endpoint() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(dir.path().join("gpm.db")).await.unwrap();
+
+        let started = chrono::Utc::now() - chrono::Duration::minutes(1);
+        let embed = ProxyRequest {
+            id: "embed".to_string(),
+            timestamp: started,
+            method: "POST".to_string(),
+            endpoint: "/api/embed".to_string(),
+            model: Some("nomic-embed-text".to_string()),
+            status: 200,
+            latency_ms: 42,
+            request_bytes: 120,
+            response_bytes: 18_000,
+            client_addr: Some("10.0.0.7".to_string()),
+            input_count: Some(3),
+            prompt_tokens: Some(27),
+            ..Default::default()
+        };
+        let pull = ProxyRequest {
+            id: "pull".to_string(),
+            timestamp: started + chrono::Duration::seconds(1),
+            method: "POST".to_string(),
+            endpoint: "/api/pull".to_string(),
+            model: Some("llama3.2".to_string()),
+            status: 200,
+            latency_ms: 93_000,
+            pull_status: Some("success".to_string()),
+            pull_completed_bytes: Some(2_019_377_376),
+            pull_total_bytes: Some(2_019_377_376),
+            ..Default::default()
+        };
+        db.insert_proxy_request(&embed).await.unwrap();
+        db.insert_proxy_request(&pull).await.unwrap();
+
+        let end = chrono::Utc::now();
+        let all = db.get_proxy_requests(started, end, None).await.unwrap();
+        assert_eq!(all, vec![pull, embed.clone()]);
+
+        let embeds = db.get_proxy_requests(started, end, Some("/api/embed")).await.unwrap();
+        assert_eq!(embeds, vec![embed]);
+    }
+}
@@ -1,8 +1,10 @@
 pub mod db;
 pub mod parquet;
+pub mod query;
 
 pub use db::Database;
-pub use parquet::ParquetArchiver;
+pub use parquet::{ArchiveTable, ParquetArchiver};
+pub use query::HistoryQuery;
 
 use crate::config::GpmConfig;
 use crate::error::Result;
@@ -35,6 +37,8 @@ impl StorageManager {
 
     pub async fn perform_maintenance(&self, config: &GpmConfig) -> Result<()> {
         if !config.storage.enable_parquet_archival {
+            info!("Running storage maintenance (deleting data older than {} days)", self.retention_days);
+            self.database.cleanup_old_data(self.retention_days).await?;
             return Ok(());
         }
 
@@ -43,37 +47,161 @@ impl StorageManager {
 
         info!("Running storage maintenance (archiving data before {})", cutoff_date);
 
-        let db_path = config.database_path();
-
         let gpu_count = self
             .archiver
-            .archive_gpu_metrics(&db_path, cutoff_date)
+            .archive_gpu_metrics(&self.database, cutoff_date)
             .await?;
 
         let process_count = self
             .archiver
-            .archive_process_events(&db_path, cutoff_date)
+            .archive_process_events(&self.database, cutoff_date)
             .await?;
 
         let llm_count = self
             .archiver
-            .archive_llm_sessions(&db_path, cutoff_date)
+            .archive_llm_sessions(&self.database, cutoff_date)
             .await?;
 
         if gpu_count + process_count + llm_count > 0 {
-            self.database
-                .cleanup_old_data(self.retention_days)
-                .await?;
-
             info!(
                 "Archived {} GPU metrics, {} process events, {} LLM sessions",
                 gpu_count, process_count, llm_count
             );
         }
 
+        self.database.prune_unarchived(self.retention_days).await?;
+
         let archive_size = self.archiver.get_archive_size_bytes()?;
         info!("Archive directory size: {:.2} MB", archive_size as f64 / 1024.0 / 1024.0);
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu::{DeviceEvent, DeviceEventKind, GpuMetrics, MigDevice};
+    use crate::ollama::ProxyRequest;
+    use tempfile::tempdir;
+
+    async fn storage_with_old_rows(dir: &std::path::Path, archival: bool) -> (GpmConfig, StorageManager) {
+        let mut config = GpmConfig::default();
+        config.service.data_dir = dir.to_path_buf();
+        config.storage.archive_dir = dir.join("archive");
+        config.storage.retention_days = 7;
+        config.storage.enable_parquet_archival = archival;
+        let storage = StorageManager::new(&config).await.unwrap();
+
+        let now = chrono::Utc::now();
+        for age_days in [10, 0] {
+            let timestamp = now - chrono::Duration::days(age_days);
+            storage
+                .database
+                .insert_gpu_metrics(&GpuMetrics {
+                    timestamp,
+                    gpu_id: 0,
+                    name: "Test GPU".to_string(),
+                    utilization_gpu: 42,
+                    utilization_memory: 10,
+                    memory_used: 1 << 30,
+                    memory_total: 8 << 30,
+                    temperature: 60,
+                    power_usage: 150,
+                    processes: Vec::new(),
+                    telemetry: Default::default(),
+                    identity: Default::default(),
+                    energy_wh: None,
+                    mig_devices: vec![MigDevice {
+                        uuid: format!("MIG-{}", age_days),
+                        index: 0,
+                        gpu_instance_id: 1,
+                        compute_instance_id: 0,
+                        profile: "7g.80gb".to_string(),
+                        multiprocessor_count: 98,
+                        memory_used: 1 << 30,
+                        memory_total: 80 << 30,
+                        utilization_gpu: None,
+                    }],
+                })
+                .await
+                .unwrap();
+            storage
+                .database
+                .insert_device_event(&DeviceEvent {
+                    id: format!("added-{}", age_days),
+                    timestamp,
+                    kind: DeviceEventKind::Added,
+                    gpu_id: 0,
+                    uuid: Some("GPU-0".to_string()),
+                    name: "Test GPU".to_string(),
+                    pci_bus_id: None,
+                    detail: None,
+                })
+                .await
+                .unwrap();
+            storage
+                .database
+                .insert_proxy_request(&ProxyRequest {
+                    id: format!("embed-{}", age_days),
+                    timestamp,
+                    method: "POST".to_string(),
+                    endpoint: "/api/embed".to_string(),
+                    status: 200,
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+
+        (config, storage)
+    }
+
+    async fn mig_row_count(storage: &StorageManager) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM gpu_mig_metrics")
+            .fetch_one(storage.database.pool())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_without_archival_deletes_old_rows() {
+        let dir = tempdir().unwrap();
+        let (config, storage) = storage_with_old_rows(dir.path(), false).await;
+
+        storage.perform_maintenance(&config).await.unwrap();
+
+        assert_eq!(storage.database.get_recent_gpu_metrics(24 * 30).await.unwrap().len(), 1);
+        assert_eq!(mig_row_count(&storage).await, 1);
+        let start = chrono::Utc::now() - chrono::Duration::days(30);
+        let requests = storage.database.get_proxy_requests(start, chrono::Utc::now(), None).await.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].id, "embed-0");
+        let events = storage.database.get_device_events(start, chrono::Utc::now()).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(storage.archiver.list_archives().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_with_archival_prunes_unarchived_tables() {
+        let dir = tempdir().unwrap();
+        let (config, storage) = storage_with_old_rows(dir.path(), true).await;
+
+        storage.perform_maintenance(&config).await.unwrap();
+
+        assert_eq!(storage.database.get_recent_gpu_metrics(24 * 30).await.unwrap().len(), 1);
+        assert_eq!(mig_row_count(&storage).await, 1);
+        let start = chrono::Utc::now() - chrono::Duration::days(30);
+        let requests = storage.database.get_proxy_requests(start, chrono::Utc::now(), None).await.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].id, "embed-0");
+        // The old sample and its MIG row, each in its own table's archive
+        assert_eq!(storage.archiver.list_archives().unwrap().len(), 2);
+        let archived = storage.archiver.scan_gpu_metrics(start, chrono::Utc::now()).unwrap();
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].mig_devices[0].uuid, "MIG-10");
+        let events = storage.database.get_device_events(start, chrono::Utc::now()).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, "added-0");
+    }
+}
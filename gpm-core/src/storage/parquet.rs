@@ -1,7 +1,49 @@
+use crate::accounting::EnergySample;
 use crate::error::{GpmError, Result};
+use crate::gpu::{GpuIdentity, GpuMetrics, GpuTelemetry, MigDevice};
+use crate::ollama::{ApiFlavor, LlmSession, SessionHardware};
+use crate::storage::Database;
 use polars::prelude::*;
+use sqlx::Row;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use tracing::{info, warn};
+use tracing::{debug, error, info};
+
+/// Tables that can be moved from SQLite into the Parquet archive, together
+/// with the column that decides which day a row belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveTable {
+    GpuMetrics,
+    /// Archived along with `GpuMetrics`, day by day
+    GpuMigMetrics,
+    ProcessEvents,
+    LlmSessions,
+}
+
+impl ArchiveTable {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::GpuMetrics => "gpu_metrics",
+            Self::GpuMigMetrics => "gpu_mig_metrics",
+            Self::ProcessEvents => "process_events",
+            Self::LlmSessions => "llm_sessions",
+        }
+    }
+
+    fn time_column(&self) -> &'static str {
+        match self {
+            Self::LlmSessions => "start_time",
+            _ => "timestamp",
+        }
+    }
+}
+
+/// Rows read from one day of one table, plus the highest rowid seen so the
+/// delete can never touch rows that were not written to the archive.
+struct DayBatch {
+    df: DataFrame,
+    max_rowid: i64,
+}
 
 pub struct ParquetArchiver {
     archive_dir: PathBuf,
@@ -16,100 +58,434 @@ impl ParquetArchiver {
         Ok(Self { archive_dir })
     }
 
+    pub fn archive_dir(&self) -> &Path {
+        &self.archive_dir
+    }
+
+    /// Directory holding all partitions of `table`.
+    pub fn table_dir(&self, table: ArchiveTable) -> PathBuf {
+        self.archive_dir.join(table.as_str())
+    }
+
     pub async fn archive_gpu_metrics(
         &self,
-        db_path: &Path,
+        db: &Database,
         cutoff_date: chrono::NaiveDate,
     ) -> Result<usize> {
-        let query = format!(
-            "SELECT * FROM gpu_metrics WHERE DATE(timestamp) < '{}'",
-            cutoff_date
-        );
-
-        self.archive_table(db_path, "gpu_metrics", &query, cutoff_date)
-            .await
+        self.archive_table(db, ArchiveTable::GpuMetrics, cutoff_date).await
     }
 
     pub async fn archive_process_events(
         &self,
-        db_path: &Path,
+        db: &Database,
         cutoff_date: chrono::NaiveDate,
     ) -> Result<usize> {
-        let query = format!(
-            "SELECT * FROM process_events WHERE DATE(timestamp) < '{}'",
-            cutoff_date
-        );
-
-        self.archive_table(db_path, "process_events", &query, cutoff_date)
-            .await
+        self.archive_table(db, ArchiveTable::ProcessEvents, cutoff_date).await
     }
 
     pub async fn archive_llm_sessions(
         &self,
-        db_path: &Path,
+        db: &Database,
         cutoff_date: chrono::NaiveDate,
     ) -> Result<usize> {
-        let query = format!(
-            "SELECT * FROM llm_sessions WHERE DATE(start_time) < '{}'",
-            cutoff_date
-        );
+        self.archive_table(db, ArchiveTable::LlmSessions, cutoff_date).await
+    }
 
-        self.archive_table(db_path, "llm_sessions", &query, cutoff_date)
-            .await
+    /// Move every row of `table` dated before `cutoff_date` into one Parquet
+    /// partition per day. Rows are deleted from SQLite only after the file
+    /// has been fsynced and its row count read back and matched.
+    ///
+    /// Each day of `gpu_metrics` archives that day of `gpu_mig_metrics` first,
+    /// since deleting a sample cascades to its MIG rows.
+    pub async fn archive_table(
+        &self,
+        db: &Database,
+        table: ArchiveTable,
+        cutoff_date: chrono::NaiveDate,
+    ) -> Result<usize> {
+        let days: Vec<String> = sqlx::query_scalar(&format!(
+            "SELECT DISTINCT DATE({col}) FROM {table} WHERE DATE({col}) < ? ORDER BY 1",
+            col = table.time_column(),
+            table = table.as_str(),
+        ))
+        .bind(cutoff_date)
+        .fetch_all(db.pool())
+        .await?;
+
+        if days.is_empty() {
+            info!("No data to archive for table {} before {}", table.as_str(), cutoff_date);
+            return Ok(0);
+        }
+
+        let mut total = 0;
+
+        for day in days {
+            let date = chrono::NaiveDate::parse_from_str(&day, "%Y-%m-%d")
+                .map_err(|e| GpmError::InvalidData(format!("Invalid archive date {}: {}", day, e)))?;
+
+            let archived = async {
+                if table == ArchiveTable::GpuMetrics {
+                    self.archive_day(db, ArchiveTable::GpuMigMetrics, date).await?;
+                }
+                self.archive_day(db, table, date).await
+            };
+
+            match archived.await {
+                Ok(count) => total += count,
+                Err(e) => {
+                    error!("Failed to archive {} for {}: {}", table.as_str(), date, e);
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(total)
     }
 
-    async fn archive_table(
+    async fn archive_day(
         &self,
-        db_path: &Path,
-        table_name: &str,
-        query: &str,
+        db: &Database,
+        table: ArchiveTable,
         date: chrono::NaiveDate,
     ) -> Result<usize> {
-        let df = self.read_from_sqlite(db_path, query)?;
+        let batch = match table {
+            ArchiveTable::GpuMetrics => Self::read_gpu_metrics(db, date).await?,
+            ArchiveTable::GpuMigMetrics => Self::read_gpu_mig_metrics(db, date).await?,
+            ArchiveTable::ProcessEvents => Self::read_process_events(db, date).await?,
+            ArchiveTable::LlmSessions => Self::read_llm_sessions(db, date).await?,
+        };
 
-        if df.height() == 0 {
-            info!("No data to archive for table {} before {}", table_name, date);
+        let expected = batch.df.height();
+        if expected == 0 {
             return Ok(0);
         }
 
-        let parquet_file = self
-            .archive_dir
-            .join(format!("{}_{}.parquet", table_name, date));
+        let partition_dir = self.table_dir(table).join(format!("date={}", date));
+        std::fs::create_dir_all(&partition_dir)?;
+
+        let parquet_file = partition_dir.join(format!(
+            "part-{}.parquet",
+            chrono::Utc::now().format("%Y%m%dT%H%M%S%.6f")
+        ));
+
+        self.write_parquet(&batch.df, &parquet_file)?;
+
+        let written = Self::parquet_row_count(&parquet_file)?;
+        if written != expected {
+            std::fs::remove_file(&parquet_file)?;
+            return Err(GpmError::ParquetError(format!(
+                "Row count mismatch for {}: wrote {}, expected {}",
+                parquet_file.display(),
+                written,
+                expected
+            )));
+        }
+
+        let mut tx = db.pool().begin().await?;
 
-        self.write_parquet(&df, &parquet_file)?;
+        let deleted = sqlx::query(&format!(
+            "DELETE FROM {table} WHERE DATE({col}) = ? AND rowid <= ?",
+            col = table.time_column(),
+            table = table.as_str(),
+        ))
+        .bind(date)
+        .bind(batch.max_rowid)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() as usize;
+
+        if deleted != expected {
+            tx.rollback().await?;
+            return Err(GpmError::ParquetError(format!(
+                "Refusing to delete {} rows from {} for {}: archived {}",
+                deleted,
+                table.as_str(),
+                date,
+                expected
+            )));
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO archive_log (archive_date, table_name, records_archived, parquet_file)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(date)
+        .bind(table.as_str())
+        .bind(expected as i64)
+        .bind(parquet_file.to_string_lossy().to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
 
         info!(
             "Archived {} records from {} to {}",
-            df.height(),
-            table_name,
+            expected,
+            table.as_str(),
             parquet_file.display()
         );
 
-        Ok(df.height())
+        Ok(expected)
     }
 
-    fn read_from_sqlite(&self, _db_path: &Path, _query: &str) -> Result<DataFrame> {
-        warn!("Parquet archival from SQLite not yet implemented - using placeholder");
+    async fn read_gpu_metrics(db: &Database, date: chrono::NaiveDate) -> Result<DayBatch> {
+        // Too many columns for a tuple row, so read them by name
+        let rows = sqlx::query(
+            r#"
+            SELECT rowid AS rowid, timestamp, gpu_id, name, utilization_gpu, utilization_memory,
+                   memory_used, memory_total, temperature, power_usage,
+                   clock_graphics_mhz, clock_sm_mhz, clock_memory_mhz, fan_speed_percent,
+                   pcie_tx_kbps, pcie_rx_kbps, pcie_link_gen, pcie_link_width, throttle_reasons,
+                   performance_state, power_limit_watts, ecc_corrected_errors, ecc_uncorrected_errors,
+                   gpu_uuid, pci_bus_id, total_energy_mj, energy_wh
+            FROM gpu_metrics
+            WHERE DATE(timestamp) = ?
+            ORDER BY rowid ASC
+            "#,
+        )
+        .bind(date)
+        .fetch_all(db.pool())
+        .await?;
 
-        let df = df! {
-            "placeholder" => &[0i64],
-        }
+        let ids: Vec<i64> = column(&rows, "rowid")?;
+        let max_rowid = ids.iter().copied().max().unwrap_or(0);
+        let timestamps: Vec<String> = column(&rows, "timestamp")?;
+
+        let int = |name: &str, dtype: DataType| -> Result<Column> {
+            Series::new(name.into(), column::<Option<i64>>(&rows, name)?)
+                .cast(&dtype)
+                .map(Column::from)
+                .map_err(|e| GpmError::ParquetError(format!("Failed to build {} column: {}", name, e)))
+        };
+
+        let df = DataFrame::new(vec![
+            Series::new("id".into(), ids).into(),
+            timestamp_series("timestamp", timestamps.iter().map(|t| Some(t.as_str())))?.into(),
+            int("gpu_id", DataType::UInt32)?,
+            Series::new("name".into(), column::<String>(&rows, "name")?).into(),
+            int("utilization_gpu", DataType::UInt32)?,
+            int("utilization_memory", DataType::UInt32)?,
+            int("memory_used", DataType::UInt64)?,
+            int("memory_total", DataType::UInt64)?,
+            int("temperature", DataType::UInt32)?,
+            int("power_usage", DataType::UInt32)?,
+            int("clock_graphics_mhz", DataType::UInt32)?,
+            int("clock_sm_mhz", DataType::UInt32)?,
+            int("clock_memory_mhz", DataType::UInt32)?,
+            int("fan_speed_percent", DataType::UInt32)?,
+            int("pcie_tx_kbps", DataType::UInt32)?,
+            int("pcie_rx_kbps", DataType::UInt32)?,
+            int("pcie_link_gen", DataType::UInt32)?,
+            int("pcie_link_width", DataType::UInt32)?,
+            Series::new("throttle_reasons".into(), column::<Option<String>>(&rows, "throttle_reasons")?).into(),
+            int("performance_state", DataType::UInt32)?,
+            int("power_limit_watts", DataType::UInt32)?,
+            int("ecc_corrected_errors", DataType::UInt64)?,
+            int("ecc_uncorrected_errors", DataType::UInt64)?,
+            Series::new("gpu_uuid".into(), column::<Option<String>>(&rows, "gpu_uuid")?).into(),
+            Series::new("pci_bus_id".into(), column::<Option<String>>(&rows, "pci_bus_id")?).into(),
+            int("total_energy_mj", DataType::UInt64)?,
+            Series::new("energy_wh".into(), column::<Option<f64>>(&rows, "energy_wh")?).into(),
+        ])
         .map_err(|e| GpmError::ParquetError(format!("Failed to create DataFrame: {}", e)))?;
 
-        Ok(df)
+        Ok(DayBatch { df, max_rowid })
     }
 
+    async fn read_gpu_mig_metrics(db: &Database, date: chrono::NaiveDate) -> Result<DayBatch> {
+        let rows = sqlx::query(
+            r#"
+            SELECT rowid AS rowid, metrics_id, timestamp, gpu_id, mig_uuid, mig_index, gpu_instance_id,
+                   compute_instance_id, profile, multiprocessor_count, memory_used, memory_total, utilization_gpu
+            FROM gpu_mig_metrics
+            WHERE DATE(timestamp) = ?
+            ORDER BY rowid ASC
+            "#,
+        )
+        .bind(date)
+        .fetch_all(db.pool())
+        .await?;
+
+        let ids: Vec<i64> = column(&rows, "rowid")?;
+        let max_rowid = ids.iter().copied().max().unwrap_or(0);
+        let timestamps: Vec<String> = column(&rows, "timestamp")?;
+
+        let int = |name: &str, dtype: DataType| -> Result<Column> {
+            Series::new(name.into(), column::<Option<i64>>(&rows, name)?)
+                .cast(&dtype)
+                .map(Column::from)
+                .map_err(|e| GpmError::ParquetError(format!("Failed to build {} column: {}", name, e)))
+        };
+
+        let df = DataFrame::new(vec![
+            Series::new("id".into(), ids).into(),
+            Series::new("metrics_id".into(), column::<i64>(&rows, "metrics_id")?).into(),
+            timestamp_series("timestamp", timestamps.iter().map(|t| Some(t.as_str())))?.into(),
+            int("gpu_id", DataType::UInt32)?,
+            Series::new("mig_uuid".into(), column::<String>(&rows, "mig_uuid")?).into(),
+            int("mig_index", DataType::UInt32)?,
+            int("gpu_instance_id", DataType::UInt32)?,
+            int("compute_instance_id", DataType::UInt32)?,
+            Series::new("profile".into(), column::<String>(&rows, "profile")?).into(),
+            int("multiprocessor_count", DataType::UInt32)?,
+            int("memory_used", DataType::UInt64)?,
+            int("memory_total", DataType::UInt64)?,
+            int("utilization_gpu", DataType::UInt32)?,
+        ])
+        .map_err(|e| GpmError::ParquetError(format!("Failed to create DataFrame: {}", e)))?;
+
+        Ok(DayBatch { df, max_rowid })
+    }
+
+    async fn read_process_events(db: &Database, date: chrono::NaiveDate) -> Result<DayBatch> {
+        // Too many columns for a tuple row, so read them by name
+        let rows = sqlx::query(
+            r#"
+            SELECT rowid AS rowid, timestamp, pid, name, category, gpu_memory_mb, gpu_utilization,
+                   command_line, exe_path, duration_secs, rule, confidence, evidence,
+                   container_id, container_name, container_image, container_runtime,
+                   systemd_unit, systemd_slice, uid, user, power_watts,
+                   gpu_memory_utilization, encoder_utilization, decoder_utilization, mig_device
+            FROM process_events
+            WHERE DATE(timestamp) = ?
+            ORDER BY rowid ASC
+            "#,
+        )
+        .bind(date)
+        .fetch_all(db.pool())
+        .await?;
+
+        let ids: Vec<i64> = column(&rows, "rowid")?;
+        let max_rowid = ids.iter().copied().max().unwrap_or(0);
+        let timestamps: Vec<String> = column(&rows, "timestamp")?;
+
+        let text = |name: &str| -> Result<Column> {
+            Ok(Series::new(name.into(), column::<Option<String>>(&rows, name)?).into())
+        };
+        let engine = |name: &str| -> Result<Column> {
+            let values = column::<Option<i64>>(&rows, name)?;
+            Ok(Series::new(name.into(), values.into_iter().map(|v| v.map(|u| u as u32)).collect::<Vec<_>>()).into())
+        };
+
+        let df = DataFrame::new(vec![
+            Series::new("id".into(), ids).into(),
+            timestamp_series("timestamp", timestamps.iter().map(|t| Some(t.as_str())))?.into(),
+            Series::new("pid".into(), column::<i64>(&rows, "pid")?.into_iter().map(|v| v as u32).collect::<Vec<_>>()).into(),
+            Series::new("name".into(), column::<String>(&rows, "name")?).into(),
+            Series::new("category".into(), column::<String>(&rows, "category")?).into(),
+            Series::new("gpu_memory_mb".into(), column::<i64>(&rows, "gpu_memory_mb")?.into_iter().map(|v| v as u64).collect::<Vec<_>>()).into(),
+            Series::new("gpu_utilization".into(), column::<i64>(&rows, "gpu_utilization")?.into_iter().map(|v| v as u32).collect::<Vec<_>>()).into(),
+            text("command_line")?,
+            text("exe_path")?,
+            Series::new("duration_secs".into(), column::<Option<i64>>(&rows, "duration_secs")?.into_iter().map(|v| v.unwrap_or(0)).collect::<Vec<_>>()).into(),
+            text("rule")?,
+            Series::new("confidence".into(), column::<Option<f64>>(&rows, "confidence")?).into(),
+            text("evidence")?,
+            text("container_id")?,
+            text("container_name")?,
+            text("container_image")?,
+            text("container_runtime")?,
+            text("systemd_unit")?,
+            text("systemd_slice")?,
+            Series::new("uid".into(), column::<Option<i64>>(&rows, "uid")?.into_iter().map(|v| v.map(|u| u as u32)).collect::<Vec<_>>()).into(),
+            text("user")?,
+            Series::new("power_watts".into(), column::<Option<f64>>(&rows, "power_watts")?).into(),
+            engine("gpu_memory_utilization")?,
+            engine("encoder_utilization")?,
+            engine("decoder_utilization")?,
+            text("mig_device")?,
+        ])
+        .map_err(|e| GpmError::ParquetError(format!("Failed to create DataFrame: {}", e)))?;
+
+        Ok(DayBatch { df, max_rowid })
+    }
+
+    async fn read_llm_sessions(db: &Database, date: chrono::NaiveDate) -> Result<DayBatch> {
+        // Too many columns for a tuple row, so read them by name
+        let rows = sqlx::query(
+            r#"
+            SELECT rowid AS rowid, id, start_time, end_time, model, prompt_tokens, completion_tokens,
+                   total_tokens, tokens_per_second, time_to_first_token_ms, time_per_output_token_ms,
+                   energy_joules, joules_per_token, avg_power_watts, peak_gpu_memory_mb, avg_gpu_utilization,
+                   api_flavor, abandoned
+            FROM llm_sessions
+            WHERE DATE(start_time) = ?
+            ORDER BY rowid ASC
+            "#,
+        )
+        .bind(date)
+        .fetch_all(db.pool())
+        .await?;
+
+        let max_rowid = column::<i64>(&rows, "rowid")?.into_iter().max().unwrap_or(0);
+        let start_times: Vec<String> = column(&rows, "start_time")?;
+        let end_times: Vec<Option<String>> = column(&rows, "end_time")?;
+
+        let real = |name: &str| -> Result<Column> {
+            Ok(Series::new(name.into(), column::<Option<f64>>(&rows, name)?).into())
+        };
+        let count = |name: &str| -> Result<Column> {
+            let values = column::<Option<i64>>(&rows, name)?;
+            Ok(Series::new(name.into(), values.into_iter().map(|v| v.map(|c| c as u64)).collect::<Vec<_>>()).into())
+        };
+
+        let df = DataFrame::new(vec![
+            Series::new("id".into(), column::<String>(&rows, "id")?).into(),
+            timestamp_series("start_time", start_times.iter().map(|t| Some(t.as_str())))?.into(),
+            timestamp_series("end_time", end_times.iter().map(|t| t.as_deref()))?.into(),
+            Series::new("model".into(), column::<String>(&rows, "model")?).into(),
+            Series::new("prompt_tokens".into(), column::<i64>(&rows, "prompt_tokens")?.into_iter().map(|v| v as u64).collect::<Vec<_>>()).into(),
+            Series::new("completion_tokens".into(), column::<i64>(&rows, "completion_tokens")?.into_iter().map(|v| v as u64).collect::<Vec<_>>()).into(),
+            Series::new("total_tokens".into(), column::<i64>(&rows, "total_tokens")?.into_iter().map(|v| v as u64).collect::<Vec<_>>()).into(),
+            Series::new("tokens_per_second".into(), column::<f64>(&rows, "tokens_per_second")?).into(),
+            count("time_to_first_token_ms")?,
+            real("time_per_output_token_ms")?,
+            real("energy_joules")?,
+            real("joules_per_token")?,
+            real("avg_power_watts")?,
+            count("peak_gpu_memory_mb")?,
+            real("avg_gpu_utilization")?,
+            Series::new("api_flavor".into(), column::<Option<String>>(&rows, "api_flavor")?).into(),
+            Series::new("abandoned".into(), column::<bool>(&rows, "abandoned")?).into(),
+        ])
+        .map_err(|e| GpmError::ParquetError(format!("Failed to create DataFrame: {}", e)))?;
+
+        Ok(DayBatch { df, max_rowid })
+    }
+
+    /// Write `df` to `path` via a temporary file that is fsynced and then
+    /// renamed, so readers never observe a partially written archive.
     fn write_parquet(&self, df: &DataFrame, path: &Path) -> Result<()> {
-        let file = std::fs::File::create(path)?;
+        let tmp_path = path.with_extension("parquet.tmp");
+        let mut file = std::fs::File::create(&tmp_path)?;
 
-        ParquetWriter::new(file)
+        ParquetWriter::new(&mut file)
             .with_compression(ParquetCompression::Snappy)
             .finish(&mut df.clone())
             .map_err(|e| GpmError::ParquetError(format!("Failed to write Parquet: {}", e)))?;
 
+        file.sync_all()?;
+        drop(file);
+
+        std::fs::rename(&tmp_path, path)?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::File::open(parent)?.sync_all()?;
+        }
+
         Ok(())
     }
 
+    fn parquet_row_count(path: &Path) -> Result<usize> {
+        let file = std::fs::File::open(path)?;
+
+        ParquetReader::new(file)
+            .num_rows()
+            .map_err(|e| GpmError::ParquetError(format!("Failed to read Parquet metadata: {}", e)))
+    }
+
     pub fn read_parquet(&self, path: &Path) -> Result<DataFrame> {
         let file = std::fs::File::open(path)?;
 
@@ -120,41 +496,532 @@ impl ParquetArchiver {
         Ok(df)
     }
 
-    pub fn list_archives(&self) -> Result<Vec<PathBuf>> {
-        let mut archives = Vec::new();
+    /// Read archived GPU samples with timestamps in `[start, end]`.
+    pub fn scan_gpu_metrics(
+        &self,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<GpuMetrics>> {
+        let Some(lf) = self.scan_table(ArchiveTable::GpuMetrics, start, end)? else {
+            return Ok(Vec::new());
+        };
+
+        let mut lf = lf.filter(time_range("timestamp", start, end));
+
+        // Archives written before telemetry, GPU identity and energy were recorded
+        // lack those columns; they are only absent when no scanned file has them
+        let schema = lf
+            .collect_schema()
+            .map_err(|e| GpmError::ParquetError(format!("Failed to read gpu_metrics archive schema: {}", e)))?;
+
+        let mut columns = vec![
+            col("timestamp").cast(DataType::Int64),
+            col("gpu_id"),
+            col("name"),
+            col("utilization_gpu"),
+            col("utilization_memory"),
+            col("memory_used"),
+            col("memory_total"),
+            col("temperature"),
+            col("power_usage"),
+        ];
+        // The sample's SQLite id, which its archived MIG rows refer to
+        if schema.contains("id") {
+            columns.push(col("id"));
+        }
+        columns.extend(
+            GPU_OPTIONAL_COLUMNS
+                .iter()
+                .filter(|c| schema.contains(c))
+                .map(|c| col(*c)),
+        );
+
+        let df = lf
+            .select(columns)
+            .collect()
+            .map_err(|e| GpmError::ParquetError(format!("Failed to scan gpu_metrics archive: {}", e)))?;
+
+        let columns = (|| -> PolarsResult<_> {
+            Ok((
+                df.column("timestamp")?.i64()?.clone(),
+                df.column("gpu_id")?.u32()?.clone(),
+                df.column("name")?.str()?.clone(),
+                df.column("utilization_gpu")?.u32()?.clone(),
+                df.column("utilization_memory")?.u32()?.clone(),
+                df.column("memory_used")?.u64()?.clone(),
+                df.column("memory_total")?.u64()?.clone(),
+                df.column("temperature")?.u32()?.clone(),
+                df.column("power_usage")?.u32()?.clone(),
+            ))
+        })()
+        .map_err(|e| GpmError::ParquetError(format!("Unexpected gpu_metrics archive schema: {}", e)))?;
+
+        let (ts, gpu_id, name, util_gpu, util_mem, mem_used, mem_total, temp, power) = columns;
+
+        let opt_u32 = |name: &str| df.column(name).ok().and_then(|c| c.u32().ok()).cloned();
+        let opt_u64 = |name: &str| df.column(name).ok().and_then(|c| c.u64().ok()).cloned();
+        let clock_graphics = opt_u32("clock_graphics_mhz");
+        let clock_sm = opt_u32("clock_sm_mhz");
+        let clock_memory = opt_u32("clock_memory_mhz");
+        let fan_speed = opt_u32("fan_speed_percent");
+        let pcie_tx = opt_u32("pcie_tx_kbps");
+        let pcie_rx = opt_u32("pcie_rx_kbps");
+        let pcie_gen = opt_u32("pcie_link_gen");
+        let pcie_width = opt_u32("pcie_link_width");
+        let throttle = df.column("throttle_reasons").ok().and_then(|c| c.str().ok()).cloned();
+        let pstate = opt_u32("performance_state");
+        let power_limit = opt_u32("power_limit_watts");
+        let ecc_corrected = opt_u64("ecc_corrected_errors");
+        let ecc_uncorrected = opt_u64("ecc_uncorrected_errors");
+        let opt_str = |name: &str| df.column(name).ok().and_then(|c| c.str().ok()).cloned();
+        let gpu_uuid = opt_str("gpu_uuid");
+        let pci_bus_id = opt_str("pci_bus_id");
+        let total_energy = opt_u64("total_energy_mj");
+        let energy = df.column("energy_wh").ok().and_then(|c| c.f64().ok()).cloned();
+        let ids = df.column("id").ok().and_then(|c| c.i64().ok()).cloned();
+        let mut mig_devices = match ids {
+            Some(_) => self.scan_mig_devices(start, end)?,
+            None => HashMap::new(),
+        };
 
-        for entry in std::fs::read_dir(&self.archive_dir)? {
-            let entry = entry?;
-            let path = entry.path();
+        let metrics = (0..df.height())
+            .filter_map(|i| {
+                Some(GpuMetrics {
+                    timestamp: chrono::DateTime::from_timestamp_micros(ts.get(i)?)?,
+                    gpu_id: gpu_id.get(i)?,
+                    name: name.get(i).unwrap_or_default().to_string(),
+                    utilization_gpu: util_gpu.get(i).unwrap_or(0),
+                    utilization_memory: util_mem.get(i).unwrap_or(0),
+                    memory_used: mem_used.get(i).unwrap_or(0),
+                    memory_total: mem_total.get(i).unwrap_or(0),
+                    temperature: temp.get(i).unwrap_or(0),
+                    power_usage: power.get(i).unwrap_or(0),
+                    processes: Vec::new(),
+                    telemetry: GpuTelemetry {
+                        clock_graphics_mhz: clock_graphics.as_ref().and_then(|c| c.get(i)),
+                        clock_sm_mhz: clock_sm.as_ref().and_then(|c| c.get(i)),
+                        clock_memory_mhz: clock_memory.as_ref().and_then(|c| c.get(i)),
+                        fan_speed_percent: fan_speed.as_ref().and_then(|c| c.get(i)),
+                        pcie_tx_kbps: pcie_tx.as_ref().and_then(|c| c.get(i)),
+                        pcie_rx_kbps: pcie_rx.as_ref().and_then(|c| c.get(i)),
+                        pcie_link_gen: pcie_gen.as_ref().and_then(|c| c.get(i)),
+                        pcie_link_width: pcie_width.as_ref().and_then(|c| c.get(i)),
+                        throttle_reasons: throttle
+                            .as_ref()
+                            .and_then(|c| c.get(i))
+                            .and_then(|r| serde_json::from_str(r).ok())
+                            .unwrap_or_default(),
+                        performance_state: pstate.as_ref().and_then(|c| c.get(i)),
+                        power_limit_watts: power_limit.as_ref().and_then(|c| c.get(i)),
+                        ecc_corrected_errors: ecc_corrected.as_ref().and_then(|c| c.get(i)),
+                        ecc_uncorrected_errors: ecc_uncorrected.as_ref().and_then(|c| c.get(i)),
+                        total_energy_mj: total_energy.as_ref().and_then(|c| c.get(i)),
+                    },
+                    identity: GpuIdentity {
+                        uuid: gpu_uuid.as_ref().and_then(|c| c.get(i)).map(str::to_string),
+                        pci_bus_id: pci_bus_id.as_ref().and_then(|c| c.get(i)).map(str::to_string),
+                        ..Default::default()
+                    },
+                    energy_wh: energy.as_ref().and_then(|c| c.get(i)),
+                    mig_devices: ids
+                        .as_ref()
+                        .and_then(|c| c.get(i))
+                        .and_then(|id| mig_devices.remove(&id))
+                        .unwrap_or_default(),
+                })
+            })
+            .collect();
+
+        Ok(metrics)
+    }
+
+    /// Archived MIG devices of the samples taken in `[start, end]`, by
+    /// `gpu_metrics` id.
+    fn scan_mig_devices(
+        &self,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> Result<HashMap<i64, Vec<MigDevice>>> {
+        let Some(lf) = self.scan_table(ArchiveTable::GpuMigMetrics, start, end)? else {
+            return Ok(HashMap::new());
+        };
+
+        let df = lf
+            .filter(time_range("timestamp", start, end))
+            .sort(["metrics_id", "mig_index"], Default::default())
+            .collect()
+            .map_err(|e| GpmError::ParquetError(format!("Failed to scan gpu_mig_metrics archive: {}", e)))?;
+
+        let columns = (|| -> PolarsResult<_> {
+            Ok((
+                df.column("metrics_id")?.i64()?.clone(),
+                df.column("mig_uuid")?.str()?.clone(),
+                df.column("mig_index")?.u32()?.clone(),
+                df.column("gpu_instance_id")?.u32()?.clone(),
+                df.column("compute_instance_id")?.u32()?.clone(),
+                df.column("profile")?.str()?.clone(),
+                df.column("multiprocessor_count")?.u32()?.clone(),
+                df.column("memory_used")?.u64()?.clone(),
+                df.column("memory_total")?.u64()?.clone(),
+                df.column("utilization_gpu")?.u32()?.clone(),
+            ))
+        })()
+        .map_err(|e| GpmError::ParquetError(format!("Unexpected gpu_mig_metrics archive schema: {}", e)))?;
+
+        let (metrics_id, uuid, index, gi, ci, profile, sm_count, mem_used, mem_total, util) = columns;
+
+        let mut devices: HashMap<i64, Vec<MigDevice>> = HashMap::new();
+        for i in 0..df.height() {
+            let Some(id) = metrics_id.get(i) else { continue };
+            devices.entry(id).or_default().push(MigDevice {
+                uuid: uuid.get(i).unwrap_or_default().to_string(),
+                index: index.get(i).unwrap_or(0),
+                gpu_instance_id: gi.get(i).unwrap_or(0),
+                compute_instance_id: ci.get(i).unwrap_or(0),
+                profile: profile.get(i).unwrap_or_default().to_string(),
+                multiprocessor_count: sm_count.get(i).unwrap_or(0),
+                memory_used: mem_used.get(i).unwrap_or(0),
+                memory_total: mem_total.get(i).unwrap_or(0),
+                utilization_gpu: util.get(i),
+            });
+        }
+
+        Ok(devices)
+    }
+
+    /// Per-category energy of the archived process samples in `[start, end]`,
+    /// worked out as `Database::get_category_energy` does for live rows.
+    pub fn scan_category_energy(
+        &self,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<EnergySample>> {
+        let Some(lf) = self.scan_table(ArchiveTable::ProcessEvents, start, end)? else {
+            return Ok(Vec::new());
+        };
 
-            if path.extension().and_then(|s| s.to_str()) == Some("parquet") {
-                archives.push(path);
+        let mut lf = lf.filter(time_range("timestamp", start, end));
+
+        // Archives written before process power was recorded carry no energy
+        let schema = lf
+            .collect_schema()
+            .map_err(|e| GpmError::ParquetError(format!("Failed to read process_events archive schema: {}", e)))?;
+        if !schema.contains("power_watts") {
+            return Ok(Vec::new());
+        }
+
+        let df = lf
+            .filter(col("power_watts").is_not_null().and(col("duration_secs").gt(lit(0))))
+            // Archiving a day again after a crash writes its rows twice
+            .unique(Some(vec!["id".into()]), UniqueKeepStrategy::First)
+            .select([
+                col("timestamp").cast(DataType::Int64),
+                col("category"),
+                (col("power_watts") * col("duration_secs").cast(DataType::Float64) / lit(3600.0)).alias("energy_wh"),
+            ])
+            .sort(["timestamp"], Default::default())
+            .collect()
+            .map_err(|e| GpmError::ParquetError(format!("Failed to scan process_events archive: {}", e)))?;
+
+        let (ts, category, energy) = (|| -> PolarsResult<_> {
+            Ok((
+                df.column("timestamp")?.i64()?.clone(),
+                df.column("category")?.str()?.clone(),
+                df.column("energy_wh")?.f64()?.clone(),
+            ))
+        })()
+        .map_err(|e| GpmError::ParquetError(format!("Unexpected process_events archive schema: {}", e)))?;
+
+        let samples = (0..df.height())
+            .filter_map(|i| {
+                Some(EnergySample {
+                    timestamp: chrono::DateTime::from_timestamp_micros(ts.get(i)?)?,
+                    key: category.get(i)?.to_string(),
+                    energy_wh: energy.get(i)?,
+                })
+            })
+            .collect();
+
+        Ok(samples)
+    }
+
+    /// Read archived LLM sessions that started in `[start, end]`.
+    pub fn scan_llm_sessions(
+        &self,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<LlmSession>> {
+        let Some(lf) = self.scan_table(ArchiveTable::LlmSessions, start, end)? else {
+            return Ok(Vec::new());
+        };
+
+        let mut lf = lf.filter(time_range("start_time", start, end));
+
+        // Archives written before these were recorded lack the optional columns
+        let schema = lf
+            .collect_schema()
+            .map_err(|e| GpmError::ParquetError(format!("Failed to read llm_sessions archive schema: {}", e)))?;
+
+        let mut columns = vec![
+            col("id"),
+            col("start_time").cast(DataType::Int64),
+            col("end_time").cast(DataType::Int64),
+            col("model"),
+            col("prompt_tokens"),
+            col("completion_tokens"),
+            col("total_tokens"),
+            col("tokens_per_second"),
+            col("time_to_first_token_ms"),
+            col("time_per_output_token_ms"),
+        ];
+        columns.extend(
+            LLM_OPTIONAL_COLUMNS
+                .iter()
+                .filter(|c| schema.contains(c))
+                .map(|c| col(*c)),
+        );
+
+        let df = lf
+            .select(columns)
+            .collect()
+            .map_err(|e| GpmError::ParquetError(format!("Failed to scan llm_sessions archive: {}", e)))?;
+
+        let columns = (|| -> PolarsResult<_> {
+            Ok((
+                df.column("id")?.str()?.clone(),
+                df.column("start_time")?.i64()?.clone(),
+                df.column("end_time")?.i64()?.clone(),
+                df.column("model")?.str()?.clone(),
+                df.column("prompt_tokens")?.u64()?.clone(),
+                df.column("completion_tokens")?.u64()?.clone(),
+                df.column("total_tokens")?.u64()?.clone(),
+                df.column("tokens_per_second")?.f64()?.clone(),
+                df.column("time_to_first_token_ms")?.u64()?.clone(),
+                df.column("time_per_output_token_ms")?.f64()?.clone(),
+            ))
+        })()
+        .map_err(|e| GpmError::ParquetError(format!("Unexpected llm_sessions archive schema: {}", e)))?;
+
+        let (id, start_time, end_time, model, prompt, completion, total, tps, ttft, tpot) = columns;
+
+        let opt_f64 = |name: &str| df.column(name).ok().and_then(|c| c.f64().ok()).cloned();
+        let energy = opt_f64("energy_joules");
+        let joules_per_token = opt_f64("joules_per_token");
+        let avg_power = opt_f64("avg_power_watts");
+        let peak_memory = df.column("peak_gpu_memory_mb").ok().and_then(|c| c.u64().ok()).cloned();
+        let avg_util = opt_f64("avg_gpu_utilization");
+        let api_flavor = df.column("api_flavor").ok().and_then(|c| c.str().ok()).cloned();
+        let abandoned = df.column("abandoned").ok().and_then(|c| c.bool().ok()).cloned();
+        let get = |c: &Option<Float64Chunked>, i: usize| c.as_ref().and_then(|c| c.get(i));
+
+        let sessions = (0..df.height())
+            .filter_map(|i| {
+                Some(LlmSession {
+                    id: id.get(i)?.to_string(),
+                    start_time: chrono::DateTime::from_timestamp_micros(start_time.get(i)?)?,
+                    end_time: end_time.get(i).and_then(chrono::DateTime::from_timestamp_micros),
+                    model: model.get(i).unwrap_or_default().to_string(),
+                    prompt_tokens: prompt.get(i).unwrap_or(0),
+                    completion_tokens: completion.get(i).unwrap_or(0),
+                    total_tokens: total.get(i).unwrap_or(0),
+                    tokens_per_second: tps.get(i).unwrap_or(0.0),
+                    time_to_first_token_ms: ttft.get(i),
+                    time_per_output_token_ms: tpot.get(i),
+                    hardware: (|| {
+                        Some(SessionHardware {
+                            energy_joules: get(&energy, i)?,
+                            joules_per_token: get(&joules_per_token, i),
+                            avg_power_watts: get(&avg_power, i)?,
+                            peak_gpu_memory_mb: peak_memory.as_ref()?.get(i)?,
+                            avg_gpu_utilization: get(&avg_util, i)?,
+                        })
+                    })(),
+                    api_flavor: api_flavor
+                        .as_ref()
+                        .and_then(|c| c.get(i))
+                        .and_then(ApiFlavor::parse)
+                        .unwrap_or_default(),
+                    abandoned: abandoned.as_ref().and_then(|c| c.get(i)).unwrap_or(false),
+                })
+            })
+            .collect();
+
+        Ok(sessions)
+    }
+
+    /// Lazily scan the day partitions of `table` that can hold rows between
+    /// `start` and `end`. Returns `None` when no partition overlaps.
+    fn scan_table(
+        &self,
+        table: ArchiveTable,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<LazyFrame>> {
+        let table_dir = self.table_dir(table);
+        if !table_dir.is_dir() {
+            return Ok(None);
+        }
+
+        let (first_day, last_day) = (start.date_naive(), end.date_naive());
+        let mut files = Vec::new();
+
+        for entry in std::fs::read_dir(&table_dir)? {
+            let path = entry?.path();
+
+            let day = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_prefix("date="))
+                .and_then(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+
+            match day {
+                Some(day) if day >= first_day && day <= last_day => collect_files(&path, &mut files)?,
+                _ => {}
             }
         }
 
+        files.retain(|p| p.extension().and_then(|s| s.to_str()) == Some("parquet"));
+        if files.is_empty() {
+            return Ok(None);
+        }
+        files.sort();
+
+        debug!("Scanning {} archived {} file(s)", files.len(), table.as_str());
+
+        let args = ScanArgsParquet {
+            hive_options: polars::io::HiveOptions {
+                enabled: Some(false),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Files written by older versions lack columns added since. Scanning
+        // them together would take the schema of the first file only, so each
+        // is scanned alone and the union of their columns kept.
+        let frames = files
+            .iter()
+            .map(|file| LazyFrame::scan_parquet(file, args.clone()))
+            .collect::<PolarsResult<Vec<_>>>()
+            .and_then(|frames| concat_lf_diagonal(frames, UnionArgs::default()))
+            .map_err(|e| GpmError::ParquetError(format!("Failed to scan {} archive: {}", table.as_str(), e)))?;
+
+        Ok(Some(frames))
+    }
+
+    pub fn list_archives(&self) -> Result<Vec<PathBuf>> {
+        let mut archives = Vec::new();
+        collect_files(&self.archive_dir, &mut archives)?;
+
+        archives.retain(|p| p.extension().and_then(|s| s.to_str()) == Some("parquet"));
         archives.sort();
         Ok(archives)
     }
 
     pub fn get_archive_size_bytes(&self) -> Result<u64> {
-        let mut total_size = 0u64;
-
-        for entry in std::fs::read_dir(&self.archive_dir)? {
-            let entry = entry?;
-            let metadata = entry.metadata()?;
+        let mut files = Vec::new();
+        collect_files(&self.archive_dir, &mut files)?;
 
-            if metadata.is_file() {
-                total_size += metadata.len();
-            }
+        let mut total_size = 0u64;
+        for path in files {
+            total_size += std::fs::metadata(path)?.len();
         }
 
         Ok(total_size)
     }
 }
 
+fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            collect_files(&path, out)?;
+        } else if file_type.is_file() {
+            out.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Columns of archived `gpu_metrics` that older archives may lack.
+const GPU_OPTIONAL_COLUMNS: &[&str] = &[
+    "clock_graphics_mhz",
+    "clock_sm_mhz",
+    "clock_memory_mhz",
+    "fan_speed_percent",
+    "pcie_tx_kbps",
+    "pcie_rx_kbps",
+    "pcie_link_gen",
+    "pcie_link_width",
+    "throttle_reasons",
+    "performance_state",
+    "power_limit_watts",
+    "ecc_corrected_errors",
+    "ecc_uncorrected_errors",
+    "gpu_uuid",
+    "pci_bus_id",
+    "total_energy_mj",
+    "energy_wh",
+];
+
+/// Session hardware, API flavor and abandoned columns of archived
+/// `llm_sessions`, which older archives lack.
+const LLM_OPTIONAL_COLUMNS: &[&str] = &[
+    "energy_joules",
+    "joules_per_token",
+    "avg_power_watts",
+    "peak_gpu_memory_mb",
+    "avg_gpu_utilization",
+    "api_flavor",
+    "abandoned",
+];
+
+/// One column of `rows`, decoded by name.
+fn column<'r, T: sqlx::Decode<'r, sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite>>(
+    rows: &'r [sqlx::sqlite::SqliteRow],
+    name: &str,
+) -> Result<Vec<T>> {
+    rows.iter().map(|r| Ok(r.try_get(name)?)).collect()
+}
+
+/// Inclusive filter on a datetime column, compared as epoch microseconds.
+fn time_range(
+    column: &str,
+    start: chrono::DateTime<chrono::Utc>,
+    end: chrono::DateTime<chrono::Utc>,
+) -> Expr {
+    let ts = col(column).cast(DataType::Int64);
+    ts.clone()
+        .gt_eq(lit(start.timestamp_micros()))
+        .and(ts.lt_eq(lit(end.timestamp_micros())))
+}
+
+/// Build a UTC microsecond datetime column from RFC 3339 strings as stored
+/// by sqlx. Unparseable values become nulls.
+fn timestamp_series<'a>(
+    name: &str,
+    values: impl Iterator<Item = Option<&'a str>>,
+) -> Result<Series> {
+    let micros: Vec<Option<i64>> = values
+        .map(|v| {
+            v.and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+                .map(|dt| dt.timestamp_micros())
+        })
+        .collect();
+
+    Series::new(name.into(), micros)
+        .cast(&DataType::Datetime(TimeUnit::Microseconds, Some("UTC".into())))
+        .map_err(|e| GpmError::ParquetError(format!("Failed to build {} column: {}", name, e)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::gpu::GpuMetrics;
     use tempfile::tempdir;
 
     #[test]
@@ -176,4 +1043,254 @@ mod tests {
 
         assert_eq!(df.shape(), df_read.shape());
     }
+
+    #[test]
+    fn test_scan_merges_older_schemas() {
+        let dir = tempdir().unwrap();
+        let archiver = ParquetArchiver::new(dir.path()).unwrap();
+
+        // The older partition sorts first and lacks the later column
+        let older = df! { "gpu_id" => &[0u32], "utilization_gpu" => &[10u32] }.unwrap();
+        let newer = df! {
+            "gpu_id" => &[1u32],
+            "utilization_gpu" => &[20u32],
+            "fan_speed_percent" => &[55u32],
+        }
+        .unwrap();
+        for (day, df) in [("2026-10-01", &older), ("2026-10-02", &newer)] {
+            let partition = archiver.table_dir(ArchiveTable::GpuMetrics).join(format!("date={}", day));
+            std::fs::create_dir_all(&partition).unwrap();
+            archiver.write_parquet(df, &partition.join("part-0.parquet")).unwrap();
+        }
+
+        let start = chrono::DateTime::parse_from_rfc3339("2026-10-01T00:00:00Z").unwrap().with_timezone(&chrono::Utc);
+        let df = archiver
+            .scan_table(ArchiveTable::GpuMetrics, start, start + chrono::Duration::days(2))
+            .unwrap()
+            .unwrap()
+            .collect()
+            .unwrap();
+
+        assert_eq!(df.height(), 2);
+        let fan = df.column("fan_speed_percent").unwrap().u32().unwrap();
+        assert_eq!(fan.get(0), None);
+        assert_eq!(fan.get(1), Some(55));
+    }
+
+    #[tokio::test]
+    async fn test_archive_gpu_metrics_moves_rows() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(dir.path().join("gpm.db")).await.unwrap();
+        let archiver = ParquetArchiver::new(dir.path().join("archive")).unwrap();
+
+        let now = chrono::Utc::now();
+        for (gpu_id, age_days) in [(0, 10), (1, 10), (0, 9), (0, 0)] {
+            db.insert_gpu_metrics(&GpuMetrics {
+                timestamp: now - chrono::Duration::days(age_days),
+                gpu_id,
+                name: "Test GPU".to_string(),
+                utilization_gpu: 42,
+                utilization_memory: 10,
+                memory_used: 1 << 30,
+                memory_total: 8 << 30,
+                temperature: 60,
+                power_usage: 150,
+                processes: Vec::new(),
+                telemetry: Default::default(),
+                identity: Default::default(),
+                energy_wh: None,
+                mig_devices: Vec::new(),
+            })
+            .await
+            .unwrap();
+        }
+
+        let cutoff = (now - chrono::Duration::days(7)).date_naive();
+        let archived = archiver.archive_gpu_metrics(&db, cutoff).await.unwrap();
+        assert_eq!(archived, 3);
+
+        let archives = archiver.list_archives().unwrap();
+        assert_eq!(archives.len(), 2);
+
+        let df = archiver.read_parquet(&archives[0]).unwrap();
+        assert_eq!(df.height(), 2);
+        assert_eq!(
+            df.column("timestamp").unwrap().dtype(),
+            &DataType::Datetime(TimeUnit::Microseconds, Some("UTC".into()))
+        );
+        assert_eq!(df.column("memory_used").unwrap().dtype(), &DataType::UInt64);
+
+        let remaining = db.get_recent_gpu_metrics(24 * 30).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+
+        let logged: i64 = sqlx::query_scalar("SELECT SUM(records_archived) FROM archive_log")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(logged, 3);
+
+        assert_eq!(archiver.archive_gpu_metrics(&db, cutoff).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_archived_gpu_telemetry_is_scanned() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(dir.path().join("gpm.db")).await.unwrap();
+        let archiver = ParquetArchiver::new(dir.path().join("archive")).unwrap();
+
+        let telemetry = GpuTelemetry {
+            clock_sm_mhz: Some(1410),
+            pcie_tx_kbps: Some(52_000),
+            throttle_reasons: vec!["sw_power_cap".to_string()],
+            performance_state: Some(2),
+            ecc_uncorrected_errors: Some(1),
+            ..Default::default()
+        };
+        let timestamp = chrono::Utc::now() - chrono::Duration::days(10);
+        db.insert_gpu_metrics(&GpuMetrics {
+            timestamp,
+            gpu_id: 0,
+            name: "Test GPU".to_string(),
+            utilization_gpu: 99,
+            utilization_memory: 70,
+            memory_used: 6 << 30,
+            memory_total: 8 << 30,
+            temperature: 83,
+            power_usage: 300,
+            processes: Vec::new(),
+            telemetry: telemetry.clone(),
+            identity: GpuIdentity {
+                uuid: Some("GPU-1234".to_string()),
+                pci_bus_id: Some("00000000:01:00.0".to_string()),
+                ..Default::default()
+            },
+            energy_wh: Some(0.5),
+            mig_devices: Vec::new(),
+        })
+        .await
+        .unwrap();
+
+        let cutoff = (chrono::Utc::now() - chrono::Duration::days(7)).date_naive();
+        assert_eq!(archiver.archive_gpu_metrics(&db, cutoff).await.unwrap(), 1);
+
+        let scanned = archiver
+            .scan_gpu_metrics(timestamp - chrono::Duration::hours(1), timestamp + chrono::Duration::hours(1))
+            .unwrap();
+        assert_eq!(scanned.len(), 1);
+        assert_eq!(scanned[0].telemetry, telemetry);
+        assert_eq!(scanned[0].identity.uuid.as_deref(), Some("GPU-1234"));
+        assert_eq!(scanned[0].energy_wh, Some(0.5));
+    }
+
+    #[tokio::test]
+    async fn test_telemetry_survives_pre_telemetry_archives() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(dir.path().join("gpm.db")).await.unwrap();
+        let archiver = ParquetArchiver::new(dir.path().join("archive")).unwrap();
+
+        let timestamp = chrono::Utc::now() - chrono::Duration::days(10);
+        let telemetry = GpuTelemetry {
+            clock_sm_mhz: Some(1410),
+            fan_speed_percent: Some(60),
+            ..Default::default()
+        };
+        db.insert_gpu_metrics(&GpuMetrics {
+            timestamp,
+            gpu_id: 0,
+            name: "Test GPU".to_string(),
+            utilization_gpu: 99,
+            utilization_memory: 70,
+            memory_used: 6 << 30,
+            memory_total: 8 << 30,
+            temperature: 83,
+            power_usage: 300,
+            processes: Vec::new(),
+            telemetry: telemetry.clone(),
+            identity: Default::default(),
+            energy_wh: Some(0.5),
+            mig_devices: Vec::new(),
+        })
+        .await
+        .unwrap();
+        let cutoff = (chrono::Utc::now() - chrono::Duration::days(7)).date_naive();
+        assert_eq!(archiver.archive_gpu_metrics(&db, cutoff).await.unwrap(), 1);
+
+        // A partition from before telemetry was recorded, a day earlier so it is read first
+        let old_timestamp = timestamp - chrono::Duration::days(1);
+        let old = df! {
+            "timestamp" => &[old_timestamp.timestamp_micros()],
+            "gpu_id" => &[0u32],
+            "name" => &["Test GPU"],
+            "utilization_gpu" => &[50u32],
+            "utilization_memory" => &[20u32],
+            "memory_used" => &[1u64 << 30],
+            "memory_total" => &[8u64 << 30],
+            "temperature" => &[60u32],
+            "power_usage" => &[150u32],
+        }
+        .unwrap()
+        .lazy()
+        .with_column(col("timestamp").cast(DataType::Datetime(TimeUnit::Microseconds, Some("UTC".into()))))
+        .collect()
+        .unwrap();
+        let partition = archiver
+            .table_dir(ArchiveTable::GpuMetrics)
+            .join(format!("date={}", old_timestamp.date_naive()));
+        std::fs::create_dir_all(&partition).unwrap();
+        archiver.write_parquet(&old, &partition.join("part-0.parquet")).unwrap();
+
+        let mut scanned = archiver
+            .scan_gpu_metrics(old_timestamp - chrono::Duration::hours(1), timestamp + chrono::Duration::hours(1))
+            .unwrap();
+        scanned.sort_by_key(|m| m.timestamp);
+        assert_eq!(scanned.len(), 2);
+        assert_eq!(scanned[0].telemetry, GpuTelemetry::default());
+        assert_eq!(scanned[0].energy_wh, None);
+        assert_eq!(scanned[1].telemetry, telemetry);
+        assert_eq!(scanned[1].energy_wh, Some(0.5));
+    }
+
+    #[tokio::test]
+    async fn test_archive_process_events_keeps_attribution() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(dir.path().join("gpm.db")).await.unwrap();
+        let archiver = ParquetArchiver::new(dir.path().join("archive")).unwrap();
+
+        let process = crate::classifier::ClassifiedProcess {
+            pid: 4242,
+            name: "python3".to_string(),
+            category: crate::classifier::WorkloadCategory::MlTraining,
+            gpu_memory_mb: 2048,
+            gpu_utilization: 90,
+            engine_utilization: None,
+            command_line: "python3 train.py".to_string(),
+            exe_path: None,
+            rule: "python_ml_training".to_string(),
+            confidence: 0.7,
+            evidence: Vec::new(),
+            cgroup: crate::classifier::cgroup::CgroupInfo {
+                container_name: Some("trainer".to_string()),
+                ..Default::default()
+            },
+            uid: Some(1000),
+            user: Some("alice".to_string()),
+            power_watts: 150.0,
+            mig_device: None,
+        };
+        db.insert_process_event(&process, 2).await.unwrap();
+        sqlx::query("UPDATE process_events SET timestamp = ?")
+            .bind(chrono::Utc::now() - chrono::Duration::days(10))
+            .execute(db.pool())
+            .await
+            .unwrap();
+
+        let cutoff = (chrono::Utc::now() - chrono::Duration::days(7)).date_naive();
+        assert_eq!(archiver.archive_process_events(&db, cutoff).await.unwrap(), 1);
+
+        let df = archiver.read_parquet(&archiver.list_archives().unwrap()[0]).unwrap();
+        assert_eq!(df.column("container_name").unwrap().str().unwrap().get(0), Some("trainer"));
+        assert_eq!(df.column("rule").unwrap().str().unwrap().get(0), Some("python_ml_training"));
+        assert_eq!(df.column("pid").unwrap().u32().unwrap().get(0), Some(4242));
+        assert_eq!(df.column("user").unwrap().str().unwrap().get(0), Some("alice"));
+    }
 }
@@ -0,0 +1,228 @@
+use crate::accounting::EnergySample;
+use crate::error::Result;
+use crate::gpu::GpuMetrics;
+use crate::ollama::LlmSession;
+use crate::storage::{Database, ParquetArchiver};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Time-range reads that span both the hot SQLite tables and the cold
+/// Parquet archive, so callers do not need to know where rows live.
+///
+/// Rows are moved rather than copied by archival, but a crash between the
+/// Parquet write and the SQLite delete can leave a row in both places, so
+/// results are de-duplicated.
+pub struct HistoryQuery {
+    database: Arc<Database>,
+    archiver: ParquetArchiver,
+}
+
+impl HistoryQuery {
+    pub fn new<P: AsRef<Path>>(database: Arc<Database>, archive_dir: P) -> Result<Self> {
+        Ok(Self {
+            database,
+            archiver: ParquetArchiver::new(archive_dir)?,
+        })
+    }
+
+    /// GPU samples in `[start, end]`, oldest first.
+    pub async fn gpu_metrics(
+        &self,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<GpuMetrics>> {
+        let mut metrics = self.archiver.scan_gpu_metrics(start, end)?;
+        metrics.extend(self.database.get_gpu_metrics_between(start, end).await?);
+
+        // Archived timestamps are stored with microsecond precision
+        metrics.sort_by_key(|m| (m.timestamp.timestamp_micros(), m.gpu_id));
+        metrics.dedup_by_key(|m| (m.timestamp.timestamp_micros(), m.gpu_id));
+
+        Ok(metrics)
+    }
+
+    /// GPU samples from the last `hours` hours, oldest first.
+    pub async fn recent_gpu_metrics(&self, hours: i64) -> Result<Vec<GpuMetrics>> {
+        let end = chrono::Utc::now();
+        self.gpu_metrics(end - chrono::Duration::hours(hours), end).await
+    }
+
+    /// Per-category process energy in `[start, end]`, oldest first.
+    pub async fn category_energy(
+        &self,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<EnergySample>> {
+        let mut samples = self.database.get_category_energy(start, end).await?;
+
+        // Days are archived whole, so a day still in SQLite is only in the
+        // archive too if the delete did not happen; SQLite wins for it
+        let live_days: std::collections::HashSet<_> = samples.iter().map(|s| s.timestamp.date_naive()).collect();
+        samples.extend(
+            self.archiver
+                .scan_category_energy(start, end)?
+                .into_iter()
+                .filter(|s| !live_days.contains(&s.timestamp.date_naive())),
+        );
+
+        samples.sort_by_key(|s| s.timestamp);
+
+        Ok(samples)
+    }
+
+    /// LLM sessions that started in `[start, end]`, newest first.
+    pub async fn llm_sessions(
+        &self,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<LlmSession>> {
+        let mut sessions = self.database.get_llm_sessions(start, end).await?;
+
+        let archived: Vec<_> = self
+            .archiver
+            .scan_llm_sessions(start, end)?
+            .into_iter()
+            .filter(|a| !sessions.iter().any(|s| s.id == a.id))
+            .collect();
+        sessions.extend(archived);
+
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.start_time));
+
+        Ok(sessions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn gpu_sample(timestamp: chrono::DateTime<chrono::Utc>, utilization_gpu: u32) -> GpuMetrics {
+        GpuMetrics {
+            timestamp,
+            gpu_id: 0,
+            name: "Test GPU".to_string(),
+            utilization_gpu,
+            utilization_memory: 10,
+            memory_used: 1 << 30,
+            memory_total: 8 << 30,
+            temperature: 60,
+            power_usage: 150,
+            processes: Vec::new(),
+            telemetry: Default::default(),
+            identity: Default::default(),
+            energy_wh: None,
+            mig_devices: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_history_spans_archive_and_database() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Database::new(dir.path().join("gpm.db")).await.unwrap());
+        let archive_dir = dir.path().join("archive");
+        let history = HistoryQuery::new(Arc::clone(&db), &archive_dir).unwrap();
+
+        let now = chrono::Utc::now();
+        db.insert_gpu_metrics(&gpu_sample(now - chrono::Duration::days(10), 11)).await.unwrap();
+        db.insert_gpu_metrics(&gpu_sample(now - chrono::Duration::hours(1), 22)).await.unwrap();
+
+        db.insert_llm_session(&LlmSession {
+            id: "old-session".to_string(),
+            start_time: now - chrono::Duration::days(10),
+            end_time: Some(now - chrono::Duration::days(10)),
+            model: "llama2".to_string(),
+            prompt_tokens: 10,
+            completion_tokens: 20,
+            total_tokens: 30,
+            tokens_per_second: 42.0,
+            time_to_first_token_ms: Some(120),
+            time_per_output_token_ms: None,
+            hardware: Some(crate::ollama::SessionHardware {
+                energy_joules: 60.0,
+                joules_per_token: Some(2.0),
+                avg_power_watts: 150.0,
+                peak_gpu_memory_mb: 4096,
+                avg_gpu_utilization: 85.0,
+            }),
+            api_flavor: crate::ollama::ApiFlavor::OpenAi,
+            abandoned: false,
+        })
+        .await
+        .unwrap();
+
+        let archiver = ParquetArchiver::new(&archive_dir).unwrap();
+        let cutoff = (now - chrono::Duration::days(7)).date_naive();
+        assert_eq!(archiver.archive_gpu_metrics(&db, cutoff).await.unwrap(), 1);
+        assert_eq!(archiver.archive_llm_sessions(&db, cutoff).await.unwrap(), 1);
+
+        let metrics = history.recent_gpu_metrics(24 * 30).await.unwrap();
+        assert_eq!(metrics.iter().map(|m| m.utilization_gpu).collect::<Vec<_>>(), vec![11, 22]);
+
+        let recent_only = history.recent_gpu_metrics(2).await.unwrap();
+        assert_eq!(recent_only.len(), 1);
+
+        let sessions = history
+            .llm_sessions(now - chrono::Duration::days(30), now)
+            .await
+            .unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].id, "old-session");
+        assert_eq!(sessions[0].time_to_first_token_ms, Some(120));
+        assert_eq!(sessions[0].time_per_output_token_ms, None);
+        assert_eq!(sessions[0].api_flavor, crate::ollama::ApiFlavor::OpenAi);
+        let hardware = sessions[0].hardware.as_ref().unwrap();
+        assert_eq!(hardware.joules_per_token, Some(2.0));
+        assert_eq!(hardware.peak_gpu_memory_mb, 4096);
+    }
+
+    #[tokio::test]
+    async fn test_category_energy_spans_archive_and_database() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Database::new(dir.path().join("gpm.db")).await.unwrap());
+        let archive_dir = dir.path().join("archive");
+        let history = HistoryQuery::new(Arc::clone(&db), &archive_dir).unwrap();
+
+        let process = |category| crate::classifier::ClassifiedProcess {
+            pid: 4242,
+            name: "python3".to_string(),
+            category,
+            gpu_memory_mb: 4096,
+            gpu_utilization: 90,
+            engine_utilization: None,
+            command_line: "python3 train.py".to_string(),
+            exe_path: None,
+            rule: "default".to_string(),
+            confidence: 0.5,
+            evidence: Vec::new(),
+            cgroup: Default::default(),
+            uid: None,
+            user: None,
+            power_watts: 300.0,
+            mig_device: None,
+        };
+        db.insert_process_event(&process(crate::classifier::WorkloadCategory::MlTraining), 60)
+            .await
+            .unwrap();
+        let now = chrono::Utc::now();
+        sqlx::query("UPDATE process_events SET timestamp = ?")
+            .bind(now - chrono::Duration::days(10))
+            .execute(db.pool())
+            .await
+            .unwrap();
+        db.insert_process_event(&process(crate::classifier::WorkloadCategory::LlmInference), 120)
+            .await
+            .unwrap();
+
+        let archiver = ParquetArchiver::new(&archive_dir).unwrap();
+        let cutoff = (now - chrono::Duration::days(7)).date_naive();
+        assert_eq!(archiver.archive_process_events(&db, cutoff).await.unwrap(), 1);
+
+        let samples = history
+            .category_energy(now - chrono::Duration::days(30), chrono::Utc::now())
+            .await
+            .unwrap();
+        let energy: Vec<_> = samples.iter().map(|s| (s.key.as_str(), s.energy_wh)).collect();
+        assert_eq!(energy, vec![("ml_training", 5.0), ("llm_inference", 10.0)]);
+    }
+}
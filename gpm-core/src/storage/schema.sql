@@ -10,12 +10,94 @@ CREATE TABLE IF NOT EXISTS gpu_metrics (
     memory_total BIGINT NOT NULL,
     temperature INTEGER NOT NULL,
     power_usage INTEGER NOT NULL,
+    clock_graphics_mhz INTEGER,
+    clock_sm_mhz INTEGER,
+    clock_memory_mhz INTEGER,
+    fan_speed_percent INTEGER,
+    pcie_tx_kbps INTEGER,
+    pcie_rx_kbps INTEGER,
+    pcie_link_gen INTEGER,
+    pcie_link_width INTEGER,
+    throttle_reasons TEXT,
+    performance_state INTEGER,
+    power_limit_watts INTEGER,
+    ecc_corrected_errors BIGINT,
+    ecc_uncorrected_errors BIGINT,
+    gpu_uuid TEXT,
+    pci_bus_id TEXT,
+    total_energy_mj BIGINT,
+    energy_wh REAL,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP
 );
 
 CREATE INDEX IF NOT EXISTS idx_gpu_metrics_timestamp ON gpu_metrics(timestamp);
 CREATE INDEX IF NOT EXISTS idx_gpu_metrics_gpu_id ON gpu_metrics(gpu_id);
 
+-- MIG devices of a gpu_metrics sample, one row per MIG device
+CREATE TABLE IF NOT EXISTS gpu_mig_metrics (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    metrics_id INTEGER NOT NULL REFERENCES gpu_metrics(id) ON DELETE CASCADE,
+    timestamp DATETIME NOT NULL,
+    gpu_id INTEGER NOT NULL,
+    mig_uuid TEXT NOT NULL,
+    mig_index INTEGER NOT NULL,
+    gpu_instance_id INTEGER NOT NULL,
+    compute_instance_id INTEGER NOT NULL,
+    profile TEXT NOT NULL,
+    multiprocessor_count INTEGER NOT NULL,
+    memory_used BIGINT NOT NULL,
+    memory_total BIGINT NOT NULL,
+    utilization_gpu INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_gpu_mig_metrics_metrics_id ON gpu_mig_metrics(metrics_id);
+CREATE INDEX IF NOT EXISTS idx_gpu_mig_metrics_timestamp ON gpu_mig_metrics(timestamp);
+
+-- Every GPU seen, keyed by UUID so history survives re-enumeration
+CREATE TABLE IF NOT EXISTS gpus (
+    uuid TEXT PRIMARY KEY,
+    gpu_index INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    serial TEXT,
+    pci_bus_id TEXT,
+    vbios_version TEXT,
+    driver_version TEXT,
+    memory_total BIGINT NOT NULL,
+    first_seen DATETIME NOT NULL,
+    last_seen DATETIME NOT NULL
+);
+
+-- GPUs appearing in or disappearing from the device list (hot-plug, reset,
+-- driver reload)
+CREATE TABLE IF NOT EXISTS gpu_device_events (
+    id TEXT PRIMARY KEY,
+    timestamp DATETIME NOT NULL,
+    kind TEXT NOT NULL,
+    gpu_index INTEGER NOT NULL,
+    uuid TEXT,
+    name TEXT NOT NULL,
+    pci_bus_id TEXT,
+    detail TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_gpu_device_events_timestamp ON gpu_device_events(timestamp);
+
+-- Xid errors, ECC errors and state changes reported by the driver
+CREATE TABLE IF NOT EXISTS gpu_events (
+    id TEXT PRIMARY KEY,
+    timestamp DATETIME NOT NULL,
+    kind TEXT NOT NULL,
+    gpu_index INTEGER,
+    uuid TEXT,
+    pci_bus_id TEXT,
+    xid INTEGER,
+    pids TEXT NOT NULL DEFAULT '[]',  -- JSON array
+    source TEXT NOT NULL,
+    message TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_gpu_events_timestamp ON gpu_events(timestamp);
+
 -- LLM sessions table
 CREATE TABLE IF NOT EXISTS llm_sessions (
     id TEXT PRIMARY KEY,
@@ -28,12 +110,43 @@ CREATE TABLE IF NOT EXISTS llm_sessions (
     tokens_per_second REAL NOT NULL DEFAULT 0.0,
     time_to_first_token_ms BIGINT,
     time_per_output_token_ms REAL,
+    energy_joules REAL,
+    joules_per_token REAL,
+    avg_power_watts REAL,
+    peak_gpu_memory_mb BIGINT,
+    avg_gpu_utilization REAL,
+    api_flavor TEXT,
+    abandoned INTEGER NOT NULL DEFAULT 0,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP
 );
 
 CREATE INDEX IF NOT EXISTS idx_llm_sessions_start_time ON llm_sessions(start_time);
 CREATE INDEX IF NOT EXISTS idx_llm_sessions_model ON llm_sessions(model);
 
+-- Every call through the Ollama proxy
+CREATE TABLE IF NOT EXISTS proxy_requests (
+    id TEXT PRIMARY KEY,
+    timestamp DATETIME NOT NULL,
+    method TEXT NOT NULL,
+    endpoint TEXT NOT NULL,
+    model TEXT,
+    status INTEGER NOT NULL,
+    latency_ms BIGINT NOT NULL,
+    request_bytes BIGINT NOT NULL DEFAULT 0,
+    response_bytes BIGINT NOT NULL DEFAULT 0,
+    client_addr TEXT,
+    session_id TEXT,
+    input_count BIGINT,
+    prompt_tokens BIGINT,
+    pull_status TEXT,
+    pull_completed_bytes BIGINT,
+    pull_total_bytes BIGINT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_proxy_requests_timestamp ON proxy_requests(timestamp);
+CREATE INDEX IF NOT EXISTS idx_proxy_requests_endpoint ON proxy_requests(endpoint);
+
 -- Process events table
 CREATE TABLE IF NOT EXISTS process_events (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -46,6 +159,22 @@ CREATE TABLE IF NOT EXISTS process_events (
     command_line TEXT,
     exe_path TEXT,
     duration_secs INTEGER DEFAULT 0,
+    rule TEXT,
+    confidence REAL,
+    evidence TEXT,
+    container_id TEXT,
+    container_name TEXT,
+    container_image TEXT,
+    container_runtime TEXT,
+    systemd_unit TEXT,
+    systemd_slice TEXT,
+    uid INTEGER,
+    user TEXT,
+    power_watts REAL,
+    gpu_memory_utilization INTEGER,
+    encoder_utilization INTEGER,
+    decoder_utilization INTEGER,
+    mig_device TEXT,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP
 );
 
@@ -53,6 +182,36 @@ CREATE INDEX IF NOT EXISTS idx_process_events_timestamp ON process_events(timest
 CREATE INDEX IF NOT EXISTS idx_process_events_category ON process_events(category);
 CREATE INDEX IF NOT EXISTS idx_process_events_pid ON process_events(pid);
 
+-- GPU process sessions (one row per process lifetime)
+CREATE TABLE IF NOT EXISTS gpu_process_sessions (
+    id TEXT PRIMARY KEY,
+    pid INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    category TEXT NOT NULL,
+    command_line TEXT,
+    first_seen DATETIME NOT NULL,
+    last_seen DATETIME NOT NULL,
+    end_time DATETIME,
+    duration_secs BIGINT NOT NULL DEFAULT 0,
+    peak_gpu_memory_mb BIGINT NOT NULL DEFAULT 0,
+    avg_gpu_utilization REAL NOT NULL DEFAULT 0.0,
+    sample_count BIGINT NOT NULL DEFAULT 0,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_gpu_process_sessions_first_seen ON gpu_process_sessions(first_seen);
+CREATE INDEX IF NOT EXISTS idx_gpu_process_sessions_category ON gpu_process_sessions(category);
+
+-- Manual classification overrides
+CREATE TABLE IF NOT EXISTS classification_overrides (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    pattern TEXT NOT NULL,
+    category TEXT NOT NULL,
+    note TEXT,
+    created_at DATETIME NOT NULL
+);
+
 -- Weekly summaries table
 CREATE TABLE IF NOT EXISTS weekly_summaries (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -82,3 +241,24 @@ CREATE TABLE IF NOT EXISTS archive_log (
 );
 
 CREATE INDEX IF NOT EXISTS idx_archive_log_date ON archive_log(archive_date);
+
+-- Alert history table
+CREATE TABLE IF NOT EXISTS alerts (
+    id TEXT PRIMARY KEY,
+    rule TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    state TEXT NOT NULL,
+    gpu_id INTEGER NOT NULL,
+    gpu_name TEXT NOT NULL,
+    threshold REAL NOT NULL,
+    value REAL NOT NULL,
+    peak_value REAL NOT NULL,
+    message TEXT NOT NULL,
+    started_at DATETIME NOT NULL,
+    resolved_at DATETIME,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_alerts_started_at ON alerts(started_at);
+CREATE INDEX IF NOT EXISTS idx_alerts_state ON alerts(state);
@@ -1,6 +1,7 @@
 use crate::classifier::ClassifiedProcess;
-use crate::gpu::GpuMetrics;
+use crate::gpu::{GpuEvent, GpuMetrics};
 use crate::ollama::LlmSession;
+use crate::tracker::ProcessEvent;
 use opentelemetry::{metrics::*, KeyValue};
 use opentelemetry_sdk::metrics::SdkMeterProvider;
 use std::sync::Arc;
@@ -12,15 +13,28 @@ pub struct MetricsCollector {
     gpu_memory_used: Gauge<u64>,
     gpu_temperature: Gauge<f64>,
     gpu_power: Gauge<f64>,
+    gpu_clock: Gauge<u64>,
+    gpu_fan_speed: Gauge<f64>,
+    gpu_pcie_throughput: Gauge<u64>,
+    gpu_pcie_link_gen: Gauge<u64>,
+    gpu_pcie_link_width: Gauge<u64>,
+    gpu_throttle_reason: Gauge<u64>,
+    gpu_performance_state: Gauge<u64>,
+    gpu_power_limit: Gauge<f64>,
+    gpu_ecc_errors: Gauge<u64>,
+    gpu_energy: Counter<f64>,
+    gpu_events: Counter<u64>,
 
     // LLM metrics
     llm_tokens_per_second: Histogram<f64>,
     llm_time_to_first_token: Histogram<f64>,
     llm_total_tokens: Counter<u64>,
+    llm_joules_per_token: Histogram<f64>,
 
     // Process metrics
     process_gpu_duration: Counter<f64>,
     process_gpu_memory: Gauge<u64>,
+    process_gpu_utilization: Gauge<f64>,
     process_count: Gauge<u64>,
 }
 
@@ -52,6 +66,66 @@ impl MetricsCollector {
             .with_unit("W")
             .build();
 
+        let gpu_energy = meter
+            .f64_counter("gpu.energy")
+            .with_description("GPU energy consumption")
+            .with_unit("Wh")
+            .build();
+
+        let gpu_events = meter
+            .u64_counter("gpu.events")
+            .with_description("Xid errors, ECC errors and state changes reported by the driver")
+            .build();
+
+        let gpu_clock = meter
+            .u64_gauge("gpu.clock.mhz")
+            .with_description("GPU clock speed in MHz")
+            .with_unit("MHz")
+            .build();
+
+        let gpu_fan_speed = meter
+            .f64_gauge("gpu.fan.speed.percent")
+            .with_description("GPU fan speed percentage")
+            .with_unit("%")
+            .build();
+
+        let gpu_pcie_throughput = meter
+            .u64_gauge("gpu.pcie.throughput.bytes")
+            .with_description("GPU PCIe throughput in bytes per second")
+            .with_unit("By/s")
+            .build();
+
+        let gpu_pcie_link_gen = meter
+            .u64_gauge("gpu.pcie.link.generation")
+            .with_description("Current GPU PCIe link generation")
+            .build();
+
+        let gpu_pcie_link_width = meter
+            .u64_gauge("gpu.pcie.link.width")
+            .with_description("Current GPU PCIe link width in lanes")
+            .build();
+
+        let gpu_throttle_reason = meter
+            .u64_gauge("gpu.throttle.reason")
+            .with_description("1 if the clock throttle reason is active")
+            .build();
+
+        let gpu_performance_state = meter
+            .u64_gauge("gpu.performance_state")
+            .with_description("GPU performance state (0 = P0, maximum)")
+            .build();
+
+        let gpu_power_limit = meter
+            .f64_gauge("gpu.power.limit.watts")
+            .with_description("Enforced GPU power limit in watts")
+            .with_unit("W")
+            .build();
+
+        let gpu_ecc_errors = meter
+            .u64_gauge("gpu.ecc.errors")
+            .with_description("Volatile GPU ECC error count")
+            .build();
+
         let llm_tokens_per_second = meter
             .f64_histogram("llm.tokens_per_second")
             .with_description("LLM generation tokens per second")
@@ -70,6 +144,12 @@ impl MetricsCollector {
             .with_unit("tokens")
             .build();
 
+        let llm_joules_per_token = meter
+            .f64_histogram("llm.joules_per_token")
+            .with_description("GPU energy per token")
+            .with_unit("J")
+            .build();
+
         let process_gpu_duration = meter
             .f64_counter("process.gpu_duration.seconds")
             .with_description("GPU usage duration by process category")
@@ -82,6 +162,12 @@ impl MetricsCollector {
             .with_unit("bytes")
             .build();
 
+        let process_gpu_utilization = meter
+            .f64_gauge("process.gpu_utilization.percent")
+            .with_description("Summed per-process GPU (SM) utilization by category")
+            .with_unit("%")
+            .build();
+
         let process_count = meter
             .u64_gauge("process.count")
             .with_description("Number of processes by category")
@@ -93,25 +179,106 @@ impl MetricsCollector {
             gpu_memory_used,
             gpu_temperature,
             gpu_power,
+            gpu_clock,
+            gpu_fan_speed,
+            gpu_pcie_throughput,
+            gpu_pcie_link_gen,
+            gpu_pcie_link_width,
+            gpu_throttle_reason,
+            gpu_performance_state,
+            gpu_power_limit,
+            gpu_ecc_errors,
+            gpu_energy,
+            gpu_events,
             llm_tokens_per_second,
             llm_time_to_first_token,
             llm_total_tokens,
+            llm_joules_per_token,
             process_gpu_duration,
             process_gpu_memory,
+            process_gpu_utilization,
             process_count,
         }
     }
 
     pub fn record_gpu_metrics(&self, metrics: &GpuMetrics) {
-        let labels = &[
+        let mut labels = vec![
             KeyValue::new("gpu_id", metrics.gpu_id.to_string()),
             KeyValue::new("gpu_name", metrics.name.clone()),
         ];
+        if let Some(uuid) = &metrics.identity.uuid {
+            labels.push(KeyValue::new("gpu_uuid", uuid.clone()));
+        }
+        let labels = labels.as_slice();
 
         self.gpu_utilization.record(metrics.utilization_gpu as f64, labels);
         self.gpu_memory_used.record(metrics.memory_used, labels);
         self.gpu_temperature.record(metrics.temperature as f64, labels);
         self.gpu_power.record(metrics.power_usage as f64, labels);
+        if let Some(wh) = metrics.energy_wh {
+            self.gpu_energy.add(wh, labels);
+        }
+
+        let t = &metrics.telemetry;
+        let with = |key: &'static str, value: &'static str| {
+            let mut l = labels.to_vec();
+            l.push(KeyValue::new(key, value));
+            l
+        };
+
+        for (clock, value) in [
+            ("graphics", t.clock_graphics_mhz),
+            ("sm", t.clock_sm_mhz),
+            ("memory", t.clock_memory_mhz),
+        ] {
+            if let Some(mhz) = value {
+                self.gpu_clock.record(mhz as u64, &with("clock", clock));
+            }
+        }
+
+        for (direction, value) in [("tx", t.pcie_tx_kbps), ("rx", t.pcie_rx_kbps)] {
+            if let Some(kb) = value {
+                self.gpu_pcie_throughput.record(kb as u64 * 1024, &with("direction", direction));
+            }
+        }
+
+        for (kind, value) in [("corrected", t.ecc_corrected_errors), ("uncorrected", t.ecc_uncorrected_errors)] {
+            if let Some(count) = value {
+                self.gpu_ecc_errors.record(count, &with("type", kind));
+            }
+        }
+
+        for (_, reason) in crate::gpu::nvml::THROTTLE_REASONS {
+            let active = t.throttle_reasons.iter().any(|r| r == reason);
+            self.gpu_throttle_reason.record(active as u64, &with("reason", reason));
+        }
+
+        if let Some(fan) = t.fan_speed_percent {
+            self.gpu_fan_speed.record(fan as f64, labels);
+        }
+        if let Some(link_gen) = t.pcie_link_gen {
+            self.gpu_pcie_link_gen.record(link_gen as u64, labels);
+        }
+        if let Some(width) = t.pcie_link_width {
+            self.gpu_pcie_link_width.record(width as u64, labels);
+        }
+        if let Some(pstate) = t.performance_state {
+            self.gpu_performance_state.record(pstate as u64, labels);
+        }
+        if let Some(limit) = t.power_limit_watts {
+            self.gpu_power_limit.record(limit as f64, labels);
+        }
+    }
+
+    pub fn record_gpu_event(&self, event: &GpuEvent) {
+        let mut labels = vec![KeyValue::new("kind", event.kind.as_str())];
+        if let Some(gpu_id) = event.gpu_id {
+            labels.push(KeyValue::new("gpu_id", gpu_id.to_string()));
+        }
+        if let Some(xid) = event.xid {
+            labels.push(KeyValue::new("xid", xid as i64));
+        }
+        self.gpu_events.add(1, &labels);
     }
 
     pub fn record_llm_session(&self, session: &LlmSession) {
@@ -124,6 +291,10 @@ impl MetricsCollector {
         }
 
         self.llm_total_tokens.add(session.total_tokens, labels);
+
+        if let Some(jpt) = session.hardware.as_ref().and_then(|h| h.joules_per_token) {
+            self.llm_joules_per_token.record(jpt, labels);
+        }
     }
 
     pub fn record_process_metrics(&self, processes: &[ClassifiedProcess]) {
@@ -131,18 +302,34 @@ impl MetricsCollector {
 
         let mut category_counts: HashMap<String, u64> = HashMap::new();
         let mut category_memory: HashMap<String, u64> = HashMap::new();
+        let mut category_utilization: HashMap<String, f64> = HashMap::new();
 
         for proc in processes {
             let cat_str = proc.category.as_str().to_string();
             *category_counts.entry(cat_str.clone()).or_insert(0) += 1;
             *category_memory.entry(cat_str.clone()).or_insert(0) += proc.gpu_memory_mb;
+            *category_utilization.entry(cat_str.clone()).or_insert(0.0) += proc.gpu_utilization as f64;
 
-            let labels = vec![
+            let mut labels = vec![
                 KeyValue::new("category", proc.category.as_str().to_string()),
                 KeyValue::new("process_name", proc.name.clone()),
                 KeyValue::new("pid", proc.pid.to_string()),
             ];
 
+            let cgroup = &proc.cgroup;
+            for (key, value) in [
+                ("user", &proc.user),
+                ("container_id", &cgroup.container_id),
+                ("container_name", &cgroup.container_name),
+                ("container_image", &cgroup.container_image),
+                ("systemd_unit", &cgroup.systemd_unit),
+                ("mig_device", &proc.mig_device),
+            ] {
+                if let Some(value) = value {
+                    labels.push(KeyValue::new(key, value.clone()));
+                }
+            }
+
             self.process_gpu_memory.record(
                 proc.gpu_memory_mb * 1024 * 1024,
                 &labels,
@@ -158,5 +345,19 @@ impl MetricsCollector {
             let labels = vec![KeyValue::new("category", category)];
             self.process_gpu_memory.record(memory_mb * 1024 * 1024, &labels);
         }
+
+        for (category, utilization) in category_utilization {
+            let labels = vec![KeyValue::new("category", category)];
+            self.process_gpu_utilization.record(utilization, &labels);
+        }
+    }
+
+    pub fn record_process_gpu_time(&self, event: &ProcessEvent) {
+        if event.elapsed_secs == 0 {
+            return;
+        }
+
+        let labels = vec![KeyValue::new("category", event.session.category.as_str().to_string())];
+        self.process_gpu_duration.add(event.elapsed_secs as f64, &labels);
     }
 }
@@ -1,6 +1,6 @@
 use crate::error::Result;
 use axum::{routing::get, Router};
-use prometheus::{Encoder, GaugeVec, HistogramVec, Opts, Registry, TextEncoder};
+use prometheus::{CounterVec, Encoder, GaugeVec, HistogramVec, Opts, Registry, TextEncoder};
 use std::sync::Arc;
 use tracing::info;
 
@@ -13,15 +13,35 @@ pub struct PrometheusExporter {
     gpu_memory_total: GaugeVec,
     gpu_temperature: GaugeVec,
     gpu_power: GaugeVec,
+    gpu_clock: GaugeVec,
+    gpu_fan_speed: GaugeVec,
+    gpu_pcie_throughput: GaugeVec,
+    gpu_pcie_link_gen: GaugeVec,
+    gpu_pcie_link_width: GaugeVec,
+    gpu_throttle_reason: GaugeVec,
+    gpu_performance_state: GaugeVec,
+    gpu_power_limit: GaugeVec,
+    gpu_ecc_errors: GaugeVec,
+    gpu_info: GaugeVec,
+    gpu_energy: CounterVec,
+    gpu_device_events: CounterVec,
+    gpu_events: CounterVec,
+    mig_memory_used: GaugeVec,
+    mig_memory_total: GaugeVec,
+    mig_utilization: GaugeVec,
 
     // LLM metrics
     llm_tokens_per_second: HistogramVec,
     llm_time_to_first_token: HistogramVec,
     llm_session_count: GaugeVec,
+    llm_joules_per_token: HistogramVec,
+    llm_energy: CounterVec,
 
     // Process metrics
     process_count: GaugeVec,
     process_gpu_memory: GaugeVec,
+    process_gpu_utilization: GaugeVec,
+    gpu_process_memory: GaugeVec,
 }
 
 impl PrometheusExporter {
@@ -53,6 +73,88 @@ impl PrometheusExporter {
             &["gpu_id", "gpu_name"],
         )?;
 
+        let gpu_clock = GaugeVec::new(
+            Opts::new("gpm_gpu_clock_mhz", "GPU clock speed in MHz"),
+            &["gpu_id", "gpu_name", "clock"],
+        )?;
+
+        let gpu_fan_speed = GaugeVec::new(
+            Opts::new("gpm_gpu_fan_speed_percent", "GPU fan speed percentage"),
+            &["gpu_id", "gpu_name"],
+        )?;
+
+        let gpu_pcie_throughput = GaugeVec::new(
+            Opts::new("gpm_gpu_pcie_throughput_bytes_per_second", "GPU PCIe throughput in bytes per second"),
+            &["gpu_id", "gpu_name", "direction"],
+        )?;
+
+        let gpu_pcie_link_gen = GaugeVec::new(
+            Opts::new("gpm_gpu_pcie_link_generation", "Current GPU PCIe link generation"),
+            &["gpu_id", "gpu_name"],
+        )?;
+
+        let gpu_pcie_link_width = GaugeVec::new(
+            Opts::new("gpm_gpu_pcie_link_width", "Current GPU PCIe link width (lanes)"),
+            &["gpu_id", "gpu_name"],
+        )?;
+
+        let gpu_throttle_reason = GaugeVec::new(
+            Opts::new("gpm_gpu_throttle_reason", "1 if the clock throttle reason is active"),
+            &["gpu_id", "gpu_name", "reason"],
+        )?;
+
+        let gpu_performance_state = GaugeVec::new(
+            Opts::new("gpm_gpu_performance_state", "GPU performance state (0 = P0, maximum)"),
+            &["gpu_id", "gpu_name"],
+        )?;
+
+        let gpu_power_limit = GaugeVec::new(
+            Opts::new("gpm_gpu_power_limit_watts", "Enforced GPU power limit in watts"),
+            &["gpu_id", "gpu_name"],
+        )?;
+
+        let gpu_ecc_errors = GaugeVec::new(
+            Opts::new("gpm_gpu_ecc_errors", "Volatile GPU ECC error count"),
+            &["gpu_id", "gpu_name", "type"],
+        )?;
+
+        let gpu_energy = CounterVec::new(
+            Opts::new("gpm_gpu_energy_wh_total", "GPU energy consumption in watt-hours"),
+            &["gpu_id", "gpu_name"],
+        )?;
+
+        let gpu_device_events = CounterVec::new(
+            Opts::new("gpm_gpu_device_events_total", "GPUs added to or removed from the device list"),
+            &["kind"],
+        )?;
+
+        let gpu_events = CounterVec::new(
+            Opts::new("gpm_gpu_events_total", "Xid errors, ECC errors and state changes reported by the driver"),
+            &["gpu_id", "kind", "xid"],
+        )?;
+
+        let gpu_info = GaugeVec::new(
+            Opts::new("gpm_gpu_info", "GPU identity; always 1, join on gpu_id to follow a card by UUID"),
+            &["gpu_id", "gpu_name", "uuid", "pci_bus_id", "serial", "vbios_version", "driver_version"],
+        )?;
+
+        let mig_labels = &["gpu_id", "gpu_name", "mig_uuid", "gpu_instance_id", "compute_instance_id", "profile"];
+
+        let mig_memory_used = GaugeVec::new(
+            Opts::new("gpm_mig_memory_used_bytes", "MIG device memory used in bytes"),
+            mig_labels,
+        )?;
+
+        let mig_memory_total = GaugeVec::new(
+            Opts::new("gpm_mig_memory_total_bytes", "MIG device memory slice in bytes"),
+            mig_labels,
+        )?;
+
+        let mig_utilization = GaugeVec::new(
+            Opts::new("gpm_mig_utilization_percent", "MIG device utilization percentage, where the driver reports it"),
+            mig_labels,
+        )?;
+
         let llm_tokens_per_second = HistogramVec::new(
             prometheus::HistogramOpts::new(
                 "gpm_llm_tokens_per_second",
@@ -76,6 +178,20 @@ impl PrometheusExporter {
             &["model"],
         )?;
 
+        let llm_joules_per_token = HistogramVec::new(
+            prometheus::HistogramOpts::new(
+                "gpm_llm_joules_per_token",
+                "GPU energy per generated or prompt token in joules",
+            )
+            .buckets(vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]),
+            &["model"],
+        )?;
+
+        let llm_energy = CounterVec::new(
+            Opts::new("gpm_llm_energy_joules_total", "GPU energy used by LLM sessions in joules"),
+            &["model"],
+        )?;
+
         let process_count = GaugeVec::new(
             Opts::new("gpm_process_count", "Number of GPU processes by category"),
             &["category"],
@@ -89,16 +205,62 @@ impl PrometheusExporter {
             &["category"],
         )?;
 
+        let process_gpu_utilization = GaugeVec::new(
+            Opts::new(
+                "gpm_process_gpu_utilization_percent",
+                "Summed per-process GPU (SM) utilization by process category",
+            ),
+            &["category"],
+        )?;
+
+        let gpu_process_memory = GaugeVec::new(
+            Opts::new(
+                "gpm_gpu_process_memory_bytes",
+                "GPU memory used by a single process, with its container or systemd unit",
+            ),
+            &[
+                "pid",
+                "process_name",
+                "category",
+                "user",
+                "container_id",
+                "container_name",
+                "container_image",
+                "systemd_unit",
+                "mig_device",
+            ],
+        )?;
+
         registry.register(Box::new(gpu_utilization.clone()))?;
         registry.register(Box::new(gpu_memory_used.clone()))?;
         registry.register(Box::new(gpu_memory_total.clone()))?;
         registry.register(Box::new(gpu_temperature.clone()))?;
         registry.register(Box::new(gpu_power.clone()))?;
+        registry.register(Box::new(gpu_clock.clone()))?;
+        registry.register(Box::new(gpu_fan_speed.clone()))?;
+        registry.register(Box::new(gpu_pcie_throughput.clone()))?;
+        registry.register(Box::new(gpu_pcie_link_gen.clone()))?;
+        registry.register(Box::new(gpu_pcie_link_width.clone()))?;
+        registry.register(Box::new(gpu_throttle_reason.clone()))?;
+        registry.register(Box::new(gpu_performance_state.clone()))?;
+        registry.register(Box::new(gpu_power_limit.clone()))?;
+        registry.register(Box::new(gpu_ecc_errors.clone()))?;
+        registry.register(Box::new(gpu_info.clone()))?;
+        registry.register(Box::new(gpu_energy.clone()))?;
+        registry.register(Box::new(gpu_device_events.clone()))?;
+        registry.register(Box::new(gpu_events.clone()))?;
+        registry.register(Box::new(mig_memory_used.clone()))?;
+        registry.register(Box::new(mig_memory_total.clone()))?;
+        registry.register(Box::new(mig_utilization.clone()))?;
         registry.register(Box::new(llm_tokens_per_second.clone()))?;
         registry.register(Box::new(llm_time_to_first_token.clone()))?;
         registry.register(Box::new(llm_session_count.clone()))?;
+        registry.register(Box::new(llm_joules_per_token.clone()))?;
+        registry.register(Box::new(llm_energy.clone()))?;
         registry.register(Box::new(process_count.clone()))?;
         registry.register(Box::new(process_gpu_memory.clone()))?;
+        registry.register(Box::new(process_gpu_utilization.clone()))?;
+        registry.register(Box::new(gpu_process_memory.clone()))?;
 
         Ok(Self {
             registry,
@@ -107,11 +269,31 @@ impl PrometheusExporter {
             gpu_memory_total,
             gpu_temperature,
             gpu_power,
+            gpu_clock,
+            gpu_fan_speed,
+            gpu_pcie_throughput,
+            gpu_pcie_link_gen,
+            gpu_pcie_link_width,
+            gpu_throttle_reason,
+            gpu_performance_state,
+            gpu_power_limit,
+            gpu_ecc_errors,
+            gpu_info,
+            gpu_energy,
+            gpu_device_events,
+            gpu_events,
+            mig_memory_used,
+            mig_memory_total,
+            mig_utilization,
             llm_tokens_per_second,
             llm_time_to_first_token,
             llm_session_count,
+            llm_joules_per_token,
+            llm_energy,
             process_count,
             process_gpu_memory,
+            process_gpu_utilization,
+            gpu_process_memory,
         })
     }
 
@@ -138,6 +320,105 @@ impl PrometheusExporter {
         self.gpu_power
             .with_label_values(labels)
             .set(metrics.power_usage as f64);
+
+        if let Some(wh) = metrics.energy_wh {
+            self.gpu_energy.with_label_values(labels).inc_by(wh);
+        }
+
+        self.update_gpu_telemetry(&gpu_id_str, &metrics.name, &metrics.telemetry);
+
+        for mig in &metrics.mig_devices {
+            let gpu_instance_id = mig.gpu_instance_id.to_string();
+            let compute_instance_id = mig.compute_instance_id.to_string();
+            let mig_labels = &[
+                gpu_id_str.as_str(),
+                metrics.name.as_str(),
+                mig.uuid.as_str(),
+                gpu_instance_id.as_str(),
+                compute_instance_id.as_str(),
+                mig.profile.as_str(),
+            ];
+
+            self.mig_memory_used.with_label_values(mig_labels).set(mig.memory_used as f64);
+            self.mig_memory_total.with_label_values(mig_labels).set(mig.memory_total as f64);
+            if let Some(utilization) = mig.utilization_gpu {
+                self.mig_utilization.with_label_values(mig_labels).set(utilization as f64);
+            }
+        }
+
+        let identity = &metrics.identity;
+        if let Some(uuid) = &identity.uuid {
+            let text = |v: &Option<String>| v.clone().unwrap_or_default();
+            self.gpu_info
+                .with_label_values(&[
+                    gpu_id_str.as_str(),
+                    metrics.name.as_str(),
+                    uuid.as_str(),
+                    &text(&identity.pci_bus_id),
+                    &text(&identity.serial),
+                    &text(&identity.vbios_version),
+                    &text(&identity.driver_version),
+                ])
+                .set(1.0);
+        }
+    }
+
+    /// Readings the device does not report are left unset rather than
+    /// exported as zero.
+    fn update_gpu_telemetry(&self, gpu_id: &str, gpu_name: &str, t: &crate::gpu::GpuTelemetry) {
+        let labels = &[gpu_id, gpu_name];
+        let set = |gauge: &GaugeVec, labels: &[&str], value: Option<f64>| {
+            if let Some(value) = value {
+                gauge.with_label_values(labels).set(value);
+            }
+        };
+
+        for (clock, value) in [
+            ("graphics", t.clock_graphics_mhz),
+            ("sm", t.clock_sm_mhz),
+            ("memory", t.clock_memory_mhz),
+        ] {
+            set(&self.gpu_clock, &[gpu_id, gpu_name, clock], value.map(f64::from));
+        }
+
+        for (direction, value) in [("tx", t.pcie_tx_kbps), ("rx", t.pcie_rx_kbps)] {
+            set(
+                &self.gpu_pcie_throughput,
+                &[gpu_id, gpu_name, direction],
+                value.map(|kb| kb as f64 * 1024.0),
+            );
+        }
+
+        for (kind, value) in [("corrected", t.ecc_corrected_errors), ("uncorrected", t.ecc_uncorrected_errors)] {
+            set(&self.gpu_ecc_errors, &[gpu_id, gpu_name, kind], value.map(|v| v as f64));
+        }
+
+        set(&self.gpu_fan_speed, labels, t.fan_speed_percent.map(f64::from));
+        set(&self.gpu_pcie_link_gen, labels, t.pcie_link_gen.map(f64::from));
+        set(&self.gpu_pcie_link_width, labels, t.pcie_link_width.map(f64::from));
+        set(&self.gpu_performance_state, labels, t.performance_state.map(f64::from));
+        set(&self.gpu_power_limit, labels, t.power_limit_watts.map(f64::from));
+
+        for (_, reason) in crate::gpu::nvml::THROTTLE_REASONS {
+            let active = t.throttle_reasons.iter().any(|r| r == reason);
+            self.gpu_throttle_reason
+                .with_label_values(&[gpu_id, gpu_name, reason])
+                .set(if active { 1.0 } else { 0.0 });
+        }
+    }
+
+    pub fn record_device_event(&self, event: &crate::gpu::DeviceEvent) {
+        self.gpu_device_events
+            .with_label_values(&[event.kind.as_str()])
+            .inc();
+    }
+
+    pub fn record_gpu_event(&self, event: &crate::gpu::GpuEvent) {
+        let gpu_id = event.gpu_id.map(|id| id.to_string()).unwrap_or_default();
+        let xid = event.xid.map(|xid| xid.to_string()).unwrap_or_default();
+        self.gpu_events
+            .with_label_values(&[gpu_id.as_str(), event.kind.as_str(), xid.as_str()])
+            .inc();
     }
 
     pub fn record_llm_session(&self, session: &crate::ollama::LlmSession) {
@@ -154,6 +435,18 @@ impl PrometheusExporter {
         self.llm_session_count
             .with_label_values(&[&session.model])
             .inc();
+
+        if let Some(hardware) = &session.hardware {
+            self.llm_energy
+                .with_label_values(&[&session.model])
+                .inc_by(hardware.energy_joules);
+
+            if let Some(jpt) = hardware.joules_per_token {
+                self.llm_joules_per_token
+                    .with_label_values(&[&session.model])
+                    .observe(jpt);
+            }
+        }
     }
 
     pub fn update_process_metrics(&self, processes: &[crate::classifier::ClassifiedProcess]) {
@@ -161,12 +454,33 @@ impl PrometheusExporter {
 
         let mut category_counts: HashMap<&str, f64> = HashMap::new();
         let mut category_memory: HashMap<&str, f64> = HashMap::new();
+        let mut category_utilization: HashMap<&str, f64> = HashMap::new();
+
+        // Drop series for processes that have exited
+        self.gpu_process_memory.reset();
 
         for proc in processes {
             let category = proc.category.as_str();
             *category_counts.entry(category).or_insert(0.0) += 1.0;
             *category_memory.entry(category).or_insert(0.0) +=
                 (proc.gpu_memory_mb * 1024 * 1024) as f64;
+            *category_utilization.entry(category).or_insert(0.0) += proc.gpu_utilization as f64;
+
+            let pid = proc.pid.to_string();
+            let cgroup = &proc.cgroup;
+            self.gpu_process_memory
+                .with_label_values(&[
+                    pid.as_str(),
+                    proc.name.as_str(),
+                    category,
+                    proc.user.as_deref().unwrap_or(""),
+                    cgroup.container_id.as_deref().unwrap_or(""),
+                    cgroup.container_name.as_deref().unwrap_or(""),
+                    cgroup.container_image.as_deref().unwrap_or(""),
+                    cgroup.systemd_unit.as_deref().unwrap_or(""),
+                    proc.mig_device.as_deref().unwrap_or(""),
+                ])
+                .set((proc.gpu_memory_mb * 1024 * 1024) as f64);
         }
 
         for (category, count) in category_counts {
@@ -178,6 +492,12 @@ impl PrometheusExporter {
                 .with_label_values(&[category])
                 .set(memory);
         }
+
+        for (category, utilization) in category_utilization {
+            self.process_gpu_utilization
+                .with_label_values(&[category])
+                .set(utilization);
+        }
     }
 
     pub fn render_metrics(&self) -> String {
@@ -0,0 +1,228 @@
+use crate::classifier::{ClassifiedProcess, WorkloadCategory};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::debug;
+
+/// Gaps between observations longer than this (suspend, stalled collector)
+/// are not counted as GPU time.
+pub(crate) const MAX_SAMPLE_GAP_SECS: i64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessEventKind {
+    Start,
+    Update,
+    End,
+}
+
+/// One continuous run of a process on the GPU, from the first poll it was
+/// seen in to the last.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessSession {
+    pub id: String,
+    pub pid: u32,
+    pub name: String,
+    pub category: WorkloadCategory,
+    pub command_line: String,
+    pub first_seen: chrono::DateTime<chrono::Utc>,
+    pub last_seen: chrono::DateTime<chrono::Utc>,
+    pub end_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub duration_secs: u64,
+    pub peak_gpu_memory_mb: u64,
+    pub avg_gpu_utilization: f64,
+    pub sample_count: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessEvent {
+    pub kind: ProcessEventKind,
+    pub session: ProcessSession,
+    /// GPU seconds attributed to this observation (0 for `End`).
+    pub elapsed_secs: u64,
+}
+
+/// Keeps per-PID state across polls and turns the flat per-poll process list
+/// into start/update/end events with real lifetimes.
+///
+/// A session is keyed by PID and process name, so a recycled PID running a
+/// different binary starts a new session.
+#[derive(Default)]
+pub struct ProcessTracker {
+    sessions: HashMap<u32, ProcessSession>,
+}
+
+impl ProcessTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn observe(
+        &mut self,
+        processes: &[ClassifiedProcess],
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Vec<ProcessEvent> {
+        let mut events = Vec::new();
+        let mut seen = Vec::with_capacity(processes.len());
+
+        for process in processes {
+            seen.push(process.pid);
+
+            if let Some(session) = self.sessions.get(&process.pid) {
+                if session.name != process.name {
+                    let session = self.sessions.remove(&process.pid).unwrap();
+                    events.push(Self::end(session));
+                }
+            }
+
+            match self.sessions.get_mut(&process.pid) {
+                Some(session) => {
+                    let gap = (now - session.last_seen).num_seconds();
+                    let elapsed = if (0..=MAX_SAMPLE_GAP_SECS).contains(&gap) { gap as u64 } else { 0 };
+
+                    session.last_seen = now;
+                    session.duration_secs += elapsed;
+                    session.category = process.category;
+                    session.peak_gpu_memory_mb = session.peak_gpu_memory_mb.max(process.gpu_memory_mb);
+                    session.avg_gpu_utilization += (process.gpu_utilization as f64 - session.avg_gpu_utilization)
+                        / (session.sample_count + 1) as f64;
+                    session.sample_count += 1;
+
+                    events.push(ProcessEvent {
+                        kind: ProcessEventKind::Update,
+                        session: session.clone(),
+                        elapsed_secs: elapsed,
+                    });
+                }
+                None => {
+                    let session = ProcessSession {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        pid: process.pid,
+                        name: process.name.clone(),
+                        category: process.category,
+                        command_line: process.command_line.clone(),
+                        first_seen: now,
+                        last_seen: now,
+                        end_time: None,
+                        duration_secs: 0,
+                        peak_gpu_memory_mb: process.gpu_memory_mb,
+                        avg_gpu_utilization: process.gpu_utilization as f64,
+                        sample_count: 1,
+                    };
+
+                    debug!("Process session started: pid={} name={}", session.pid, session.name);
+
+                    events.push(ProcessEvent {
+                        kind: ProcessEventKind::Start,
+                        session: session.clone(),
+                        elapsed_secs: 0,
+                    });
+                    self.sessions.insert(process.pid, session);
+                }
+            }
+        }
+
+        let gone: Vec<u32> = self
+            .sessions
+            .keys()
+            .filter(|pid| !seen.contains(pid))
+            .copied()
+            .collect();
+
+        for pid in gone {
+            if let Some(session) = self.sessions.remove(&pid) {
+                events.push(Self::end(session));
+            }
+        }
+
+        events
+    }
+
+    fn end(mut session: ProcessSession) -> ProcessEvent {
+        session.end_time = Some(session.last_seen);
+
+        debug!(
+            "Process session ended: pid={} name={} duration={}s",
+            session.pid, session.name, session.duration_secs
+        );
+
+        ProcessEvent {
+            kind: ProcessEventKind::End,
+            session,
+            elapsed_secs: 0,
+        }
+    }
+
+    pub fn active_sessions(&self) -> Vec<ProcessSession> {
+        self.sessions.values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, name: &str, gpu_memory_mb: u64, gpu_utilization: u32) -> ClassifiedProcess {
+        ClassifiedProcess {
+            pid,
+            name: name.to_string(),
+            category: WorkloadCategory::MlTraining,
+            gpu_memory_mb,
+            gpu_utilization,
+            engine_utilization: None,
+            command_line: format!("{} train.py", name),
+            exe_path: None,
+            rule: "python_ml_training".to_string(),
+            confidence: 0.7,
+            evidence: Vec::new(),
+            cgroup: Default::default(),
+            uid: Some(1000),
+            user: Some("alice".to_string()),
+            power_watts: 120.0,
+            mig_device: None,
+        }
+    }
+
+    fn at(seconds: i64) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    #[test]
+    fn test_session_lifetime() {
+        let mut tracker = ProcessTracker::new();
+
+        let events = tracker.observe(&[process(42, "python3", 1000, 50)], at(0));
+        assert_eq!(events[0].kind, ProcessEventKind::Start);
+
+        let events = tracker.observe(&[process(42, "python3", 3000, 100)], at(2));
+        assert_eq!(events[0].kind, ProcessEventKind::Update);
+        assert_eq!(events[0].elapsed_secs, 2);
+
+        tracker.observe(&[process(42, "python3", 2000, 90)], at(4));
+
+        let events = tracker.observe(&[], at(6));
+        assert_eq!(events.len(), 1);
+        let session = &events[0].session;
+        assert_eq!(events[0].kind, ProcessEventKind::End);
+        assert_eq!(session.duration_secs, 4);
+        assert_eq!(session.end_time, Some(at(4)));
+        assert_eq!(session.peak_gpu_memory_mb, 3000);
+        assert_eq!(session.avg_gpu_utilization, 80.0);
+        assert!(tracker.active_sessions().is_empty());
+    }
+
+    #[test]
+    fn test_reused_pid_and_long_gap() {
+        let mut tracker = ProcessTracker::new();
+
+        tracker.observe(&[process(42, "python3", 1000, 50)], at(0));
+
+        // Suspend/resume: the gap is not counted as GPU time
+        let events = tracker.observe(&[process(42, "python3", 1000, 50)], at(3600));
+        assert_eq!(events[0].elapsed_secs, 0);
+
+        let events = tracker.observe(&[process(42, "game.exe", 4000, 95)], at(3602));
+        let kinds: Vec<_> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![ProcessEventKind::End, ProcessEventKind::Start]);
+        assert_ne!(events[0].session.id, events[1].session.id);
+    }
+}
@@ -1,7 +1,7 @@
 use gpm_core::{
     config::GpmConfig,
     gpu::{GpuMonitorBackend, GpuMetrics},
-    storage::Database,
+    storage::{Database, HistoryQuery},
     GpmError,
 };
 use serde::{Deserialize, Serialize};
@@ -14,6 +14,7 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilte
 /// Dashboard state shared across Tauri commands
 pub struct DashboardState {
     db: Arc<Database>,
+    history: Arc<HistoryQuery>,
     gpu_monitor: Arc<Mutex<Option<GpuMonitorBackend>>>,
     config_path: PathBuf,
 }
@@ -26,13 +27,17 @@ impl DashboardState {
         let config_path = config.config_path();
 
         // Initialize database connection
-        let db = Database::new(&db_path).await?;
+        let db = Arc::new(Database::new(&db_path).await?);
+
+        // Historical queries span SQLite and the Parquet archive
+        let history = HistoryQuery::new(Arc::clone(&db), &config.storage.archive_dir)?;
 
         // Initialize GPU monitor for real-time metrics
         let gpu_monitor = GpuMonitorBackend::initialize(&config).ok();
 
         Ok(Self {
-            db: Arc::new(db),
+            db,
+            history: Arc::new(history),
             gpu_monitor: Arc::new(Mutex::new(gpu_monitor)),
             config_path,
         })
@@ -154,15 +159,15 @@ async fn get_realtime_metrics(
     }
 }
 
-/// Get historical GPU metrics from database
+/// Get historical GPU metrics from the database and Parquet archive
 #[tauri::command]
 async fn get_historical_metrics(
     state: State<'_, DashboardState>,
     hours: i64,
 ) -> Result<Vec<GpuMetricData>, ErrorResponse> {
     let metrics = state
-        .db
-        .get_recent_gpu_metrics(hours)
+        .history
+        .recent_gpu_metrics(hours)
         .await
         .map_err(|e| ErrorResponse {
             error: format!("Failed to get metrics: {}", e),
@@ -193,8 +198,8 @@ async fn get_llm_sessions(
         .with_timezone(&chrono::Utc);
 
     let sessions = state
-        .db
-        .get_llm_sessions(start, end)
+        .history
+        .llm_sessions(start, end)
         .await
         .map_err(|e| ErrorResponse {
             error: format!("Failed to get LLM sessions: {}", e),
@@ -225,8 +230,8 @@ async fn get_chart_data(
     hours: i64,
 ) -> Result<ChartDataResponse, ErrorResponse> {
     let metrics = state
-        .db
-        .get_recent_gpu_metrics(hours)
+        .history
+        .recent_gpu_metrics(hours)
         .await
         .map_err(|e| ErrorResponse {
             error: format!("Failed to get metrics: {}", e),